@@ -17,7 +17,7 @@ use std::fmt;
 use std::marker::Unpin;
 use thiserror::Error;
 use tiny_keccak::keccak256;
-use web3::types::{Address, Log, H256};
+use web3::types::{Address, Log, H256, U256};
 
 use graph::prelude::*;
 use graph::{
@@ -46,6 +46,43 @@ pub struct EthereumContractCall {
     pub args: Vec<Token>,
 }
 
+/// Identifying information an Ethereum node reports about the network it
+/// is connected to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EthereumNetworkIdentifier {
+    /// The chain id as reported by `eth_chainId`
+    pub chain_id: u64,
+    /// The network id as reported by `net_version`
+    pub net_version: String,
+}
+
+/// What an Ethereum node reports about its own progress, so that we can
+/// tell whether it is reachable and synced before relying on it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EthereumHealth {
+    /// The network id as reported by `net_version`
+    pub net_version: String,
+    /// The number of the latest block as reported by `eth_blockNumber`
+    pub latest_block: u64,
+    /// The highest block the node knows of while it is still syncing, as
+    /// reported by `eth_syncing`; `None` once the node is synced
+    pub highest_block: Option<u64>,
+}
+
+impl EthereumHealth {
+    pub fn is_syncing(&self) -> bool {
+        self.highest_block.is_some()
+    }
+
+    /// How many blocks the node's latest block is behind the highest block
+    /// it knows of
+    pub fn blocks_behind(&self) -> u64 {
+        self.highest_block
+            .map(|highest| highest.saturating_sub(self.latest_block))
+            .unwrap_or(0)
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum EthereumContractCallError {
     #[error("ABI error: {0}")]
@@ -61,6 +98,8 @@ pub enum EthereumContractCallError {
     Revert(String),
     #[error("ethereum node took too long to perform call")]
     Timeout,
+    #[error("ethereum node does not support calls by block hash (EIP-1898)")]
+    BlockHashNotSupported,
 }
 
 impl From<ABIError> for EthereumContractCallError {
@@ -201,7 +240,7 @@ impl bc::TriggerFilter<Chain> for TriggerFilter {
 }
 
 #[derive(Clone, Debug, Default)]
-pub(crate) struct EthereumLogFilter {
+pub struct EthereumLogFilter {
     /// Log filters can be represented as a bipartite graph between contracts and events. An edge
     /// exists between a contract and an event if a data source for the contract has a trigger for
     /// the event.
@@ -310,6 +349,18 @@ impl EthereumLogFilter {
         this
     }
 
+    /// A filter that matches events with any of `signatures`, regardless
+    /// of the contract that emitted them
+    #[cfg(test)]
+    pub(crate) fn from_event_signatures(
+        signatures: impl IntoIterator<Item = EventSignature>,
+    ) -> Self {
+        EthereumLogFilter {
+            contracts_and_events_graph: GraphMap::new(),
+            wildcard_events: signatures.into_iter().map(|sig| (sig, false)).collect(),
+        }
+    }
+
     /// Extends this log filter with another one.
     pub fn extend(&mut self, other: EthereumLogFilter) {
         if other.is_empty() {
@@ -761,6 +812,10 @@ pub struct ProviderEthRpcMetrics {
     request_duration: Box<HistogramVec>,
     errors: Box<CounterVec>,
     status: Box<GaugeVec>,
+    reconnects: Box<CounterVec>,
+    call_memo_hits: Box<CounterVec>,
+    call_memo_misses: Box<CounterVec>,
+    throttled: Box<CounterVec>,
 }
 
 impl ProviderEthRpcMetrics {
@@ -788,10 +843,42 @@ impl ProviderEthRpcMetrics {
                 vec![String::from("provider")],
             )
             .unwrap();
+        let reconnects = registry
+            .new_counter_vec(
+                "ethereum_rpc_reconnects_total",
+                "Counts how often the connection to the Ethereum node was reestablished",
+                vec![String::from("provider")],
+            )
+            .unwrap();
+        let call_memo_hits = registry
+            .new_counter_vec(
+                "ethereum_call_memo_hits_total",
+                "Counts contract calls answered from the in-memory call memo",
+                vec![String::from("provider")],
+            )
+            .unwrap();
+        let call_memo_misses = registry
+            .new_counter_vec(
+                "ethereum_call_memo_misses_total",
+                "Counts contract calls that the in-memory call memo could not answer",
+                vec![String::from("provider")],
+            )
+            .unwrap();
+        let throttled = registry
+            .new_counter_vec(
+                "ethereum_rpc_throttled_total",
+                "Counts requests to the Ethereum node that had to wait because of the provider's rate limit",
+                vec![String::from("provider")],
+            )
+            .unwrap();
         Self {
             request_duration,
             errors,
             status,
+            reconnects,
+            call_memo_hits,
+            call_memo_misses,
+            throttled,
         }
     }
 
@@ -810,6 +897,22 @@ impl ProviderEthRpcMetrics {
             .with_label_values(&[provider])
             .set(status.into());
     }
+
+    pub fn add_reconnect(&self, provider: &str) {
+        self.reconnects.with_label_values(&[provider]).inc();
+    }
+
+    pub fn add_call_memo_hit(&self, provider: &str) {
+        self.call_memo_hits.with_label_values(&[provider]).inc();
+    }
+
+    pub fn add_call_memo_miss(&self, provider: &str) {
+        self.call_memo_misses.with_label_values(&[provider]).inc();
+    }
+
+    pub fn add_throttled(&self, provider: &str) {
+        self.throttled.with_label_values(&[provider]).inc();
+    }
 }
 
 #[derive(Clone)]
@@ -911,6 +1014,16 @@ pub trait EthereumAdapter: Send + Sync + 'static {
         block_number: BlockNumber,
     ) -> Box<dyn Future<Item = Option<LightEthereumBlock>, Error = Error> + Send>;
 
+    /// Find the blocks with the given numbers, requesting up to
+    /// `ETHEREUM_BLOCK_BATCH_SIZE` of them in one JSON-RPC batch. The
+    /// blocks are returned in ascending order; blocks the Ethereum node
+    /// does not have are left out.
+    fn blocks_by_numbers(
+        &self,
+        logger: &Logger,
+        block_numbers: Vec<BlockNumber>,
+    ) -> Box<dyn Future<Item = Vec<LightEthereumBlock>, Error = Error> + Send>;
+
     /// Load full information for the specified `block` (in particular, transaction receipts).
     fn load_full_block(
         &self,
@@ -947,6 +1060,15 @@ pub trait EthereumAdapter: Send + Sync + 'static {
         call: EthereumContractCall,
         cache: Arc<dyn EthereumCallCache>,
     ) -> Box<dyn Future<Item = Vec<Token>, Error = EthereumContractCallError> + Send>;
+
+    /// Estimate the gas needed to execute the function call at the call's
+    /// block with `eth_estimateGas`. If execution reverts, the error is an
+    /// `EthereumContractCallError::Revert` with the revert reason.
+    fn estimate_gas(
+        &self,
+        logger: &Logger,
+        call: EthereumContractCall,
+    ) -> Box<dyn Future<Item = U256, Error = EthereumContractCallError> + Send>;
 }
 
 #[cfg(test)]
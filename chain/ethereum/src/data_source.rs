@@ -198,6 +198,8 @@ impl blockchain::DataSource<Chain> for DataSource {
             errors.push(anyhow!("data source has duplicated block handlers"));
         }
 
+        errors.extend(self.mapping.validate_handlers(&self.contract_abi));
+
         // Validate that event handlers don't require receipts for API versions lower than 0.0.7
         let api_version = self.api_version();
         if api_version < semver::Version::new(0, 0, 7) {
@@ -308,134 +310,6 @@ impl DataSource {
         }
     }
 
-    /// Returns the contract event with the given signature, if it exists. A an event from the ABI
-    /// will be matched if:
-    /// 1. An event signature is equal to `signature`.
-    /// 2. There are no equal matches, but there is exactly one event that equals `signature` if all
-    ///    `indexed` modifiers are removed from the parameters.
-    fn contract_event_with_signature(&self, signature: &str) -> Option<&Event> {
-        // Returns an `Event(uint256,address)` signature for an event, without `indexed` hints.
-        fn ambiguous_event_signature(event: &Event) -> String {
-            format!(
-                "{}({})",
-                event.name,
-                event
-                    .inputs
-                    .iter()
-                    .map(|input| format!("{}", event_param_type_signature(&input.kind)))
-                    .collect::<Vec<_>>()
-                    .join(",")
-            )
-        }
-
-        // Returns an `Event(indexed uint256,address)` type signature for an event.
-        fn event_signature(event: &Event) -> String {
-            format!(
-                "{}({})",
-                event.name,
-                event
-                    .inputs
-                    .iter()
-                    .map(|input| format!(
-                        "{}{}",
-                        if input.indexed { "indexed " } else { "" },
-                        event_param_type_signature(&input.kind)
-                    ))
-                    .collect::<Vec<_>>()
-                    .join(",")
-            )
-        }
-
-        // Returns the signature of an event parameter type (e.g. `uint256`).
-        fn event_param_type_signature(kind: &ParamType) -> String {
-            use ParamType::*;
-
-            match kind {
-                Address => "address".into(),
-                Bytes => "bytes".into(),
-                Int(size) => format!("int{}", size),
-                Uint(size) => format!("uint{}", size),
-                Bool => "bool".into(),
-                String => "string".into(),
-                Array(inner) => format!("{}[]", event_param_type_signature(&*inner)),
-                FixedBytes(size) => format!("bytes{}", size),
-                FixedArray(inner, size) => {
-                    format!("{}[{}]", event_param_type_signature(&*inner), size)
-                }
-                Tuple(components) => format!(
-                    "({})",
-                    components
-                        .iter()
-                        .map(|component| event_param_type_signature(&component))
-                        .collect::<Vec<_>>()
-                        .join(",")
-                ),
-            }
-        }
-
-        self.contract_abi
-            .contract
-            .events()
-            .find(|event| event_signature(event) == signature)
-            .or_else(|| {
-                // Fallback for subgraphs that don't use `indexed` in event signatures yet:
-                //
-                // If there is only one event variant with this name and if its signature
-                // without `indexed` matches the event signature from the manifest, we
-                // can safely assume that the event is a match, we don't need to force
-                // the subgraph to add `indexed`.
-
-                // Extract the event name; if there is no '(' in the signature,
-                // `event_name` will be empty and not match any events, so that's ok
-                let parens = signature.find('(').unwrap_or(0);
-                let event_name = &signature[0..parens];
-
-                let matching_events = self
-                    .contract_abi
-                    .contract
-                    .events()
-                    .filter(|event| event.name == event_name)
-                    .collect::<Vec<_>>();
-
-                // Only match the event signature without `indexed` if there is
-                // only a single event variant
-                if matching_events.len() == 1
-                    && ambiguous_event_signature(matching_events[0]) == signature
-                {
-                    Some(matching_events[0])
-                } else {
-                    // More than one event variant or the signature
-                    // still doesn't match, even if we ignore `indexed` hints
-                    None
-                }
-            })
-    }
-
-    fn contract_function_with_signature(&self, target_signature: &str) -> Option<&Function> {
-        self.contract_abi
-            .contract
-            .functions()
-            .filter(|function| match function.state_mutability {
-                StateMutability::Payable | StateMutability::NonPayable => true,
-                StateMutability::Pure | StateMutability::View => false,
-            })
-            .find(|function| {
-                // Construct the argument function signature:
-                // `address,uint256,bool`
-                let mut arguments = function
-                    .inputs
-                    .iter()
-                    .map(|input| format!("{}", input.kind))
-                    .collect::<Vec<String>>()
-                    .join(",");
-                // `address,uint256,bool)
-                arguments.push_str(")");
-                // `operation(address,uint256,bool)`
-                let actual_signature = vec![function.name.clone(), arguments].join("(");
-                target_signature == actual_signature
-            })
-    }
-
     fn matches_trigger_address(&self, trigger: &EthereumTrigger) -> bool {
         let ds_address = match self.source.address {
             Some(addr) => addr,
@@ -495,7 +369,8 @@ impl DataSource {
                     .map(|event_handler| {
                         // Identify the event ABI in the contract
                         let event_abi = self
-                            .contract_event_with_signature(event_handler.event.as_str())
+                            .contract_abi
+                            .event_with_signature(event_handler.event.as_str())
                             .with_context(|| {
                                 anyhow!(
                                     "Event with the signature \"{}\" not found in \
@@ -601,7 +476,8 @@ impl DataSource {
 
                 // Identify the function ABI in the contract
                 let function_abi = self
-                    .contract_function_with_signature(handler.function.as_str())
+                    .contract_abi
+                    .function_with_signature(handler.function.as_str())
                     .with_context(|| {
                         anyhow!(
                             "Function with the signature \"{}\" not found in \
@@ -833,6 +709,10 @@ impl blockchain::DataSourceTemplate<Chain> for DataSourceTemplate {
         &self.name
     }
 
+    fn kind(&self) -> &str {
+        &self.kind
+    }
+
     fn api_version(&self) -> semver::Version {
         self.mapping.api_version.clone()
     }
@@ -840,6 +720,13 @@ impl blockchain::DataSourceTemplate<Chain> for DataSourceTemplate {
     fn runtime(&self) -> &[u8] {
         self.mapping.runtime.as_ref()
     }
+
+    fn validate(&self) -> Vec<Error> {
+        match self.mapping.find_abi(&self.source.abi) {
+            Ok(contract_abi) => self.mapping.validate_handlers(&contract_abi),
+            Err(e) => vec![e],
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Deserialize)]
@@ -878,6 +765,40 @@ impl Mapping {
         calls_host_fn(&self.runtime, "ethereum.call")
     }
 
+    /// Validate that all event and call handlers refer to events and
+    /// functions that exist in `contract_abi`; otherwise, triggers for them
+    /// could never be decoded
+    fn validate_handlers(&self, contract_abi: &MappingABI) -> Vec<Error> {
+        let mut errors = vec![];
+        for event_handler in &self.event_handlers {
+            if contract_abi
+                .event_with_signature(&event_handler.event)
+                .is_none()
+            {
+                errors.push(anyhow!(
+                    "data source has an event handler for `{}`, but the event is not \
+                     declared in the contract ABI `{}`",
+                    event_handler.event,
+                    contract_abi.name
+                ));
+            }
+        }
+        for call_handler in &self.call_handlers {
+            if contract_abi
+                .function_with_signature(&call_handler.function)
+                .is_none()
+            {
+                errors.push(anyhow!(
+                    "data source has a call handler for `{}`, but the function is not \
+                     declared in the contract ABI `{}` or is not payable or non-payable",
+                    call_handler.function,
+                    contract_abi.name
+                ));
+            }
+        }
+        errors
+    }
+
     pub fn has_call_handler(&self) -> bool {
         !self.call_handlers.is_empty()
     }
@@ -964,6 +885,133 @@ pub struct MappingABI {
     pub contract: Contract,
 }
 
+impl MappingABI {
+    /// Returns the contract event with the given signature, if it exists. A an event from the ABI
+    /// will be matched if:
+    /// 1. An event signature is equal to `signature`.
+    /// 2. There are no equal matches, but there is exactly one event that equals `signature` if all
+    ///    `indexed` modifiers are removed from the parameters.
+    fn event_with_signature(&self, signature: &str) -> Option<&Event> {
+        // Returns an `Event(uint256,address)` signature for an event, without `indexed` hints.
+        fn ambiguous_event_signature(event: &Event) -> String {
+            format!(
+                "{}({})",
+                event.name,
+                event
+                    .inputs
+                    .iter()
+                    .map(|input| format!("{}", event_param_type_signature(&input.kind)))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )
+        }
+
+        // Returns an `Event(indexed uint256,address)` type signature for an event.
+        fn event_signature(event: &Event) -> String {
+            format!(
+                "{}({})",
+                event.name,
+                event
+                    .inputs
+                    .iter()
+                    .map(|input| format!(
+                        "{}{}",
+                        if input.indexed { "indexed " } else { "" },
+                        event_param_type_signature(&input.kind)
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )
+        }
+
+        // Returns the signature of an event parameter type (e.g. `uint256`).
+        fn event_param_type_signature(kind: &ParamType) -> String {
+            use ParamType::*;
+
+            match kind {
+                Address => "address".into(),
+                Bytes => "bytes".into(),
+                Int(size) => format!("int{}", size),
+                Uint(size) => format!("uint{}", size),
+                Bool => "bool".into(),
+                String => "string".into(),
+                Array(inner) => format!("{}[]", event_param_type_signature(&*inner)),
+                FixedBytes(size) => format!("bytes{}", size),
+                FixedArray(inner, size) => {
+                    format!("{}[{}]", event_param_type_signature(&*inner), size)
+                }
+                Tuple(components) => format!(
+                    "({})",
+                    components
+                        .iter()
+                        .map(|component| event_param_type_signature(&component))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                ),
+            }
+        }
+
+        self.contract
+            .events()
+            .find(|event| event_signature(event) == signature)
+            .or_else(|| {
+                // Fallback for subgraphs that don't use `indexed` in event signatures yet:
+                //
+                // If there is only one event variant with this name and if its signature
+                // without `indexed` matches the event signature from the manifest, we
+                // can safely assume that the event is a match, we don't need to force
+                // the subgraph to add `indexed`.
+
+                // Extract the event name; if there is no '(' in the signature,
+                // `event_name` will be empty and not match any events, so that's ok
+                let parens = signature.find('(').unwrap_or(0);
+                let event_name = &signature[0..parens];
+
+                let matching_events = self
+                    .contract
+                    .events()
+                    .filter(|event| event.name == event_name)
+                    .collect::<Vec<_>>();
+
+                // Only match the event signature without `indexed` if there is
+                // only a single event variant
+                if matching_events.len() == 1
+                    && ambiguous_event_signature(matching_events[0]) == signature
+                {
+                    Some(matching_events[0])
+                } else {
+                    // More than one event variant or the signature
+                    // still doesn't match, even if we ignore `indexed` hints
+                    None
+                }
+            })
+    }
+
+    fn function_with_signature(&self, target_signature: &str) -> Option<&Function> {
+        self.contract
+            .functions()
+            .filter(|function| match function.state_mutability {
+                StateMutability::Payable | StateMutability::NonPayable => true,
+                StateMutability::Pure | StateMutability::View => false,
+            })
+            .find(|function| {
+                // Construct the argument function signature:
+                // `address,uint256,bool`
+                let mut arguments = function
+                    .inputs
+                    .iter()
+                    .map(|input| format!("{}", input.kind))
+                    .collect::<Vec<String>>()
+                    .join(",");
+                // `address,uint256,bool)
+                arguments.push_str(")");
+                // `operation(address,uint256,bool)`
+                let actual_signature = vec![function.name.clone(), arguments].join("(");
+                target_signature == actual_signature
+            })
+    }
+}
+
 impl UnresolvedMappingABI {
     pub async fn resolve(
         self,
@@ -83,6 +83,17 @@ pub struct EnvVars {
     /// `GRAPH_ETHEREUM_TARGET_TRIGGERS_PER_BLOCK_RANGE`. The default value is
     /// 100.
     pub target_triggers_per_block_range: u64,
+    /// Reestablish IPC and WebSocket connections to Ethereum nodes when they
+    /// get dropped instead of failing every request made over them.
+    ///
+    /// Set by the flag `GRAPH_ETHEREUM_RECONNECT_TRANSPORT`. Off by default.
+    pub reconnect_transport: bool,
+    /// Memoize the results of `eth_call`s in memory for the blocks that
+    /// are currently being processed so that identical calls within a
+    /// block do not go to the call cache or the Ethereum node again.
+    ///
+    /// Set by the flag `GRAPH_ETHEREUM_MEMOIZE_CALLS`. On by default.
+    pub memoize_calls: bool,
 }
 
 // This does not print any values avoid accidentally leaking any sensitive env vars
@@ -124,6 +135,8 @@ impl From<Inner> for EnvVars {
                 .unwrap_or(cfg!(target_os = "macos")),
             cleanup_blocks: x.cleanup_blocks.0,
             target_triggers_per_block_range: x.target_triggers_per_block_range,
+            reconnect_transport: x.reconnect_transport.0,
+            memoize_calls: x.memoize_calls.0,
         }
     }
 }
@@ -172,4 +185,8 @@ struct Inner {
         default = "100"
     )]
     target_triggers_per_block_range: u64,
+    #[envconfig(from = "GRAPH_ETHEREUM_RECONNECT_TRANSPORT", default = "false")]
+    reconnect_transport: EnvVarBoolean,
+    #[envconfig(from = "GRAPH_ETHEREUM_MEMOIZE_CALLS", default = "true")]
+    memoize_calls: EnvVarBoolean,
 }
@@ -21,7 +21,7 @@ use graph::{
             self,
             types::{
                 Address, BlockId, BlockNumber as Web3BlockNumber, Bytes, CallRequest, Filter,
-                FilterBuilder, Log, Transaction, TransactionReceipt, H256,
+                FilterBuilder, Log, SyncState, Transaction, TransactionReceipt, H256, U256,
             },
         },
         BlockNumber, ChainStore, CheapClone, DynTryFuture, Error, EthereumCallCache, Logger,
@@ -35,11 +35,11 @@ use graph::{
     prelude::web3::types::{Trace, TraceFilter, TraceFilterBuilder, H160},
 };
 use itertools::Itertools;
-use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::convert::TryFrom;
 use std::iter::FromIterator;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 use crate::adapter::ProviderStatus;
@@ -47,10 +47,10 @@ use crate::chain::BlockFinality;
 use crate::{
     adapter::{
         EthGetLogsFilter, EthereumAdapter as EthereumAdapterTrait, EthereumBlockFilter,
-        EthereumCallFilter, EthereumContractCall, EthereumContractCallError, EthereumLogFilter,
-        ProviderEthRpcMetrics, SubgraphEthRpcMetrics,
+        EthereumCallFilter, EthereumContractCall, EthereumContractCallError, EthereumHealth,
+        EthereumLogFilter, EthereumNetworkIdentifier, ProviderEthRpcMetrics, SubgraphEthRpcMetrics,
     },
-    transport::Transport,
+    transport::{RateLimit, Transport},
     trigger::{EthereumBlockTriggerType, EthereumTrigger},
     TriggerFilter, ENV_VARS,
 };
@@ -64,6 +64,50 @@ pub struct EthereumAdapter {
     web3: Arc<Web3<Transport>>,
     metrics: Arc<ProviderEthRpcMetrics>,
     supports_eip_1898: bool,
+    call_memo: Arc<Mutex<CallMemo>>,
+}
+
+/// The number of blocks for which `CallMemo` keeps results
+const CALL_MEMO_BLOCKS: usize = 16;
+
+/// Results of `eth_call`s for the blocks that are currently being
+/// processed. Mappings often make the same call, e.g. `decimals()`, many
+/// times while processing one block; this lets us answer repeated calls
+/// from memory. Since all subgraphs on a provider share the memo and
+/// process different blocks, it keeps the results for the
+/// `CALL_MEMO_BLOCKS` most recently used blocks
+#[derive(Default)]
+struct CallMemo {
+    /// Results by block, the most recently used block last
+    blocks: VecDeque<(BlockPtr, HashMap<(Address, Vec<u8>), Vec<u8>>)>,
+}
+
+impl CallMemo {
+    /// Move the results for `block` to the back and return them
+    fn touch(&mut self, block: &BlockPtr) -> Option<&mut HashMap<(Address, Vec<u8>), Vec<u8>>> {
+        let pos = self.blocks.iter().position(|(ptr, _)| ptr == block)?;
+        let entry = self.blocks.remove(pos)?;
+        self.blocks.push_back(entry);
+        self.blocks.back_mut().map(|(_, results)| results)
+    }
+
+    fn get(&mut self, address: Address, call_data: &[u8], block: &BlockPtr) -> Option<Vec<u8>> {
+        self.touch(block)?
+            .get(&(address, call_data.to_vec()))
+            .cloned()
+    }
+
+    fn set(&mut self, address: Address, call_data: Vec<u8>, block: &BlockPtr, result: Vec<u8>) {
+        if self.touch(block).is_none() {
+            if self.blocks.len() >= CALL_MEMO_BLOCKS {
+                self.blocks.pop_front();
+            }
+            self.blocks.push_back((block.clone(), HashMap::new()));
+        }
+        if let Some((_, results)) = self.blocks.back_mut() {
+            results.insert((address, call_data), result);
+        }
+    }
 }
 
 /// Gas limit for `eth_call`. The value of 50_000_000 is a protocol-wide parameter so this
@@ -85,6 +129,7 @@ impl CheapClone for EthereumAdapter {
             web3: self.web3.cheap_clone(),
             metrics: self.metrics.cheap_clone(),
             supports_eip_1898: self.supports_eip_1898,
+            call_memo: self.call_memo.cheap_clone(),
         }
     }
 }
@@ -97,6 +142,7 @@ impl EthereumAdapter {
         transport: Transport,
         provider_metrics: Arc<ProviderEthRpcMetrics>,
         supports_eip_1898: bool,
+        rate_limit: Option<RateLimit>,
     ) -> Self {
         // Unwrap: The transport was constructed with this url, so it is valid and has a host.
         let hostname = graph::url::Url::parse(url)
@@ -105,6 +151,15 @@ impl EthereumAdapter {
             .unwrap()
             .to_string();
 
+        let transport = if ENV_VARS.reconnect_transport {
+            transport.reconnecting(&logger, url, &provider, provider_metrics.cheap_clone())
+        } else {
+            transport
+        };
+        let transport = match rate_limit {
+            Some(limit) => transport.rate_limited(limit, &provider, provider_metrics.cheap_clone()),
+            None => transport,
+        };
         let web3 = Arc::new(Web3::new(transport));
 
         // Use the client version to check if it is ganache. For compatibility with unit tests, be
@@ -123,6 +178,7 @@ impl EthereumAdapter {
             web3,
             metrics: provider_metrics,
             supports_eip_1898: supports_eip_1898 && !is_ganache,
+            call_memo: Arc::new(Mutex::new(CallMemo::default())),
         }
     }
 
@@ -140,6 +196,7 @@ impl EthereumAdapter {
         retry(retry_log_message, &logger)
             .limit(ENV_VARS.request_retries)
             .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+            .before_attempt(wait_for_rate_limit(&self.web3, 1))
             .run(move || {
                 let trace_filter: TraceFilter = match addresses.len() {
                     0 => TraceFilterBuilder::default()
@@ -237,6 +294,7 @@ impl EthereumAdapter {
             })
             .limit(ENV_VARS.request_retries)
             .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+            .before_attempt(wait_for_rate_limit(&self.web3, 1))
             .run(move || {
                 let eth_adapter = eth_adapter.cheap_clone();
                 let subgraph_metrics = subgraph_metrics.clone();
@@ -419,8 +477,6 @@ impl EthereumAdapter {
         call_data: Bytes,
         block_ptr: BlockPtr,
     ) -> impl Future<Item = Bytes, Error = EthereumContractCallError> + Send {
-        let web3 = self.web3.clone();
-
         // Ganache does not support calls by block hash.
         // See https://github.com/trufflesuite/ganache-cli/issues/973
         let block_id = if !self.supports_eip_1898 {
@@ -429,6 +485,100 @@ impl EthereumAdapter {
             BlockId::Hash(block_ptr.hash_as_h256())
         };
         let retry_log_message = format!("eth_call RPC call for block {}", block_ptr);
+        self.call_at_block_id(
+            logger,
+            contract_address,
+            call_data,
+            block_id,
+            retry_log_message,
+        )
+    }
+
+    /// Perform an `eth_call` against the block with hash `block_hash`. Unlike
+    /// `call`, this never falls back to calling by block number, so that the
+    /// call can not accidentally read state from a different block at the
+    /// same height. Fails with `EthereumContractCallError::BlockHashNotSupported`
+    /// if the Ethereum node does not support calls by block hash (EIP-1898)
+    pub fn call_at_hash(
+        &self,
+        logger: &Logger,
+        contract_address: Address,
+        call_data: Bytes,
+        block_hash: H256,
+    ) -> Box<dyn Future<Item = Bytes, Error = EthereumContractCallError> + Send> {
+        self.call_at_block(
+            logger,
+            contract_address,
+            call_data,
+            BlockId::Hash(block_hash),
+        )
+    }
+
+    /// Perform an `eth_call` against an arbitrary block. Besides specific
+    /// blocks, `block` can use the tags `latest`, `earliest` and `pending`,
+    /// e.g. `BlockId::Number(BlockNumber::Pending)` to read state that
+    /// includes transactions that have not been mined yet. Calls by hash
+    /// fail with `EthereumContractCallError::BlockHashNotSupported` if the
+    /// Ethereum node does not support them (EIP-1898)
+    pub fn call_at_block(
+        &self,
+        logger: &Logger,
+        contract_address: Address,
+        call_data: Bytes,
+        block: BlockId,
+    ) -> Box<dyn Future<Item = Bytes, Error = EthereumContractCallError> + Send> {
+        let retry_log_message = match block {
+            BlockId::Hash(hash) => {
+                if !self.supports_eip_1898 {
+                    return Box::new(future::err(
+                        EthereumContractCallError::BlockHashNotSupported,
+                    ));
+                }
+                format!("eth_call RPC call for block hash {:x}", hash)
+            }
+            BlockId::Number(Web3BlockNumber::Number(number)) => {
+                format!("eth_call RPC call for block number {}", number)
+            }
+            BlockId::Number(Web3BlockNumber::Latest) => {
+                "eth_call RPC call for latest block".to_string()
+            }
+            BlockId::Number(Web3BlockNumber::Earliest) => {
+                "eth_call RPC call for earliest block".to_string()
+            }
+            BlockId::Number(Web3BlockNumber::Pending) => {
+                "eth_call RPC call for pending block".to_string()
+            }
+        };
+
+        Box::new(self.call_at_block_id(
+            logger.clone(),
+            contract_address,
+            call_data,
+            block,
+            retry_log_message,
+        ))
+    }
+
+    /// Remember the result of `call` for the rest of its block
+    fn memoize_call(&self, call: &EthereumContractCall, call_data: Vec<u8>, result: Vec<u8>) {
+        if ENV_VARS.memoize_calls {
+            self.call_memo
+                .lock()
+                .unwrap()
+                .set(call.address, call_data, &call.block_ptr, result);
+        }
+    }
+
+    fn call_at_block_id(
+        &self,
+        logger: Logger,
+        contract_address: Address,
+        call_data: Bytes,
+        block_id: BlockId,
+        retry_log_message: String,
+    ) -> impl Future<Item = Bytes, Error = EthereumContractCallError> + Send {
+        let web3 = self.web3.clone();
+
         retry(retry_log_message, &logger)
             .when(|result| match result {
                 Ok(_) | Err(EthereumContractCallError::Revert(_)) => false,
@@ -436,6 +586,7 @@ impl EthereumAdapter {
             })
             .limit(ENV_VARS.request_retries)
             .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+            .before_attempt(wait_for_rate_limit(&web3, 1))
             .run(move || {
                 let call_data = call_data.clone();
                 let web3 = web3.cheap_clone();
@@ -453,113 +604,11 @@ impl EthereumAdapter {
                         max_priority_fee_per_gas: None,
                         transaction_type: None,
                     };
-                    let result = web3.eth().call(req, Some(block_id)).boxed().await;
-
-                    // Try to check if the call was reverted. The JSON-RPC response for reverts is
-                    // not standardized, so we have ad-hoc checks for each Ethereum client.
-
-                    // 0xfe is the "designated bad instruction" of the EVM, and Solidity uses it for
-                    // asserts.
-                    const PARITY_BAD_INSTRUCTION_FE: &str = "Bad instruction fe";
-
-                    // 0xfd is REVERT, but on some contracts, and only on older blocks,
-                    // this happens. Makes sense to consider it a revert as well.
-                    const PARITY_BAD_INSTRUCTION_FD: &str = "Bad instruction fd";
-
-                    const PARITY_BAD_JUMP_PREFIX: &str = "Bad jump";
-                    const PARITY_STACK_LIMIT_PREFIX: &str = "Out of stack";
-
-                    // See f0af4ab0-6b7c-4b68-9141-5b79346a5f61.
-                    const PARITY_OUT_OF_GAS: &str = "Out of gas";
-
-                    const PARITY_VM_EXECUTION_ERROR: i64 = -32015;
-                    const PARITY_REVERT_PREFIX: &str = "Reverted 0x";
-
-                    // Deterministic Geth execution errors. We might need to expand this as
-                    // subgraphs come across other errors. See
-                    // https://github.com/ethereum/go-ethereum/blob/cd57d5cd38ef692de8fbedaa56598b4e9fbfbabc/core/vm/errors.go
-                    const GETH_EXECUTION_ERRORS: &[&str] = &[
-                        // The "revert" substring covers a few known error messages, including:
-                        // Hardhat: "error: transaction reverted",
-                        // Ganache and Moonbeam: "vm exception while processing transaction: revert",
-                        // Geth: "execution reverted"
-                        // And others.
-                        "revert",
-                        "invalid jump destination",
-                        "invalid opcode",
-                        // Ethereum says 1024 is the stack sizes limit, so this is deterministic.
-                        "stack limit reached 1024",
-                        // See f0af4ab0-6b7c-4b68-9141-5b79346a5f61 for why the gas limit is considered deterministic.
-                        "out of gas",
-                    ];
-
-                    let env_geth_call_errors = ENV_VARS.geth_eth_call_errors.iter();
-                    let mut geth_execution_errors = GETH_EXECUTION_ERRORS
-                        .iter()
-                        .map(|s| *s)
-                        .chain(env_geth_call_errors.map(|s| s.as_str()));
-
-                    let as_solidity_revert_with_reason = |bytes: &[u8]| {
-                        let solidity_revert_function_selector =
-                            &tiny_keccak::keccak256(b"Error(string)")[..4];
-
-                        match bytes.len() >= 4 && &bytes[..4] == solidity_revert_function_selector {
-                            false => None,
-                            true => ethabi::decode(&[ParamType::String], &bytes[4..])
-                                .ok()
-                                .and_then(|tokens| tokens[0].clone().into_string()),
-                        }
-                    };
-
-                    match result {
-                        // A successful response.
-                        Ok(bytes) => Ok(bytes),
-
-                        // Check for Geth revert.
-                        Err(web3::Error::Rpc(rpc_error))
-                            if geth_execution_errors
-                                .any(|e| rpc_error.message.to_lowercase().contains(e)) =>
-                        {
-                            Err(EthereumContractCallError::Revert(rpc_error.message))
-                        }
-
-                        // Check for Parity revert.
-                        Err(web3::Error::Rpc(ref rpc_error))
-                            if rpc_error.code.code() == PARITY_VM_EXECUTION_ERROR =>
-                        {
-                            match rpc_error.data.as_ref().and_then(|d| d.as_str()) {
-                                Some(data)
-                                    if data.starts_with(PARITY_REVERT_PREFIX)
-                                        || data.starts_with(PARITY_BAD_JUMP_PREFIX)
-                                        || data.starts_with(PARITY_STACK_LIMIT_PREFIX)
-                                        || data == PARITY_BAD_INSTRUCTION_FE
-                                        || data == PARITY_BAD_INSTRUCTION_FD
-                                        || data == PARITY_OUT_OF_GAS =>
-                                {
-                                    let reason = if data == PARITY_BAD_INSTRUCTION_FE {
-                                        PARITY_BAD_INSTRUCTION_FE.to_owned()
-                                    } else {
-                                        let payload = data.trim_start_matches(PARITY_REVERT_PREFIX);
-                                        hex::decode(payload)
-                                            .ok()
-                                            .and_then(|payload| {
-                                                as_solidity_revert_with_reason(&payload)
-                                            })
-                                            .unwrap_or("no reason".to_owned())
-                                    };
-                                    Err(EthereumContractCallError::Revert(reason))
-                                }
-
-                                // The VM execution error was not identified as a revert.
-                                _ => Err(EthereumContractCallError::Web3Error(web3::Error::Rpc(
-                                    rpc_error.clone(),
-                                ))),
-                            }
-                        }
-
-                        // The error was not identified as a revert.
-                        Err(err) => Err(EthereumContractCallError::Web3Error(err)),
-                    }
+                    web3.eth()
+                        .call(req, Some(block_id))
+                        .boxed()
+                        .await
+                        .map_err(call_error)
                 }
             })
             .map_err(|e| e.into_inner().unwrap_or(EthereumContractCallError::Timeout))
@@ -580,6 +629,7 @@ impl EthereumAdapter {
             retry(format!("load block {}", hash), &logger)
                 .limit(ENV_VARS.request_retries)
                 .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+                .before_attempt(wait_for_rate_limit(&web3, 1))
                 .run(move || {
                     Box::pin(web3.eth().block_with_txs(BlockId::Hash(hash)))
                         .compat()
@@ -613,6 +663,7 @@ impl EthereumAdapter {
             retry(format!("load block ptr {}", block_num), &logger)
                 .no_limit()
                 .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+                .before_attempt(wait_for_rate_limit(&web3, 1))
                 .run(move || {
                     let web3 = web3.clone();
                     async move {
@@ -645,7 +696,10 @@ impl EthereumAdapter {
     /// those confirmations.
     /// If the Ethereum node is far behind in processing blocks, even old blocks can be subject to
     /// reorgs.
-    pub(crate) async fn is_on_main_chain(
+    ///
+    /// If the Ethereum node does not have a block at the height of `block_ptr`,
+    /// the block is not considered to be on the main chain.
+    pub async fn is_on_main_chain(
         &self,
         logger: &Logger,
         block_ptr: BlockPtr,
@@ -654,9 +708,15 @@ impl EthereumAdapter {
             .block_hash_by_block_number(&logger, block_ptr.number)
             .compat()
             .await?;
-        block_hash
-            .ok_or_else(|| anyhow!("Ethereum node is missing block #{}", block_ptr.number))
-            .map(|block_hash| block_hash == block_ptr.hash_as_h256())
+        match block_hash {
+            Some(block_hash) => Ok(block_hash == block_ptr.hash_as_h256()),
+            None => {
+                debug!(logger, "Ethereum node is missing block";
+                       "number" => block_ptr.number,
+                       "hash" => block_ptr.hash_hex());
+                Ok(false)
+            }
+        }
     }
 
     pub(crate) fn logs_in_block_range(
@@ -685,6 +745,55 @@ impl EthereumAdapter {
         .boxed()
     }
 
+    /// Find all blocks in `[from, to]` that contain logs matching
+    /// `log_filter` and return pointers to them, in ascending order of block
+    /// number and with each block appearing only once.
+    ///
+    /// To avoid sending one huge `eth_getLogs` request to the Ethereum node,
+    /// the range is scanned in windows of at most
+    /// `GRAPH_ETHEREUM_MAX_BLOCK_RANGE_SIZE` blocks. Each window goes through
+    /// `logs_in_block_range`, which shrinks the range further if the node
+    /// complains that a request is too heavy.
+    pub async fn blocks_with_logs(
+        &self,
+        logger: &Logger,
+        subgraph_metrics: Arc<SubgraphEthRpcMetrics>,
+        from: BlockNumber,
+        to: BlockNumber,
+        log_filter: &EthereumLogFilter,
+    ) -> Result<Vec<BlockPtr>, Error> {
+        let mut blocks: BTreeMap<BlockNumber, H256> = BTreeMap::new();
+        let window = ENV_VARS.max_block_range_size.max(1);
+        let mut start = from;
+        while start <= to {
+            let end = (start + window - 1).min(to);
+            let logs = self
+                .logs_in_block_range(
+                    logger,
+                    subgraph_metrics.cheap_clone(),
+                    start,
+                    end,
+                    log_filter.clone(),
+                )
+                .await?;
+            for log in logs {
+                // Logs from pending blocks have neither a block number nor hash
+                let (number, hash) = match (log.block_number, log.block_hash) {
+                    (Some(number), Some(hash)) => (number, hash),
+                    _ => continue,
+                };
+                let number = BlockNumber::try_from(number.as_u64())?;
+                blocks.entry(number).or_insert(hash);
+            }
+            start = end + 1;
+        }
+
+        Ok(blocks
+            .into_iter()
+            .map(|(number, hash)| BlockPtr::from((hash, number)))
+            .collect())
+    }
+
     pub(crate) fn calls_in_block_range<'a>(
         &self,
         logger: &Logger,
@@ -797,6 +906,7 @@ impl EthereumAdapter {
             retry("chain_id RPC call", &logger)
                 .no_limit()
                 .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+                .before_attempt(wait_for_rate_limit(&web3, 1))
                 .run(move || {
                     let web3 = web3.cheap_clone();
                     async move { web3.eth().chain_id().await }
@@ -805,6 +915,274 @@ impl EthereumAdapter {
         )
         .map_err(Error::msg)
     }
+
+    /// Ask the Ethereum node for its chain id and network version. This
+    /// makes it possible to check that the node is connected to the network
+    /// that a subgraph manifest declares before indexing starts
+    pub async fn network_identifiers(&self) -> Result<EthereumNetworkIdentifier, Error> {
+        let logger = self.logger.clone();
+        let web3 = self.web3.clone();
+        let net_version = retry("net_version RPC call", &logger)
+            .no_limit()
+            .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+            .before_attempt(wait_for_rate_limit(&web3, 1))
+            .run(move || {
+                let web3 = web3.cheap_clone();
+                async move { web3.net().version().await }
+            })
+            .map_err(|e| anyhow!("failed to read network version: {}", e));
+
+        let (chain_id, net_version) = try_join!(self.chain_id(), net_version)?;
+        Ok(EthereumNetworkIdentifier {
+            chain_id,
+            net_version,
+        })
+    }
+
+    /// Ask the Ethereum node whether it is still syncing and how far along it
+    /// is. Unlike most other requests, this gives up after a limited number
+    /// of attempts, since its purpose is to find out whether the node can
+    /// be used at all
+    pub async fn health_check(&self, logger: &Logger) -> Result<EthereumHealth, Error> {
+        let web3 = self.web3.clone();
+        let net_version = retry("net_version RPC call", logger)
+            .limit(ENV_VARS.request_retries)
+            .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+            .before_attempt(wait_for_rate_limit(&web3, 1))
+            .run(move || {
+                let web3 = web3.cheap_clone();
+                async move { web3.net().version().await }
+            })
+            .map_err(|e| anyhow!("failed to read network version: {}", e));
+
+        let web3 = self.web3.clone();
+        let latest_block = retry("eth_blockNumber RPC call", logger)
+            .limit(ENV_VARS.request_retries)
+            .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+            .before_attempt(wait_for_rate_limit(&web3, 1))
+            .run(move || {
+                let web3 = web3.cheap_clone();
+                async move { web3.eth().block_number().await }
+            })
+            .map_err(|e| anyhow!("failed to read latest block number: {}", e));
+
+        let web3 = self.web3.clone();
+        let sync_state = retry("eth_syncing RPC call", logger)
+            .limit(ENV_VARS.request_retries)
+            .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+            .before_attempt(wait_for_rate_limit(&web3, 1))
+            .run(move || {
+                let web3 = web3.cheap_clone();
+                async move { web3.eth().syncing().await }
+            })
+            .map_err(|e| anyhow!("failed to read sync state: {}", e));
+
+        let (net_version, latest_block, sync_state) =
+            try_join!(net_version, latest_block, sync_state)?;
+        let highest_block = match sync_state {
+            SyncState::Syncing(info) => Some(info.highest_block.as_u64()),
+            SyncState::NotSyncing => None,
+        };
+        Ok(EthereumHealth {
+            net_version,
+            latest_block: latest_block.as_u64(),
+            highest_block,
+        })
+    }
+
+    /// Get the balance of `address` as of `block`, or as of the latest block
+    /// if `block` is `None`
+    pub fn get_balance(
+        &self,
+        logger: &Logger,
+        address: Address,
+        block: Option<BlockNumber>,
+    ) -> Box<dyn Future<Item = U256, Error = Error> + Send> {
+        let web3 = self.web3.clone();
+        let retry_log_message = format!("eth_getBalance RPC call for address {:x}", address);
+        Box::new(
+            retry(retry_log_message, &logger)
+                .limit(ENV_VARS.request_retries)
+                .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+                .before_attempt(wait_for_rate_limit(&web3, 1))
+                .run(move || {
+                    let web3 = web3.cheap_clone();
+                    async move {
+                        web3.eth()
+                            .balance(address, block.map(Web3BlockNumber::from))
+                            .await
+                            .map_err(Error::from)
+                    }
+                })
+                .boxed()
+                .compat()
+                .map_err(move |e| {
+                    e.into_inner().unwrap_or_else(move || {
+                        anyhow!(
+                            "Ethereum node took too long to return the balance of {:x}",
+                            address
+                        )
+                    })
+                }),
+        )
+    }
+
+    /// Get the contents of storage `slot` of `address` as of `block`, or as
+    /// of the latest block if `block` is `None`
+    pub fn get_storage_at(
+        &self,
+        logger: &Logger,
+        address: Address,
+        slot: H256,
+        block: Option<BlockNumber>,
+    ) -> Box<dyn Future<Item = H256, Error = Error> + Send> {
+        let web3 = self.web3.clone();
+        let retry_log_message = format!(
+            "eth_getStorageAt RPC call for address {:x} and slot {:x}",
+            address, slot
+        );
+        Box::new(
+            retry(retry_log_message, &logger)
+                .limit(ENV_VARS.request_retries)
+                .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+                .before_attempt(wait_for_rate_limit(&web3, 1))
+                .run(move || {
+                    let web3 = web3.cheap_clone();
+                    async move {
+                        web3.eth()
+                            .storage(
+                                address,
+                                U256::from_big_endian(slot.as_bytes()),
+                                block.map(Web3BlockNumber::from),
+                            )
+                            .await
+                            .map_err(Error::from)
+                    }
+                })
+                .boxed()
+                .compat()
+                .map_err(move |e| {
+                    e.into_inner().unwrap_or_else(move || {
+                        anyhow!(
+                            "Ethereum node took too long to return storage slot {:x} of {:x}",
+                            slot,
+                            address
+                        )
+                    })
+                }),
+        )
+    }
+}
+
+/// Wait until the rate limit of `web3`'s transport allows sending
+/// `requests` more requests. Used with `retry(..).before_attempt(..)` so
+/// that the JSON-RPC timeout does not include time spent throttled
+fn wait_for_rate_limit(
+    web3: &Arc<Web3<Transport>>,
+    requests: usize,
+) -> impl Fn() -> BoxFuture<'static, ()> + Send + Sync + 'static {
+    let transport = web3.transport().clone();
+    move || transport.reserve(requests)
+}
+
+/// Turn an error from `eth_call` or `eth_estimateGas` into an
+/// `EthereumContractCallError`, recognizing the various ways in which
+/// Ethereum clients report that execution reverted
+fn call_error(err: web3::Error) -> EthereumContractCallError {
+    // Try to check if the call was reverted. The JSON-RPC response for reverts is
+    // not standardized, so we have ad-hoc checks for each Ethereum client.
+
+    // 0xfe is the "designated bad instruction" of the EVM, and Solidity uses it for
+    // asserts.
+    const PARITY_BAD_INSTRUCTION_FE: &str = "Bad instruction fe";
+
+    // 0xfd is REVERT, but on some contracts, and only on older blocks,
+    // this happens. Makes sense to consider it a revert as well.
+    const PARITY_BAD_INSTRUCTION_FD: &str = "Bad instruction fd";
+
+    const PARITY_BAD_JUMP_PREFIX: &str = "Bad jump";
+    const PARITY_STACK_LIMIT_PREFIX: &str = "Out of stack";
+
+    // See f0af4ab0-6b7c-4b68-9141-5b79346a5f61.
+    const PARITY_OUT_OF_GAS: &str = "Out of gas";
+
+    const PARITY_VM_EXECUTION_ERROR: i64 = -32015;
+    const PARITY_REVERT_PREFIX: &str = "Reverted 0x";
+
+    // Deterministic Geth execution errors. We might need to expand this as
+    // subgraphs come across other errors. See
+    // https://github.com/ethereum/go-ethereum/blob/cd57d5cd38ef692de8fbedaa56598b4e9fbfbabc/core/vm/errors.go
+    const GETH_EXECUTION_ERRORS: &[&str] = &[
+        // The "revert" substring covers a few known error messages, including:
+        // Hardhat: "error: transaction reverted",
+        // Ganache and Moonbeam: "vm exception while processing transaction: revert",
+        // Geth: "execution reverted"
+        // And others.
+        "revert",
+        "invalid jump destination",
+        "invalid opcode",
+        // Ethereum says 1024 is the stack sizes limit, so this is deterministic.
+        "stack limit reached 1024",
+        // See f0af4ab0-6b7c-4b68-9141-5b79346a5f61 for why the gas limit is considered deterministic.
+        "out of gas",
+    ];
+
+    let env_geth_call_errors = ENV_VARS.geth_eth_call_errors.iter();
+    let mut geth_execution_errors = GETH_EXECUTION_ERRORS
+        .iter()
+        .map(|s| *s)
+        .chain(env_geth_call_errors.map(|s| s.as_str()));
+
+    let as_solidity_revert_with_reason = |bytes: &[u8]| {
+        let solidity_revert_function_selector = &tiny_keccak::keccak256(b"Error(string)")[..4];
+
+        match bytes.len() >= 4 && &bytes[..4] == solidity_revert_function_selector {
+            false => None,
+            true => ethabi::decode(&[ParamType::String], &bytes[4..])
+                .ok()
+                .and_then(|tokens| tokens[0].clone().into_string()),
+        }
+    };
+
+    match err {
+        // Check for Geth revert.
+        web3::Error::Rpc(rpc_error)
+            if geth_execution_errors.any(|e| rpc_error.message.to_lowercase().contains(e)) =>
+        {
+            EthereumContractCallError::Revert(rpc_error.message)
+        }
+
+        // Check for Parity revert.
+        web3::Error::Rpc(ref rpc_error) if rpc_error.code.code() == PARITY_VM_EXECUTION_ERROR => {
+            match rpc_error.data.as_ref().and_then(|d| d.as_str()) {
+                Some(data)
+                    if data.starts_with(PARITY_REVERT_PREFIX)
+                        || data.starts_with(PARITY_BAD_JUMP_PREFIX)
+                        || data.starts_with(PARITY_STACK_LIMIT_PREFIX)
+                        || data == PARITY_BAD_INSTRUCTION_FE
+                        || data == PARITY_BAD_INSTRUCTION_FD
+                        || data == PARITY_OUT_OF_GAS =>
+                {
+                    let reason = if data == PARITY_BAD_INSTRUCTION_FE {
+                        PARITY_BAD_INSTRUCTION_FE.to_owned()
+                    } else {
+                        let payload = data.trim_start_matches(PARITY_REVERT_PREFIX);
+                        hex::decode(payload)
+                            .ok()
+                            .and_then(|payload| as_solidity_revert_with_reason(&payload))
+                            .unwrap_or("no reason".to_owned())
+                    };
+                    EthereumContractCallError::Revert(reason)
+                }
+
+                // The VM execution error was not identified as a revert.
+                _ => EthereumContractCallError::Web3Error(web3::Error::Rpc(rpc_error.clone())),
+            }
+        }
+
+        // The error was not identified as a revert.
+        err => EthereumContractCallError::Web3Error(err),
+    }
 }
 
 #[async_trait]
@@ -900,6 +1278,7 @@ impl EthereumAdapterTrait for EthereumAdapter {
             retry("eth_getBlockByNumber(latest) no txs RPC call", logger)
                 .no_limit()
                 .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+                .before_attempt(wait_for_rate_limit(&web3, 1))
                 .run(move || {
                     let web3 = web3.cheap_clone();
                     async move {
@@ -934,6 +1313,7 @@ impl EthereumAdapterTrait for EthereumAdapter {
             retry("eth_getBlockByNumber(latest) with txs RPC call", logger)
                 .no_limit()
                 .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+                .before_attempt(wait_for_rate_limit(&web3, 1))
                 .run(move || {
                     let web3 = web3.cheap_clone();
                     async move {
@@ -991,6 +1371,7 @@ impl EthereumAdapterTrait for EthereumAdapter {
             retry(retry_log_message, &logger)
                 .limit(ENV_VARS.request_retries)
                 .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+                .before_attempt(wait_for_rate_limit(&web3, 1))
                 .run(move || {
                     Box::pin(web3.eth().block_with_txs(BlockId::Hash(block_hash)))
                         .compat()
@@ -1022,6 +1403,7 @@ impl EthereumAdapterTrait for EthereumAdapter {
             retry(retry_log_message, &logger)
                 .no_limit()
                 .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+                .before_attempt(wait_for_rate_limit(&web3, 1))
                 .run(move || {
                     let web3 = web3.cheap_clone();
                     async move {
@@ -1044,6 +1426,58 @@ impl EthereumAdapterTrait for EthereumAdapter {
         )
     }
 
+    fn blocks_by_numbers(
+        &self,
+        logger: &Logger,
+        mut block_numbers: Vec<BlockNumber>,
+    ) -> Box<dyn Future<Item = Vec<LightEthereumBlock>, Error = Error> + Send> {
+        block_numbers.sort_unstable();
+        block_numbers.dedup();
+
+        let web3 = self.web3.clone();
+        let logger = logger.clone();
+        let chunks: Vec<Vec<BlockNumber>> = block_numbers
+            .chunks(ENV_VARS.block_batch_size.max(1))
+            .map(|chunk| chunk.to_vec())
+            .collect();
+        Box::new(
+            async move {
+                let mut blocks = Vec::new();
+                for chunk in chunks {
+                    let (first, last) = (chunk[0], chunk[chunk.len() - 1]);
+                    let retry_log_message = format!(
+                        "batch eth_getBlockByNumber RPC call for blocks {} to {}",
+                        first, last
+                    );
+                    let chunk_blocks = retry(retry_log_message, &logger)
+                        .no_limit()
+                        .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+                        .before_attempt(wait_for_rate_limit(&web3, chunk.len()))
+                        .run({
+                            let web3 = web3.cheap_clone();
+                            move || {
+                                fetch_blocks_by_numbers_in_batch(web3.cheap_clone(), chunk.clone())
+                            }
+                        })
+                        .await
+                        .map_err(move |e| {
+                            e.into_inner().unwrap_or_else(move || {
+                                anyhow!(
+                                    "Ethereum node took too long to return blocks {} to {}",
+                                    first,
+                                    last
+                                )
+                            })
+                        })?;
+                    blocks.extend(chunk_blocks.into_iter().flatten());
+                }
+                Ok(blocks)
+            }
+            .boxed()
+            .compat(),
+        )
+    }
+
     fn load_full_block(
         &self,
         logger: &Logger,
@@ -1129,6 +1563,7 @@ impl EthereumAdapterTrait for EthereumAdapter {
             retry(retry_log_message, &logger)
                 .no_limit()
                 .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+                .before_attempt(wait_for_rate_limit(&web3, 1))
                 .run(move || {
                     let web3 = web3.cheap_clone();
                     async move {
@@ -1180,17 +1615,40 @@ impl EthereumAdapterTrait for EthereumAdapter {
 
         trace!(logger, "eth_call";
             "address" => hex::encode(&call.address),
+            "function" => call.function.signature(),
             "data" => hex::encode(&call_data)
         );
 
-        // Check if we have it cached, if not do the call and cache.
-        Box::new(
-            match cache
+        // Check if we have it memoized for this block or cached in the
+        // store, if not do the call and cache.
+        let memoized = if ENV_VARS.memoize_calls {
+            self.call_memo
+                .lock()
+                .unwrap()
+                .get(call.address, &call_data, &call.block_ptr)
+        } else {
+            None
+        };
+        if ENV_VARS.memoize_calls {
+            match memoized {
+                Some(_) => self.metrics.add_call_memo_hit(&self.provider),
+                None => self.metrics.add_call_memo_miss(&self.provider),
+            }
+        }
+        let cached = memoized.or_else(|| {
+            let result = cache
                 .get_call(call.address, &call_data, call.block_ptr.clone())
                 .map_err(|e| error!(logger, "call cache get error"; "error" => e.to_string()))
                 .ok()
-                .flatten()
-            {
+                .flatten();
+            if let Some(result) = &result {
+                self.memoize_call(&call, call_data.clone(), result.clone());
+            }
+            result
+        });
+
+        Box::new(
+            match cached {
                 Some(result) => {
                     Box::new(future::ok(result)) as Box<dyn Future<Item = _, Error = _> + Send>
                 }
@@ -1198,6 +1656,7 @@ impl EthereumAdapterTrait for EthereumAdapter {
                     let cache = cache.clone();
                     let call = call.clone();
                     let logger = logger.clone();
+                    let eth = self.clone();
                     Box::new(
                         self.call(
                             logger.clone(),
@@ -1206,6 +1665,8 @@ impl EthereumAdapterTrait for EthereumAdapter {
                             call.block_ptr.clone(),
                         )
                         .map(move |result| {
+                            eth.memoize_call(&call, call_data.clone(), result.0.clone());
+
                             // Don't block handler execution on writing to the cache.
                             let for_cache = result.0.clone();
                             let _ = graph::spawn_blocking_allow_panic(move || {
@@ -1232,13 +1693,86 @@ impl EthereumAdapterTrait for EthereumAdapter {
                     // Decode failures are reverts. The reasoning is that if Solidity fails to
                     // decode an argument, that's a revert, so the same goes for the output.
                     call.function.decode_output(&output).map_err(|e| {
-                        EthereumContractCallError::Revert(format!("failed to decode output: {}", e))
+                        EthereumContractCallError::Revert(format!(
+                            "failed to decode output of `{}` on contract 0x{}: {}, output: 0x{}",
+                            call.function.signature(),
+                            hex::encode(&call.address),
+                            e,
+                            hex::encode(&output)
+                        ))
                     })
                 }
             }),
         )
     }
 
+    fn estimate_gas(
+        &self,
+        logger: &Logger,
+        call: EthereumContractCall,
+    ) -> Box<dyn Future<Item = U256, Error = EthereumContractCallError> + Send> {
+        for (token, kind) in call
+            .args
+            .iter()
+            .zip(call.function.inputs.iter().map(|p| &p.kind))
+        {
+            if !token.type_check(kind) {
+                return Box::new(future::err(EthereumContractCallError::TypeError(
+                    token.clone(),
+                    kind.clone(),
+                )));
+            }
+        }
+
+        let call_data = match call.function.encode_input(&call.args) {
+            Ok(data) => Bytes(data),
+            Err(e) => return Box::new(future::err(EthereumContractCallError::EncodingError(e))),
+        };
+
+        let web3 = self.web3.clone();
+        let block_number = Web3BlockNumber::Number(call.block_ptr.number.into());
+        let address = call.address;
+
+        Box::new(
+            retry(
+                format!("eth_estimateGas RPC call for block {}", call.block_ptr),
+                logger,
+            )
+            .when(|result| match result {
+                Ok(_) | Err(EthereumContractCallError::Revert(_)) => false,
+                Err(_) => true,
+            })
+            .limit(ENV_VARS.request_retries)
+            .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+            .before_attempt(wait_for_rate_limit(&web3, 1))
+            .run(move || {
+                let req = CallRequest {
+                    to: Some(address),
+                    gas: None,
+                    data: Some(call_data.clone()),
+                    from: None,
+                    gas_price: None,
+                    value: None,
+                    access_list: None,
+                    max_fee_per_gas: None,
+                    max_priority_fee_per_gas: None,
+                    transaction_type: None,
+                };
+                let web3 = web3.cheap_clone();
+
+                async move {
+                    web3.eth()
+                        .estimate_gas(req, Some(block_number))
+                        .await
+                        .map_err(call_error)
+                }
+            })
+            .map_err(|e| e.into_inner().unwrap_or(EthereumContractCallError::Timeout))
+            .boxed()
+            .compat(),
+        )
+    }
+
     /// Load Ethereum blocks in bulk, returning results as they come back as a Stream.
     fn load_blocks(
         &self,
@@ -1752,6 +2286,7 @@ async fn fetch_transaction_receipts_in_batch_with_retry(
         .limit(ENV_VARS.request_retries)
         .no_logging()
         .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+        .before_attempt(wait_for_rate_limit(&web3, hashes.len()))
         .run(move || {
             let web3 = web3.cheap_clone();
             let hashes = hashes.clone();
@@ -1792,6 +2327,28 @@ async fn fetch_transaction_receipts_in_batch(
     Ok(collected)
 }
 
+/// Fetch the blocks with the given numbers with one JSON-RPC batch
+/// request. The result has an entry for each number, in the same order
+async fn fetch_blocks_by_numbers_in_batch(
+    web3: Arc<Web3<Transport>>,
+    block_numbers: Vec<BlockNumber>,
+) -> Result<Vec<Option<LightEthereumBlock>>, Error> {
+    let batching_web3 = Web3::new(Batch::new(web3.transport().clone()));
+    let eth = batching_web3.eth();
+    let block_futures: Vec<_> = block_numbers
+        .into_iter()
+        .map(|block_number| eth.block_with_txs(BlockId::Number(block_number.into())))
+        .collect();
+
+    batching_web3.transport().submit_batch().await?;
+
+    let mut blocks = Vec::with_capacity(block_futures.len());
+    for block in block_futures {
+        blocks.push(block.await?);
+    }
+    Ok(blocks)
+}
+
 /// Retries fetching a single transaction receipt.
 async fn fetch_transaction_receipt_with_retry(
     web3: Arc<Web3<Transport>>,
@@ -1807,6 +2364,7 @@ async fn fetch_transaction_receipt_with_retry(
     retry(retry_log_message, &logger)
         .limit(ENV_VARS.request_retries)
         .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+        .before_attempt(wait_for_rate_limit(&web3, 1))
         .run(move || web3.eth().transaction_receipt(transaction_hash).boxed())
         .await
         .map_err(|_timeout| anyhow!(block_hash).into())
@@ -1991,13 +2549,22 @@ async fn get_transaction_receipts_for_transaction_hashes(
 #[cfg(test)]
 mod tests {
 
+    use crate::adapter::{EthereumLogFilter, ProviderEthRpcMetrics, SubgraphEthRpcMetrics};
+    use crate::transport::{mock::MockTransport, Transport};
     use crate::trigger::{EthereumBlockTriggerType, EthereumTrigger};
 
-    use super::{parse_block_triggers, EthereumBlock, EthereumBlockFilter, EthereumBlockWithCalls};
+    use super::{
+        parse_block_triggers, CallMemo, EthereumAdapter, EthereumAdapterTrait, EthereumBlock,
+        EthereumBlockFilter, EthereumBlockWithCalls, CALL_MEMO_BLOCKS,
+    };
     use graph::blockchain::BlockPtr;
     use graph::prelude::ethabi::ethereum_types::U64;
-    use graph::prelude::web3::types::{Address, Block, Bytes, H256};
-    use graph::prelude::EthereumCall;
+    use graph::prelude::futures03::compat::Future01CompatExt;
+    use graph::prelude::serde_json::{self, json, Value};
+    use graph::prelude::web3::types::{Address, Block, Bytes, Transaction, H256};
+    use graph::prelude::{o, slog, tokio, web3, EthereumCall, Logger};
+    use graph_mock::MockMetricsRegistry;
+    use jsonrpc_core::types::error::{Error as RpcError, ErrorCode};
     use std::collections::HashSet;
     use std::iter::FromIterator;
     use std::sync::Arc;
@@ -2112,4 +2679,157 @@ mod tests {
     fn bytes(value: Vec<u8>) -> Bytes {
         Bytes::from(value)
     }
+
+    async fn mock_adapter(transport: MockTransport) -> EthereumAdapter {
+        let registry = Arc::new(MockMetricsRegistry::new());
+        EthereumAdapter::new(
+            Logger::root(slog::Discard, o!()),
+            "mock".to_string(),
+            "http://localhost:8545",
+            Transport::Mock(transport),
+            Arc::new(ProviderEthRpcMetrics::new(registry)),
+            true,
+            None,
+        )
+        .await
+    }
+
+    fn subgraph_metrics() -> Arc<SubgraphEthRpcMetrics> {
+        let registry = Arc::new(MockMetricsRegistry::new());
+        Arc::new(SubgraphEthRpcMetrics::new(registry, "test"))
+    }
+
+    fn block_number_param(filter: &Value, key: &str) -> u64 {
+        let number = filter[key].as_str().unwrap().trim_start_matches("0x");
+        u64::from_str_radix(number, 16).unwrap()
+    }
+
+    #[tokio::test]
+    async fn blocks_with_logs_shrinks_range_and_dedups_blocks() {
+        // Blocks 3 and 7 have two matching logs each, block 5 has one
+        let log_blocks = [3u64, 3, 5, 7, 7];
+
+        let transport = MockTransport::new(move |method, params| {
+            if method != "eth_getLogs" {
+                return Err(web3::Error::Unreachable);
+            }
+            let from = block_number_param(&params[0], "fromBlock");
+            let to = block_number_param(&params[0], "toBlock");
+            if to - from >= 4 {
+                return Err(web3::Error::Rpc(RpcError {
+                    code: ErrorCode::ServerError(-32005),
+                    message: "query returned more than 10000 results".to_string(),
+                    data: None,
+                }));
+            }
+            let logs: Vec<_> = log_blocks
+                .iter()
+                .filter(|number| (from..=to).contains(*number))
+                .map(|number| {
+                    json!({
+                        "address": format!("{:?}", address(1)),
+                        "topics": [format!("{:?}", hash(9))],
+                        "data": "0x",
+                        "blockHash": format!("{:?}", hash(*number as u8)),
+                        "blockNumber": format!("0x{:x}", number),
+                    })
+                })
+                .collect();
+            Ok(serde_json::Value::Array(logs))
+        });
+        let adapter = mock_adapter(transport.clone()).await;
+        let logger = Logger::root(slog::Discard, o!());
+        let filter = EthereumLogFilter::from_event_signatures(vec![hash(9)]);
+
+        let blocks = adapter
+            .blocks_with_logs(&logger, subgraph_metrics(), 1, 10, &filter)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            vec![
+                BlockPtr::from((hash(3), 3)),
+                BlockPtr::from((hash(5), 5)),
+                BlockPtr::from((hash(7), 7)),
+            ],
+            blocks
+        );
+        // The first request for the whole range was too heavy, and the
+        // range was then scanned one block at a time
+        let log_requests = transport
+            .sent()
+            .into_iter()
+            .flatten()
+            .filter(|method| method == "eth_getLogs")
+            .count();
+        assert_eq!(11, log_requests);
+    }
+
+    #[test]
+    fn call_memo_keeps_recent_blocks() {
+        let ptr = |number: u8| BlockPtr::from((hash(number), number as u64));
+        let mut memo = CallMemo::default();
+
+        // Subgraphs that are at different blocks do not push each other's
+        // results out of the memo
+        memo.set(address(1), vec![1], &ptr(1), vec![10]);
+        memo.set(address(1), vec![1], &ptr(2), vec![20]);
+        assert_eq!(Some(vec![10]), memo.get(address(1), &[1], &ptr(1)));
+        assert_eq!(Some(vec![20]), memo.get(address(1), &[1], &ptr(2)));
+        assert_eq!(None, memo.get(address(2), &[1], &ptr(1)));
+
+        // Block 1 was used last and survives when the memo fills up
+        memo.get(address(1), &[1], &ptr(1));
+        for number in 3..(CALL_MEMO_BLOCKS as u8 + 2) {
+            memo.set(address(1), vec![1], &ptr(number), vec![number]);
+        }
+        assert_eq!(Some(vec![10]), memo.get(address(1), &[1], &ptr(1)));
+        assert_eq!(None, memo.get(address(1), &[1], &ptr(2)));
+    }
+
+    #[tokio::test]
+    async fn blocks_by_numbers_sends_one_batch() {
+        // The node does not have block 9
+        let transport = MockTransport::new(move |method, params| {
+            if method != "eth_getBlockByNumber" {
+                return Err(web3::Error::Unreachable);
+            }
+            let number = params[0].as_str().unwrap().trim_start_matches("0x");
+            let number = u64::from_str_radix(number, 16).unwrap();
+            if number == 9 {
+                return Ok(Value::Null);
+            }
+            let block: Block<Transaction> = Block {
+                hash: Some(hash(number as u8)),
+                number: Some(U64::from(number)),
+                ..Default::default()
+            };
+            Ok(serde_json::to_value(block).unwrap())
+        });
+        let adapter = mock_adapter(transport.clone()).await;
+        let logger = Logger::root(slog::Discard, o!());
+
+        let blocks = adapter
+            .blocks_by_numbers(&logger, vec![7, 3, 9, 5, 3])
+            .compat()
+            .await
+            .unwrap();
+
+        let numbers: Vec<_> = blocks
+            .iter()
+            .map(|block| block.number.unwrap().as_u64())
+            .collect();
+        assert_eq!(vec![3, 5, 7], numbers);
+        // All blocks were requested in a single batch, without duplicates
+        let block_requests: Vec<_> = transport
+            .sent()
+            .into_iter()
+            .filter(|methods| {
+                methods
+                    .iter()
+                    .any(|method| method == "eth_getBlockByNumber")
+            })
+            .collect();
+        assert_eq!(vec![vec!["eth_getBlockByNumber"; 4]], block_requests);
+    }
 }
@@ -6,9 +6,16 @@ use graph::{
         error, ethabi::ethereum_types::H256, info, tokio, trace, warn, ChainStore, Error,
         EthereumBlockWithCalls, Future01CompatExt, LogCode, Logger,
     },
+    util::backoff::ExponentialBackoff,
 };
 use std::{sync::Arc, time::Duration};
 
+/// Upper bound for the delay between polls while polling keeps failing
+const MAX_POLLING_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Number of consecutive failed polls after which we warn about them
+const SUSTAINED_FAILURE_ATTEMPTS: u64 = 5;
+
 pub struct BlockIngestor {
     logger: Logger,
     ancestor_count: i32,
@@ -35,8 +42,14 @@ impl BlockIngestor {
     }
 
     pub async fn into_polling_stream(self) {
+        // Consecutive failures back off exponentially from the polling
+        // interval so that we do not hammer a struggling endpoint
+        let mut backoff =
+            ExponentialBackoff::new(self.polling_interval, MAX_POLLING_BACKOFF).with_jitter(0.5);
+
         loop {
-            match self.do_poll().await {
+            let res = self.do_poll().await;
+            match &res {
                 // Some polls will fail due to transient issues
                 Err(err @ IngestorError::BlockUnavailable(_)) => {
                     info!(
@@ -63,7 +76,24 @@ impl BlockIngestor {
                 self.cleanup_cached_blocks()
             }
 
-            tokio::time::sleep(self.polling_interval).await;
+            match res {
+                Ok(()) => {
+                    if backoff.attempt > 0 {
+                        info!(self.logger, "Block polling recovered";
+                              "failed_attempts" => backoff.attempt);
+                    }
+                    backoff.reset();
+                    tokio::time::sleep(self.polling_interval).await;
+                }
+                Err(_) => {
+                    if backoff.attempt + 1 >= SUSTAINED_FAILURE_ATTEMPTS {
+                        warn!(self.logger, "Block polling keeps failing, backing off";
+                              "failed_attempts" => backoff.attempt + 1,
+                              "retry_delay_ms" => backoff.delay().as_millis() as u64);
+                    }
+                    backoff.sleep_async().await;
+                }
+            }
         }
     }
 
@@ -11,7 +11,7 @@ mod transport;
 pub use self::capabilities::NodeCapabilities;
 pub use self::ethereum_adapter::EthereumAdapter;
 pub use self::runtime::RuntimeAdapter;
-pub use self::transport::Transport;
+pub use self::transport::{RateLimit, Transport};
 pub use env::ENV_VARS;
 
 // ETHDEP: These concrete types should probably not be exposed.
@@ -24,7 +24,8 @@ pub mod trigger;
 
 pub use crate::adapter::{
     EthereumAdapter as EthereumAdapterTrait, EthereumContractCall, EthereumContractCallError,
-    MockEthereumAdapter, ProviderEthRpcMetrics, SubgraphEthRpcMetrics, TriggerFilter,
+    EthereumHealth, EthereumLogFilter, EthereumNetworkIdentifier, MockEthereumAdapter,
+    ProviderEthRpcMetrics, SubgraphEthRpcMetrics, TriggerFilter,
 };
 pub use crate::chain::Chain;
 pub use crate::network::EthereumNetworks;
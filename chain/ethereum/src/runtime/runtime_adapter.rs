@@ -14,9 +14,9 @@ use graph::{
     cheap_clone::CheapClone,
     prelude::{
         ethabi::{self, Address, Token},
-        EthereumCallCache, Future01CompatExt,
+        hex, EthereumCallCache, Future01CompatExt,
     },
-    runtime::{asc_get, asc_new, AscPtr, HostExportError},
+    runtime::{asc_get, asc_new_or_null, HostExportError},
     semver::Version,
     slog::{info, trace, Logger},
 };
@@ -90,10 +90,7 @@ fn ethereum_call(
         call,
         abis,
     )?;
-    match result {
-        Some(tokens) => Ok(asc_new(ctx.heap, tokens.as_slice(), &ctx.gas)?),
-        None => Ok(AscPtr::null()),
-    }
+    Ok(asc_new_or_null(ctx.heap, result.as_deref(), &ctx.gas)?)
 }
 
 /// Returns `Ok(None)` if the call was reverted.
@@ -158,6 +155,14 @@ fn eth_call(
             })?,
     };
 
+    // Identify the exact contract and function in errors and logs
+    let target = format!(
+        "function `{}` of contract \"{}\" at 0x{}",
+        function.signature(),
+        unresolved_call.contract_name,
+        hex::encode(&unresolved_call.contract_address)
+    );
+
     let call = EthereumContractCall {
         address: unresolved_call.contract_address,
         block_ptr: block_ptr.cheap_clone(),
@@ -169,38 +174,41 @@ fn eth_call(
     let logger1 = logger.clone();
     let call_cache = call_cache.clone();
     let result = match graph::block_on(
-            eth_adapter.contract_call(&logger1, call, call_cache).compat()
-        ) {
-            Ok(tokens) => Ok(Some(tokens)),
-            Err(EthereumContractCallError::Revert(reason)) => {
-                info!(logger, "Contract call reverted"; "reason" => reason);
-                Ok(None)
-            }
-
-            // Any error reported by the Ethereum node could be due to the block no longer being on
-            // the main chain. This is very unespecific but we don't want to risk failing a
-            // subgraph due to a transient error such as a reorg.
-            Err(EthereumContractCallError::Web3Error(e)) => Err(HostExportError::PossibleReorg(anyhow::anyhow!(
-                "Ethereum node returned an error when calling function \"{}\" of contract \"{}\": {}",
-                unresolved_call.function_name,
-                unresolved_call.contract_name,
-                e
-            ))),
-
-            // Also retry on timeouts.
-            Err(EthereumContractCallError::Timeout) => Err(HostExportError::PossibleReorg(anyhow::anyhow!(
-                "Ethereum node did not respond when calling function \"{}\" of contract \"{}\"",
-                unresolved_call.function_name,
-                unresolved_call.contract_name,
-            ))),
-
-            Err(e) => Err(HostExportError::Unknown(anyhow::anyhow!(
-                "Failed to call function \"{}\" of contract \"{}\": {}",
-                unresolved_call.function_name,
-                unresolved_call.contract_name,
+        eth_adapter
+            .contract_call(&logger1, call, call_cache)
+            .compat(),
+    ) {
+        Ok(tokens) => Ok(Some(tokens)),
+        Err(EthereumContractCallError::Revert(reason)) => {
+            info!(logger, "Contract call reverted";
+                      "reason" => reason,
+                      "address" => format!("0x{}", hex::encode(&unresolved_call.contract_address)),
+                      "function" => function.signature());
+            Ok(None)
+        }
+
+        // Any error reported by the Ethereum node could be due to the block no longer being on
+        // the main chain. This is very unespecific but we don't want to risk failing a
+        // subgraph due to a transient error such as a reorg.
+        Err(EthereumContractCallError::Web3Error(e)) => {
+            Err(HostExportError::PossibleReorg(anyhow::anyhow!(
+                "Ethereum node returned an error when calling {}: {}",
+                target,
                 e
-            ))),
-        };
+            )))
+        }
+
+        // Also retry on timeouts.
+        Err(EthereumContractCallError::Timeout) => Err(HostExportError::PossibleReorg(
+            anyhow::anyhow!("Ethereum node did not respond when calling {}", target,),
+        )),
+
+        Err(e) => Err(HostExportError::Unknown(anyhow::anyhow!(
+            "Failed to call {}: {}",
+            target,
+            e
+        ))),
+    };
 
     trace!(logger, "Contract call finished";
               "address" => &unresolved_call.contract_address.to_string(),
@@ -4,9 +4,18 @@ use jsonrpc_core::Value;
 use web3::transports::{http, ipc, ws};
 use web3::RequestId;
 
+use graph::prelude::futures03::future::BoxFuture;
+use graph::prelude::futures03::FutureExt;
 use graph::prelude::*;
 use graph::url::Url;
+use graph::util::backoff::ExponentialBackoff;
+use std::fmt;
 use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::adapter::ProviderEthRpcMetrics;
 
 /// Abstraction over the different web3 transports.
 #[derive(Clone, Debug)]
@@ -14,6 +23,20 @@ pub enum Transport {
     RPC(http::Http),
     IPC(ipc::Ipc),
     WS(ws::WebSocket),
+    Reconnecting(ReconnectingTransport<Transport>),
+    RateLimited(RateLimitedTransport<Transport>),
+    #[cfg(test)]
+    Mock(mock::MockTransport),
+}
+
+/// The maximum rate at which requests are sent to a provider
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct RateLimit {
+    /// The sustained number of requests per second
+    pub requests_per_second: u32,
+    /// How many requests can be sent at once after the provider was idle.
+    /// Defaults to `requests_per_second`
+    pub burst: Option<u32>,
 }
 
 impl Transport {
@@ -46,6 +69,62 @@ impl Transport {
             .unwrap();
         Transport::RPC(http::Http::with_client(client, rpc))
     }
+
+    /// Wrap an IPC or WebSocket transport so that it reestablishes the
+    /// connection to `url` when it gets dropped. HTTP transports do not keep
+    /// a connection open and are returned unchanged.
+    pub fn reconnecting(
+        self,
+        logger: &Logger,
+        url: &str,
+        provider: &str,
+        metrics: Arc<ProviderEthRpcMetrics>,
+    ) -> Self {
+        let url = url.to_string();
+        let connect: Connector<Transport> = match &self {
+            Transport::RPC(_) | Transport::Reconnecting(_) | Transport::RateLimited(_) => {
+                return self
+            }
+            #[cfg(test)]
+            Transport::Mock(_) => return self,
+            Transport::IPC(_) => Arc::new(move || {
+                let url = url.clone();
+                async move { ipc::Ipc::new(&url).await.map(Transport::IPC) }.boxed()
+            }),
+            Transport::WS(_) => Arc::new(move || {
+                let url = url.clone();
+                async move { ws::WebSocket::new(&url).await.map(Transport::WS) }.boxed()
+            }),
+        };
+        Transport::Reconnecting(ReconnectingTransport::new(
+            logger, self, connect, provider, metrics,
+        ))
+    }
+
+    /// Wrap this transport so that it sends requests no faster than
+    /// `limit` allows. Requests that exceed the limit wait until they can
+    /// be sent instead of failing.
+    pub fn rate_limited(
+        self,
+        limit: RateLimit,
+        provider: &str,
+        metrics: Arc<ProviderEthRpcMetrics>,
+    ) -> Self {
+        Transport::RateLimited(RateLimitedTransport::new(self, limit, provider, metrics))
+    }
+
+    /// Wait until the rate limit of this transport allows sending `count`
+    /// more requests and set that capacity aside for the next requests
+    /// that are sent. Transports without a rate limit are always ready.
+    pub fn reserve(&self, count: usize) -> BoxFuture<'static, ()> {
+        match self {
+            Transport::RateLimited(t) => {
+                let t = t.clone();
+                async move { t.reserve(count).await }.boxed()
+            }
+            _ => std::future::ready(()).boxed(),
+        }
+    }
 }
 
 impl web3::Transport for Transport {
@@ -56,6 +135,10 @@ impl web3::Transport for Transport {
             Transport::RPC(http) => http.prepare(method, params),
             Transport::IPC(ipc) => ipc.prepare(method, params),
             Transport::WS(ws) => ws.prepare(method, params),
+            Transport::Reconnecting(t) => t.prepare(method, params),
+            Transport::RateLimited(t) => t.prepare(method, params),
+            #[cfg(test)]
+            Transport::Mock(t) => t.prepare(method, params),
         }
     }
 
@@ -64,6 +147,10 @@ impl web3::Transport for Transport {
             Transport::RPC(http) => Box::new(http.send(id, request)),
             Transport::IPC(ipc) => Box::new(ipc.send(id, request)),
             Transport::WS(ws) => Box::new(ws.send(id, request)),
+            Transport::Reconnecting(t) => Box::new(t.send(id, request)),
+            Transport::RateLimited(t) => Box::new(t.send(id, request)),
+            #[cfg(test)]
+            Transport::Mock(t) => Box::new(t.send(id, request)),
         }
     }
 }
@@ -83,6 +170,414 @@ impl web3::BatchTransport for Transport {
             Transport::RPC(http) => Box::new(http.send_batch(requests)),
             Transport::IPC(ipc) => Box::new(ipc.send_batch(requests)),
             Transport::WS(ws) => Box::new(ws.send_batch(requests)),
+            Transport::Reconnecting(t) => Box::new(t.send_batch(requests)),
+            Transport::RateLimited(t) => Box::new(t.send_batch(requests)),
+            #[cfg(test)]
+            Transport::Mock(t) => Box::new(t.send_batch(requests)),
+        }
+    }
+}
+
+/// How to establish a new connection for a `ReconnectingTransport`
+type Connector<T> = Arc<dyn Fn() -> BoxFuture<'static, Result<T, web3::Error>> + Send + Sync>;
+
+/// A transport that wraps a connection-oriented transport like IPC or
+/// WebSockets and reestablishes the connection when it was dropped. Requests
+/// that fail because the connection was closed are retried once on the new
+/// connection.
+#[derive(Clone)]
+pub struct ReconnectingTransport<T> {
+    logger: Logger,
+    provider: String,
+    current: Arc<RwLock<T>>,
+    /// Incremented every time we reconnect so that concurrent requests
+    /// that all noticed the same dropped connection only reconnect once
+    generation: Arc<AtomicUsize>,
+    /// The id for the next request. We hand out ids ourselves rather than
+    /// using the ones from the current connection since a new connection
+    /// starts counting from scratch and its ids would collide with those
+    /// of requests that are still in flight
+    next_id: Arc<AtomicUsize>,
+    reconnect_lock: Arc<tokio::sync::Mutex<()>>,
+    connect: Connector<T>,
+    metrics: Arc<ProviderEthRpcMetrics>,
+}
+
+impl<T> fmt::Debug for ReconnectingTransport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReconnectingTransport")
+            .field("provider", &self.provider)
+            .finish()
+    }
+}
+
+impl<T> ReconnectingTransport<T>
+where
+    T: web3::BatchTransport + Clone + Send + Sync + 'static,
+    T::Out: Send,
+    T::Batch: Send,
+{
+    const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+    const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+    pub fn new(
+        logger: &Logger,
+        transport: T,
+        connect: Connector<T>,
+        provider: &str,
+        metrics: Arc<ProviderEthRpcMetrics>,
+    ) -> Self {
+        ReconnectingTransport {
+            logger: logger.new(o!("component" => "ReconnectingTransport")),
+            provider: provider.to_string(),
+            current: Arc::new(RwLock::new(transport)),
+            generation: Arc::new(AtomicUsize::new(0)),
+            next_id: Arc::new(AtomicUsize::new(1)),
+            reconnect_lock: Arc::new(tokio::sync::Mutex::new(())),
+            connect,
+            metrics,
+        }
+    }
+
+    fn current(&self) -> (T, usize) {
+        let transport = self.current.read().unwrap().clone();
+        (transport, self.generation.load(Ordering::SeqCst))
+    }
+
+    /// Whether `error` indicates that the underlying connection is gone
+    fn is_connection_closed(error: &web3::Error) -> bool {
+        matches!(
+            error,
+            web3::Error::Unreachable | web3::Error::Io(_) | web3::Error::Transport(_)
+        )
+    }
+
+    /// Replace the connection of generation `failed`, retrying with
+    /// exponential backoff until we succeed. If another request already
+    /// replaced it, do nothing. The lock is only held while we try to
+    /// connect, not while we wait between attempts
+    async fn reconnect(&self, failed: usize) {
+        let mut backoff = ExponentialBackoff::new(Self::INITIAL_BACKOFF, Self::MAX_BACKOFF);
+        loop {
+            let guard = self.reconnect_lock.lock().await;
+            if self.generation.load(Ordering::SeqCst) != failed {
+                return;
+            }
+
+            match (self.connect)().await {
+                Ok(transport) => {
+                    *self.current.write().unwrap() = transport;
+                    self.generation.fetch_add(1, Ordering::SeqCst);
+                    self.metrics.add_reconnect(&self.provider);
+                    info!(self.logger, "Reconnected to Ethereum node";
+                          "attempts" => backoff.attempt + 1);
+                    return;
+                }
+                Err(e) => {
+                    drop(guard);
+                    warn!(self.logger, "Failed to reconnect to Ethereum node, retrying";
+                          "error" => e.to_string(),
+                          "retry_delay_s" => backoff.delay().as_secs());
+                    backoff.sleep_async().await;
+                }
+            }
+        }
+    }
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        (id, web3::helpers::build_request(id, method, params))
+    }
+
+    fn send(
+        &self,
+        id: RequestId,
+        request: Call,
+    ) -> impl Future<Output = Result<Value, web3::Error>> + Send + Unpin {
+        let this = self.clone();
+        async move {
+            let (transport, generation) = this.current();
+            match transport.send(id, request.clone()).await {
+                Err(e) if Self::is_connection_closed(&e) => {
+                    warn!(this.logger, "Connection to Ethereum node dropped";
+                          "error" => e.to_string());
+                    this.reconnect(generation).await;
+                    let (transport, _) = this.current();
+                    transport.send(id, request).await
+                }
+                result => result,
+            }
+        }
+        .boxed()
+    }
+
+    fn send_batch<I>(
+        &self,
+        requests: I,
+    ) -> impl Future<Output = Result<Vec<Result<Value, web3::Error>>, web3::Error>> + Send + Unpin
+    where
+        I: IntoIterator<Item = (RequestId, Call)>,
+    {
+        let requests: Vec<_> = requests.into_iter().collect();
+        let this = self.clone();
+        async move {
+            let (transport, generation) = this.current();
+            match transport.send_batch(requests.clone()).await {
+                Err(e) if Self::is_connection_closed(&e) => {
+                    warn!(this.logger, "Connection to Ethereum node dropped";
+                          "error" => e.to_string());
+                    this.reconnect(generation).await;
+                    let (transport, _) = this.current();
+                    transport.send_batch(requests).await
+                }
+                result => result,
+            }
+        }
+        .boxed()
+    }
+}
+
+/// A token bucket that refills at `rate` tokens per second up to
+/// `capacity` tokens
+struct TokenBucket {
+    capacity: f64,
+    rate: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit) -> Self {
+        let rate = limit.requests_per_second.max(1) as f64;
+        let capacity = limit.burst.unwrap_or(limit.requests_per_second).max(1) as f64;
+        TokenBucket {
+            capacity,
+            rate,
+            tokens: capacity,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Take a token from the bucket. If the bucket is empty, return how
+    /// long it will take until the next token becomes available
+    fn try_acquire(&mut self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+}
+
+/// A transport that limits the rate at which requests are sent through
+/// the transport it wraps. Every request in a batch counts against the
+/// limit separately.
+#[derive(Clone)]
+pub struct RateLimitedTransport<T> {
+    inner: T,
+    bucket: Arc<Mutex<TokenBucket>>,
+    /// Tokens that were taken from the bucket by `reserve` and not yet
+    /// used by a request
+    reserved: Arc<AtomicUsize>,
+    provider: String,
+    metrics: Arc<ProviderEthRpcMetrics>,
+}
+
+impl<T> fmt::Debug for RateLimitedTransport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimitedTransport")
+            .field("provider", &self.provider)
+            .finish()
+    }
+}
+
+impl<T> RateLimitedTransport<T>
+where
+    T: web3::BatchTransport + Clone + Send + Sync + 'static,
+    T::Out: Send,
+    T::Batch: Send,
+{
+    pub fn new(
+        inner: T,
+        limit: RateLimit,
+        provider: &str,
+        metrics: Arc<ProviderEthRpcMetrics>,
+    ) -> Self {
+        RateLimitedTransport {
+            inner,
+            bucket: Arc::new(Mutex::new(TokenBucket::new(limit))),
+            reserved: Arc::new(AtomicUsize::new(0)),
+            provider: provider.to_string(),
+            metrics,
+        }
+    }
+
+    /// Wait until the rate limit allows sending `count` more requests
+    async fn acquire(&self, count: usize) {
+        for _ in 0..count {
+            let mut throttled = false;
+            loop {
+                let res = self.bucket.lock().unwrap().try_acquire();
+                match res {
+                    Ok(()) => break,
+                    Err(wait) => {
+                        if !throttled {
+                            self.metrics.add_throttled(&self.provider);
+                            throttled = true;
+                        }
+                        tokio::time::sleep(wait).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Wait until the rate limit allows sending `count` more requests and
+    /// keep the tokens for the next requests that are sent
+    async fn reserve(&self, count: usize) {
+        self.acquire(count).await;
+        self.reserved.fetch_add(count, Ordering::SeqCst);
+    }
+
+    /// Wait until the rate limit allows sending `count` requests, using
+    /// reserved tokens first
+    async fn acquire_unreserved(&self, count: usize) {
+        let reserved = self
+            .reserved
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |reserved| {
+                Some(reserved - reserved.min(count))
+            })
+            .unwrap();
+        self.acquire(count - reserved.min(count)).await;
+    }
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        self.inner.prepare(method, params)
+    }
+
+    fn send(
+        &self,
+        id: RequestId,
+        request: Call,
+    ) -> impl Future<Output = Result<Value, web3::Error>> + Send + Unpin {
+        let this = self.clone();
+        async move {
+            this.acquire_unreserved(1).await;
+            this.inner.send(id, request).await
+        }
+        .boxed()
+    }
+
+    fn send_batch<I>(
+        &self,
+        requests: I,
+    ) -> impl Future<Output = Result<Vec<Result<Value, web3::Error>>, web3::Error>> + Send + Unpin
+    where
+        I: IntoIterator<Item = (RequestId, Call)>,
+    {
+        let requests: Vec<_> = requests.into_iter().collect();
+        let this = self.clone();
+        async move {
+            this.acquire_unreserved(requests.len()).await;
+            this.inner.send_batch(requests).await
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod mock {
+    use super::*;
+    use graph::prelude::futures03::future;
+    use jsonrpc_core::types::{MethodCall, Params};
+
+    type Responder = dyn Fn(&str, &[Value]) -> Result<Value, web3::Error> + Send + Sync;
+
+    /// A transport for tests that answers every request by calling a
+    /// function with the request's method and parameters. It remembers
+    /// the methods it was asked to call, grouping requests that were sent
+    /// in the same batch
+    #[derive(Clone)]
+    pub struct MockTransport {
+        respond: Arc<Responder>,
+        next_id: Arc<AtomicUsize>,
+        sent: Arc<Mutex<Vec<Vec<String>>>>,
+    }
+
+    impl fmt::Debug for MockTransport {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("MockTransport").finish()
+        }
+    }
+
+    impl MockTransport {
+        pub fn new<F>(respond: F) -> Self
+        where
+            F: Fn(&str, &[Value]) -> Result<Value, web3::Error> + Send + Sync + 'static,
+        {
+            MockTransport {
+                respond: Arc::new(respond),
+                next_id: Arc::new(AtomicUsize::new(1)),
+                sent: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+
+        /// The methods of all requests sent so far, one entry per call to
+        /// `send` or `send_batch`
+        pub fn sent(&self) -> Vec<Vec<String>> {
+            self.sent.lock().unwrap().clone()
+        }
+
+        fn answer(&self, request: &Call) -> (String, Result<Value, web3::Error>) {
+            match request {
+                Call::MethodCall(MethodCall { method, params, .. }) => {
+                    let params = match params {
+                        Params::Array(params) => params.clone(),
+                        Params::Map(_) | Params::None => vec![],
+                    };
+                    (method.clone(), (self.respond)(method, &params))
+                }
+                _ => (
+                    String::new(),
+                    Err(web3::Error::Decoder("not a method call".to_string())),
+                ),
+            }
+        }
+
+        pub(super) fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+            let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+            (id, web3::helpers::build_request(id, method, params))
+        }
+
+        pub(super) fn send(
+            &self,
+            _id: RequestId,
+            request: Call,
+        ) -> future::Ready<Result<Value, web3::Error>> {
+            let (method, result) = self.answer(&request);
+            self.sent.lock().unwrap().push(vec![method]);
+            future::ready(result)
+        }
+
+        pub(super) fn send_batch<I>(
+            &self,
+            requests: I,
+        ) -> future::Ready<Result<Vec<Result<Value, web3::Error>>, web3::Error>>
+        where
+            I: IntoIterator<Item = (RequestId, Call)>,
+        {
+            let (methods, results): (Vec<_>, Vec<_>) = requests
+                .into_iter()
+                .map(|(_, request)| self.answer(&request))
+                .unzip();
+            self.sent.lock().unwrap().push(methods);
+            future::ready(Ok(results))
         }
     }
 }
@@ -224,7 +224,7 @@ dataSources:
           file:
             /: /ipfs/Qmabi
       callHandlers:
-        - function: get(address)
+        - function: get(uint256)
           handler: handleget
 schema:
   file:
@@ -239,6 +239,83 @@ specVersion: 0.0.2
     assert_eq!(true, required_capabilities.traces);
 }
 
+#[test]
+fn event_handler_not_in_abi_causes_validation_error() {
+    const YAML: &str = "
+specVersion: 0.0.2
+schema:
+  file:
+    /: /ipfs/Qmschema
+dataSources:
+  - kind: ethereum/contract
+    name: Factory
+    network: mainnet
+    source:
+      abi: Factory
+      startBlock: 9562480
+    mapping:
+      kind: ethereum/events
+      apiVersion: 0.0.4
+      language: wasm/assemblyscript
+      entities:
+        - TestEntity
+      file:
+        /: /ipfs/Qmmapping
+      abis:
+        - name: Factory
+          file:
+            /: /ipfs/Qmabi
+      eventHandlers:
+        - event: Transfer(address,uint256)
+          handler: handleTransfer
+";
+
+    test_store::run_test_sequentially(|store| async move {
+        let store = store.subgraph_store();
+        let unvalidated: UnvalidatedSubgraphManifest<Chain> = {
+            let mut resolver = TextResolver::default();
+            let id = DeploymentHash::new("Qmmanifest").unwrap();
+            resolver.add(id.as_str(), &YAML);
+            resolver.add("/ipfs/Qmabi", &ABI);
+            resolver.add("/ipfs/Qmschema", &GQL_SCHEMA);
+            resolver.add("/ipfs/Qmmapping", &MAPPING_WITH_IPFS_FUNC_WASM);
+
+            let resolver: Arc<dyn LinkResolverTrait> = Arc::new(resolver);
+
+            let raw = serde_yaml::from_str(YAML).unwrap();
+            UnvalidatedSubgraphManifest::resolve(
+                id,
+                raw,
+                &resolver,
+                &LOGGER,
+                SPEC_VERSION_0_0_4.clone(),
+            )
+            .await
+            .expect("Parsing simple manifest works")
+        };
+
+        let error_msg = unvalidated
+            .validate(store.clone(), true)
+            .await
+            .expect_err("Validation must fail")
+            .into_iter()
+            .find(|e| {
+                matches!(
+                    e,
+                    SubgraphManifestValidationError::DataSourceValidation(_, _)
+                )
+            })
+            .expect("There must be a DataSourceValidation error")
+            .to_string();
+
+        assert_eq!(
+            "data source Factory is invalid: data source has an event handler for \
+             `Transfer(address,uint256)`, but the event is not declared in the contract ABI `Factory`",
+            error_msg
+        );
+    });
+}
+
 #[test]
 fn undeclared_grafting_feature_causes_feature_validation_error() {
     const YAML: &str = "
@@ -475,7 +552,7 @@ dataSources:
           file:
             /: /ipfs/Qmabi
       callHandlers:
-        - function: get(address)
+        - function: get(uint256)
           handler: handleget
 ";
 
@@ -553,7 +630,7 @@ dataSources:
           file:
             /: /ipfs/Qmabi
       callHandlers:
-        - function: get(address)
+        - function: get(uint256)
           handler: handleget
 ";
 
@@ -627,3 +704,183 @@ schema:
         assert!(manifest.features.contains(&SubgraphFeature::NonFatalErrors))
     });
 }
+
+async fn dry_run_validate(text: &str) -> Vec<SubgraphManifestValidationError> {
+    let mut resolver = TextResolver::default();
+    let id = DeploymentHash::new("Qmmanifest").unwrap();
+
+    resolver.add(&format!("/ipfs/{}", id), &text);
+    resolver.add("/ipfs/Qmschema", &GQL_SCHEMA);
+    resolver.add("/ipfs/Qmabi", &ABI);
+    resolver.add("/ipfs/Qmmapping", &MAPPING_WITH_IPFS_FUNC_WASM);
+
+    let resolver: Arc<dyn LinkResolverTrait> = Arc::new(resolver);
+
+    SubgraphManifest::<Chain>::validate(id, &resolver, &LOGGER, SPEC_VERSION_0_0_4.clone()).await
+}
+
+#[tokio::test]
+async fn dry_run_validation() {
+    const YAML: &str = "
+specVersion: 0.0.2
+schema:
+  file:
+    /: /ipfs/Qmschema
+dataSources:
+  - kind: ethereum/contract
+    name: Factory
+    network: mainnet
+    source:
+      address: \"0x0000000000000000000000000000000000000000\"
+      abi: Factory
+    mapping:
+      kind: ethereum/events
+      apiVersion: 0.0.4
+      language: wasm/assemblyscript
+      entities:
+        - TestEntity
+      file:
+        /: /ipfs/Qmmapping
+      abis:
+        - name: Factory
+          file:
+            /: /ipfs/Qmabi
+templates:
+  - kind: ethereum/unknown
+    name: Pair
+    network: mainnet
+    source:
+      abi: Factory
+    mapping:
+      kind: ethereum/events
+      apiVersion: 0.0.4
+      language: wasm/assemblyscript
+      entities:
+        - TestEntity
+      file:
+        /: /ipfs/Qmmapping
+      abis:
+        - name: Factory
+          file:
+            /: /ipfs/Qmabi
+";
+
+    let errors = dry_run_validate(YAML).await;
+    assert_eq!(1, errors.len());
+    assert_eq!(
+        "data source template Pair has kind `ethereum/unknown` but no data source has that kind",
+        errors[0].to_string()
+    );
+
+    // A manifest whose ABI can not be resolved is reported as a resolve
+    // error rather than causing a failure
+    let errors = dry_run_validate(&YAML.replace("/ipfs/Qmabi", "/ipfs/Qmmissing")).await;
+    assert_eq!(1, errors.len());
+    assert!(matches!(
+        errors[0],
+        SubgraphManifestValidationError::ResolveError(_)
+    ));
+}
+
+#[tokio::test]
+async fn call_handler_not_in_abi_causes_validation_error() {
+    const YAML: &str = "
+specVersion: 0.0.2
+schema:
+  file:
+    /: /ipfs/Qmschema
+dataSources:
+  - kind: ethereum/contract
+    name: Factory
+    network: mainnet
+    source:
+      address: \"0x0000000000000000000000000000000000000000\"
+      abi: Factory
+    mapping:
+      kind: ethereum/events
+      apiVersion: 0.0.4
+      language: wasm/assemblyscript
+      entities:
+        - TestEntity
+      file:
+        /: /ipfs/Qmmapping
+      abis:
+        - name: Factory
+          file:
+            /: /ipfs/Qmabi
+      callHandlers:
+        - function: get(uint256)
+          handler: handleget
+";
+
+    // `get(uint256)` is in the ABI
+    let errors = dry_run_validate(YAML).await;
+    assert!(errors.is_empty(), "unexpected errors: {:?}", errors);
+
+    let errors = dry_run_validate(&YAML.replace("get(uint256)", "set(uint256)")).await;
+    assert_eq!(1, errors.len());
+    assert_eq!(
+        "data source Factory is invalid: data source has a call handler for `set(uint256)`, \
+         but the function is not declared in the contract ABI `Factory` or is not payable \
+         or non-payable",
+        errors[0].to_string()
+    );
+}
+
+#[tokio::test]
+async fn template_handler_not_in_abi_causes_validation_error() {
+    const YAML: &str = "
+specVersion: 0.0.2
+schema:
+  file:
+    /: /ipfs/Qmschema
+dataSources:
+  - kind: ethereum/contract
+    name: Factory
+    network: mainnet
+    source:
+      address: \"0x0000000000000000000000000000000000000000\"
+      abi: Factory
+    mapping:
+      kind: ethereum/events
+      apiVersion: 0.0.4
+      language: wasm/assemblyscript
+      entities:
+        - TestEntity
+      file:
+        /: /ipfs/Qmmapping
+      abis:
+        - name: Factory
+          file:
+            /: /ipfs/Qmabi
+templates:
+  - kind: ethereum/contract
+    name: Pair
+    network: mainnet
+    source:
+      abi: Factory
+    mapping:
+      kind: ethereum/events
+      apiVersion: 0.0.4
+      language: wasm/assemblyscript
+      entities:
+        - TestEntity
+      file:
+        /: /ipfs/Qmmapping
+      abis:
+        - name: Factory
+          file:
+            /: /ipfs/Qmabi
+      eventHandlers:
+        - event: Transfer(address,uint256)
+          handler: handleTransfer
+";
+
+    let errors = dry_run_validate(YAML).await;
+    assert_eq!(1, errors.len());
+    assert_eq!(
+        "data source template Pair is invalid: data source has an event handler for \
+         `Transfer(address,uint256)`, but the event is not declared in the contract ABI `Factory`",
+        errors[0].to_string()
+    );
+}
@@ -319,6 +319,10 @@ impl blockchain::DataSourceTemplate<Chain> for DataSourceTemplate {
         &self.name
     }
 
+    fn kind(&self) -> &str {
+        &self.kind
+    }
+
     fn api_version(&self) -> semver::Version {
         self.mapping.api_version.clone()
     }
@@ -326,6 +330,10 @@ impl blockchain::DataSourceTemplate<Chain> for DataSourceTemplate {
     fn runtime(&self) -> &[u8] {
         self.mapping.runtime.as_ref()
     }
+
+    fn validate(&self) -> Vec<Error> {
+        vec![]
+    }
 }
 
 #[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Deserialize)]
@@ -315,6 +315,14 @@ impl blockchain::DataSourceTemplate<Chain> for DataSourceTemplate {
     fn runtime(&self) -> &[u8] {
         unimplemented!("{}", TEMPLATE_ERROR);
     }
+
+    fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    fn validate(&self) -> Vec<Error> {
+        unimplemented!("{}", TEMPLATE_ERROR);
+    }
 }
 
 #[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Deserialize)]
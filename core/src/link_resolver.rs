@@ -10,6 +10,7 @@ use graph::env::EnvVars;
 use graph::util::futures::RetryConfigNoTimeout;
 use lru_time_cache::LruCache;
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 
 use graph::{
     ipfs_client::{IpfsClient, ObjectStatResponse},
@@ -107,6 +108,68 @@ fn restrict_file_size(
     Ok(())
 }
 
+/// The sha2-256 digest that the content of the IPFS file `path` must have,
+/// if we can tell from its CID. We can only recompute the CID of a file
+/// when it is a CIDv1 with the `raw` codec and a sha2-256 multihash, since
+/// its hash covers the bytes of the file directly. For all other CIDs, in
+/// particular CIDv0 and `dag-pb` CIDs, the hash also covers how the file
+/// was split into blocks, and we return `None`
+fn raw_sha256_digest(path: &str) -> Option<Vec<u8>> {
+    // The version, codec, hash function and digest length of a CIDv1 for
+    // a raw block with a sha2-256 digest
+    const RAW_SHA256_PREFIX: [u8; 4] = [0x01, 0x55, 0x12, 0x20];
+
+    // A CIDv1 is usually written in base32, with multibase prefix `b`
+    let cid_bytes = path.strip_prefix('b').and_then(decode_base32)?;
+    if cid_bytes.len() != RAW_SHA256_PREFIX.len() + 32 || !cid_bytes.starts_with(&RAW_SHA256_PREFIX)
+    {
+        return None;
+    }
+    Some(cid_bytes[RAW_SHA256_PREFIX.len()..].to_vec())
+}
+
+/// Check that `digest`, the sha2-256 digest of what we received for the
+/// IPFS file `path`, matches what its CID requires
+fn check_digest(path: &str, expected: &[u8], digest: &[u8]) -> Result<(), Error> {
+    if expected != digest {
+        return Err(IpfsContentMismatch {
+            path: path.to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Check that `data` is the content of the IPFS file `path` where we can
+/// tell; see `raw_sha256_digest`
+fn verify_content(path: &str, data: &[u8]) -> Result<(), Error> {
+    match raw_sha256_digest(path) {
+        Some(expected) => check_digest(path, &expected, Sha256::digest(data).as_slice()),
+        None => Ok(()),
+    }
+}
+
+/// Decode `s` as unpadded, lowercase base32 (RFC 4648). Return `None` if
+/// `s` contains characters outside of the base32 alphabet
+fn decode_base32(s: &str) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+    let mut bytes = Vec::with_capacity(s.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in s.bytes() {
+        let value = ALPHABET.iter().position(|a| *a == c)? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
 #[derive(Clone)]
 pub struct LinkResolver {
     clients: Arc<Vec<Arc<IpfsClient>>>,
@@ -181,39 +244,53 @@ impl LinkResolverTrait for LinkResolver {
         .await?;
 
         let max_cache_file_size = self.env_vars.mappings.max_ipfs_cache_file_size;
-        let max_file_size = self.env_vars.mappings.max_ipfs_file_bytes.map(|n| n as u64);
-        restrict_file_size(&path, &stat, &max_file_size)?;
+        let max_file_size = self.env_vars.mappings.max_ipfs_file_bytes;
+        restrict_file_size(&path, &stat, &Some(max_file_size as u64))?;
 
-        let path = path.clone();
-        let this = self.clone();
         let timeout = self.timeout;
-        let logger = logger.clone();
-        let data = retry_policy(self.retry, "ipfs.cat", &logger)
-            .run(move || {
+        let data = retry_policy(self.retry, "ipfs.cat", logger)
+            .run({
                 let path = path.clone();
-                let client = client.clone();
-                let this = this.clone();
-                let logger = logger.clone();
-                async move {
-                    let data = client.cat_all(path.clone(), timeout).await?.to_vec();
-
-                    // Only cache files if they are not too large
-                    if data.len() <= max_cache_file_size {
-                        let mut cache = this.cache.lock().unwrap();
-                        if !cache.contains_key(&path) {
-                            cache.insert(path.to_owned(), data.clone());
-                        }
-                    } else {
-                        debug!(logger, "File too large for cache";
-                                    "path" => path,
-                                    "size" => data.len()
-                        );
+                move || {
+                    let path = path.clone();
+                    let client = client.clone();
+                    async move {
+                        // The stat can be wrong, so we also stop downloading
+                        // once the file turns out to be too large
+                        let data = client
+                            .cat_all(path, timeout, max_file_size)
+                            .await?
+                            .map(|data| data.to_vec());
+                        Result::<Option<Vec<u8>>, reqwest::Error>::Ok(data)
                     }
-                    Result::<Vec<u8>, reqwest::Error>::Ok(data)
                 }
             })
-            .await?;
+            .await?
+            .ok_or_else(|| {
+                anyhow!(
+                    "IPFS file {} is too large. It can be at most {} bytes",
+                    path,
+                    max_file_size
+                )
+            })?;
+
+        // Never put content into the cache that an IPFS node made up
+        if self.env_vars.mappings.verify_ipfs_content {
+            verify_content(&path, &data)?;
+        }
 
+        // Only cache files if they are not too large
+        if data.len() <= max_cache_file_size {
+            let mut cache = self.cache.lock().unwrap();
+            if !cache.contains_key(&path) {
+                cache.insert(path.to_owned(), data.clone());
+            }
+        } else {
+            debug!(logger, "File too large for cache";
+                        "path" => &path,
+                        "size" => data.len()
+            );
+        }
         Ok(data)
     }
 
@@ -235,6 +312,14 @@ impl LinkResolverTrait for LinkResolver {
 
         let mut stream = client.cat(path.to_string()).await?.fuse().boxed().compat();
 
+        // We can only tell whether the content matches the CID once we have
+        // seen all of it; values from a file that turns out not to match
+        // have been passed on already, but the stream ends with an error
+        let path = path.to_string();
+        let mut verify = raw_sha256_digest(&path)
+            .filter(|_| self.env_vars.mappings.verify_ipfs_content)
+            .map(|expected| (expected, Sha256::new()));
+
         let mut buf = BytesMut::with_capacity(1024);
 
         // Count the number of lines we've already successfully deserialized.
@@ -282,9 +367,22 @@ impl LinkResolverTrait for LinkResolver {
                         // add that so that the last line gets picked up in the next
                         // run through the loop.
                         match try_ready!(stream.poll().map_err(|e| anyhow::anyhow!("{}", e))) {
-                            Some(b) => buf.extend_from_slice(&b),
-                            None if buf.len() > 0 => buf.extend_from_slice(&[b'\n']),
-                            None => return Ok(Async::Ready(None)),
+                            Some(b) => {
+                                if let Some((_, hasher)) = verify.as_mut() {
+                                    hasher.update(&b);
+                                }
+                                buf.extend_from_slice(&b)
+                            }
+                            None => {
+                                if let Some((expected, hasher)) = verify.take() {
+                                    check_digest(&path, &expected, hasher.finalize().as_slice())?;
+                                }
+                                if buf.len() > 0 {
+                                    buf.extend_from_slice(&[b'\n'])
+                                } else {
+                                    return Ok(Async::Ready(None));
+                                }
+                            }
                         }
                     }
                 }
@@ -305,7 +403,7 @@ mod tests {
     #[tokio::test]
     async fn max_file_size() {
         let mut env_vars = EnvVars::default();
-        env_vars.mappings.max_ipfs_file_bytes = Some(200);
+        env_vars.mappings.max_ipfs_file_bytes = 200;
 
         let file: &[u8] = &[0u8; 201];
         let client = IpfsClient::localhost();
@@ -324,6 +422,13 @@ mod tests {
                 link
             )
         );
+
+        let file: &[u8] = &[1u8; 100];
+        let link = client.add(file.into()).await.unwrap().hash;
+        let data = LinkResolver::cat(&resolver, &logger, &Link { link })
+            .await
+            .unwrap();
+        assert_eq!(data, file);
     }
 
     async fn json_round_trip(text: &'static str, env_vars: EnvVars) -> Result<Vec<Value>, Error> {
@@ -359,6 +464,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn verify_raw_cid() {
+        const CID: &str = "bafkreief7nra5ytim5vp4zwazww47lztr5xeqfpvuzpgbybw4kfkcfd6ie";
+
+        assert!(verify_content(CID, b"Hello, IPFS").is_ok());
+        let err = verify_content(CID, b"Hello, IPFS!").unwrap_err();
+        assert!(err.is::<IpfsContentMismatch>());
+
+        // We can't check CIDv0 and accept whatever we received
+        assert!(raw_sha256_digest("QmWATWQ7fVPP2EFGu71UkfnqhYXDYH566qy47CnJDgvs8u").is_none());
+        assert!(verify_content(
+            "QmWATWQ7fVPP2EFGu71UkfnqhYXDYH566qy47CnJDgvs8u",
+            b"Hello, IPFS"
+        )
+        .is_ok());
+    }
+
     #[tokio::test]
     async fn ipfs_map_file_size() {
         let file = "\"small test string that trips the size restriction\"";
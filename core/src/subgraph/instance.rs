@@ -82,8 +82,8 @@ where
             if let Some(sender) = self.module_cache.get(&module_hash) {
                 sender.clone()
             } else {
-                let sender = T::spawn_mapping(
-                    module_bytes.to_owned(),
+                let sender = self.host_builder.spawn_mapping(
+                    module_bytes,
                     logger,
                     self.subgraph_id.clone(),
                     host_metrics.clone(),
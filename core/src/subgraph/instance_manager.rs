@@ -11,7 +11,12 @@ use graph::blockchain::Blockchain;
 use graph::blockchain::NodeCapabilities;
 use graph::blockchain::{BlockchainKind, TriggerFilter};
 use graph::prelude::{SubgraphInstanceManager as SubgraphInstanceManagerTrait, *};
-use graph::{blockchain::BlockchainMap, components::store::DeploymentLocator};
+use graph::{
+    blockchain::BlockchainMap,
+    components::store::{DeploymentLocator, WriteBatch},
+};
+use graph_runtime_wasm::ValidModuleCache;
+use tokio::sync::Semaphore;
 use tokio::task;
 
 pub struct SubgraphInstanceManager<S: SubgraphStore> {
@@ -23,6 +28,14 @@ pub struct SubgraphInstanceManager<S: SubgraphStore> {
     instances: SharedInstanceKeepAliveMap,
     link_resolver: Arc<dyn LinkResolver>,
     static_filters: bool,
+    /// Bounds how many runtime hosts are built concurrently while starting
+    /// subgraphs; `None` means no limit
+    host_permits: Option<(Arc<Semaphore>, usize)>,
+    /// Compiled WASM modules shared across all subgraphs
+    module_cache: Arc<ValidModuleCache>,
+    /// How the writable store of each subgraph combines queued blocks into
+    /// one transaction while the subgraph is behind the chain head
+    write_batch: WriteBatch,
 }
 
 #[async_trait]
@@ -100,6 +113,8 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
         metrics_registry: Arc<dyn MetricsRegistry>,
         link_resolver: Arc<dyn LinkResolver>,
         static_filters: bool,
+        max_concurrent_hosts: Option<usize>,
+        write_batch: WriteBatch,
     ) -> Self {
         let logger = logger_factory.component_logger("SubgraphInstanceManager", None);
         let logger_factory = logger_factory.with_parent(logger.clone());
@@ -109,10 +124,13 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
             subgraph_store,
             chains,
             manager_metrics: SubgraphInstanceManagerMetrics::new(metrics_registry.cheap_clone()),
+            module_cache: Arc::new(ValidModuleCache::new(metrics_registry.cheap_clone())),
             metrics_registry,
             instances: SharedInstanceKeepAliveMap::default(),
             link_resolver,
             static_filters,
+            host_permits: max_concurrent_hosts.map(|max| (Arc::new(Semaphore::new(max)), max)),
+            write_batch,
         }
     }
 
@@ -130,6 +148,7 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
             .cheap_clone()
             .writable(logger.clone(), deployment.id)
             .await?;
+        store.set_write_batch(self.write_batch);
 
         // Start the subgraph deployment before reading dynamic data
         // sources; if the subgraph is a graft or a copy, starting it will
@@ -241,12 +260,31 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
             chain.runtime_adapter(),
             self.link_resolver.cheap_clone(),
             subgraph_store.ens_lookup(),
+            self.module_cache.cheap_clone(),
         );
 
         let features = manifest.features.clone();
         let unified_api_version = manifest.unified_mapping_api_version()?;
+
+        // Hold one permit per host while the hosts are built so that starting
+        // many subgraphs at once does not build an unbounded number of hosts
+        // in parallel. A subgraph with more data sources than the limit
+        // takes all permits and builds its hosts one after the other.
+        let host_permits = match &self.host_permits {
+            Some((semaphore, max)) => {
+                let count = manifest.data_sources.len().min(*max);
+                let permits = semaphore
+                    .cheap_clone()
+                    .acquire_many_owned(count as u32)
+                    .await
+                    .expect("the host semaphore is never closed");
+                Some(permits)
+            }
+            None => None,
+        };
         let instance =
             SubgraphInstance::from_manifest(&logger, manifest, host_builder, host_metrics.clone())?;
+        drop(host_permits);
 
         let inputs = IndexingInputs {
             deployment: deployment.clone(),
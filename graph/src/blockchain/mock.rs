@@ -146,6 +146,14 @@ impl<C: Blockchain> DataSourceTemplate<C> for MockDataSourceTemplate {
     fn name(&self) -> &str {
         todo!()
     }
+
+    fn kind(&self) -> &str {
+        todo!()
+    }
+
+    fn validate(&self) -> Vec<Error> {
+        todo!()
+    }
 }
 
 #[derive(Clone, Default, Deserialize)]
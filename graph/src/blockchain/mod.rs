@@ -247,6 +247,10 @@ pub trait DataSourceTemplate<C: Blockchain>: Send + Sync + Debug {
     fn api_version(&self) -> semver::Version;
     fn runtime(&self) -> &[u8];
     fn name(&self) -> &str;
+    fn kind(&self) -> &str;
+
+    /// Used as part of manifest validation. If there are no errors, return an empty vector.
+    fn validate(&self) -> Vec<Error>;
 }
 
 #[async_trait]
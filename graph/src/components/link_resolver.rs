@@ -5,6 +5,7 @@ use async_trait::async_trait;
 use futures03::prelude::Stream;
 use serde_json::Value;
 use slog::Logger;
+use thiserror::Error;
 
 use crate::data::subgraph::Link;
 use crate::prelude::Error;
@@ -21,6 +22,15 @@ pub struct JsonStreamValue {
 pub type JsonValueStream =
     Pin<Box<dyn Stream<Item = Result<JsonStreamValue, Error>> + Send + 'static>>;
 
+/// The content an IPFS node returned for a file does not hash to the
+/// file's CID. This means that the node misbehaved, and callers should not
+/// treat it like a file that could not be found
+#[derive(Error, Debug)]
+#[error("the content of IPFS file {path} does not match its CID; an IPFS node returned different content")]
+pub struct IpfsContentMismatch {
+    pub path: String,
+}
+
 /// Resolves links to subgraph manifests and resources referenced by them.
 #[async_trait]
 pub trait LinkResolver: Send + Sync + 'static + Debug {
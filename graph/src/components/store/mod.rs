@@ -160,6 +160,8 @@ pub enum EntityFilter {
     EndsWithNoCase(Attribute, Value),
     NotEndsWith(Attribute, Value),
     NotEndsWithNoCase(Attribute, Value),
+    IsNull(Attribute),
+    IsNotNull(Attribute),
     ChangeBlockGte(BlockNumber),
 }
 
@@ -212,6 +214,10 @@ pub enum EntityOrder {
     Ascending(String, ValueType),
     /// Order descending by the given attribute. Use `id` as a tie-breaker
     Descending(String, ValueType),
+    /// Order by several attributes, each in its own direction. Entities
+    /// that are equal in all of them are ordered by ascending `id` unless
+    /// `id` is one of the attributes
+    Multiple(Vec<(String, OrderDirection)>),
     /// Order by the `id` of the entities
     Default,
     /// Do not order at all. This speeds up queries where we know that
@@ -219,6 +225,13 @@ pub enum EntityOrder {
     Unordered,
 }
 
+/// The direction in which to order by an attribute
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderDirection {
+    Ascending,
+    Descending,
+}
+
 /// How many entities to return, how many to skip etc.
 #[derive(Clone, Debug, PartialEq)]
 pub struct EntityRange {
@@ -394,6 +407,10 @@ pub struct EntityQuery {
 
     pub query_id: Option<String>,
 
+    /// When set, the query is abandoned, and the database stops running
+    /// it, once this handle is canceled
+    pub cancel: Option<CancelHandle>,
+
     _force_use_of_new: (),
 }
 
@@ -412,6 +429,7 @@ impl EntityQuery {
             range: EntityRange::first(100),
             logger: None,
             query_id: None,
+            cancel: None,
             _force_use_of_new: (),
         }
     }
@@ -441,6 +459,11 @@ impl EntityQuery {
         self
     }
 
+    pub fn cancel_on(mut self, handle: CancelHandle) -> Self {
+        self.cancel = Some(handle);
+        self
+    }
+
     pub fn simplify(mut self) -> Self {
         // If there is one window, with one id, in a direct relation to the
         // entities, we can simplify the query by changing the filter and
@@ -753,6 +776,31 @@ pub enum UnfailOutcome {
     Unfailed,
 }
 
+/// How the background writer of a `WritableStore` combines blocks that
+/// are waiting in its write queue into one database transaction. Blocks
+/// are never held back to form a batch; they are only combined when they
+/// are already waiting, e.g., because a subgraph is far behind the chain
+/// head
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WriteBatch {
+    /// The largest number of blocks that are written in one transaction
+    pub size: usize,
+    /// Only blocks that were queued at most this long after the first
+    /// block of a batch are added to the batch
+    pub duration: Duration,
+}
+
+impl Default for WriteBatch {
+    /// The batching that `GRAPH_STORE_WRITE_BATCH_SIZE` and
+    /// `GRAPH_STORE_WRITE_BATCH_DURATION` configure
+    fn default() -> Self {
+        WriteBatch {
+            size: ENV_VARS.store.write_batch_size,
+            duration: ENV_VARS.store.write_batch_duration,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct StoredDynamicDataSource {
     pub name: String,
@@ -236,6 +236,10 @@ pub trait WritableStore: Send + Sync + 'static {
 
     /// Wait for the background writer to finish processing its queue
     async fn flush(&self) -> Result<(), StoreError>;
+
+    /// Change how the background writer combines queued blocks into one
+    /// transaction. This has no effect when writes are not queued
+    fn set_write_batch(&self, write_batch: WriteBatch);
 }
 
 #[async_trait]
@@ -62,6 +62,7 @@ pub enum QueryExecutionError {
     EntityParseError(String),
     StoreError(CloneableAnyhowError),
     Timeout,
+    Cancelled,
     EmptySelectionSet(String),
     AmbiguousDerivedFromResult(Pos, String, String, String),
     Unimplemented(String),
@@ -83,6 +84,7 @@ pub enum QueryExecutionError {
     SubgraphManifestResolveError(Arc<SubgraphManifestResolveError>),
     InvalidSubgraphManifest,
     ResultTooBig(usize, usize),
+    AmbiguousResult(String),
 }
 
 impl QueryExecutionError {
@@ -125,6 +127,7 @@ impl QueryExecutionError {
             | EntityParseError(_)
             | StoreError(_)
             | Timeout
+            | Cancelled
             | EnumCoercionError(_, _, _, _, _)
             | ScalarCoercionError(_, _, _, _)
             | AmbiguousDerivedFromResult(_, _, _, _)
@@ -139,7 +142,8 @@ impl QueryExecutionError {
             | SubgraphManifestResolveError(_)
             | InvalidSubgraphManifest
             | ValidationError(_, _)
-            | ResultTooBig(_, _) => false,
+            | ResultTooBig(_, _)
+            | AmbiguousResult(_) => false,
         }
     }
 }
@@ -247,6 +251,7 @@ impl fmt::Display for QueryExecutionError {
                 write!(f, "Store error: {}", e.0)
             }
             Timeout => write!(f, "Query timed out"),
+            Cancelled => write!(f, "Query was cancelled"),
             EmptySelectionSet(entity_type) => {
                 write!(f, "Selection set for type `{}` is empty", entity_type)
             }
@@ -287,6 +292,7 @@ impl fmt::Display for QueryExecutionError {
             SubgraphManifestResolveError(e) => write!(f, "failed to resolve subgraph manifest: {}", e),
             InvalidSubgraphManifest => write!(f, "invalid subgraph manifest file"),
             ResultTooBig(actual, limit) => write!(f, "the result size of {} is larger than the allowed limit of {}", actual, limit),
+            AmbiguousResult(entity) => write!(f, "expected at most one {} to match the filter, but found more than one", entity),
         }
     }
 }
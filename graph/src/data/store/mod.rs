@@ -571,6 +571,12 @@ impl Entity {
         self.0.contains_key(key)
     }
 
+    /// Iterate over the attributes of this entity and their values in no
+    /// particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&Attribute, &Value)> {
+        self.0.iter()
+    }
+
     // This collects the entity into an ordered vector so that it can be iterated deterministically.
     pub fn sorted(self) -> Vec<(String, Value)> {
         let mut v: Vec<_> = self.0.into_iter().collect();
@@ -964,3 +970,35 @@ fn fmt_debug() {
     let bi = Value::BigInt(scalar::BigInt::from(-17i32));
     assert_eq!("BigInt(-17)", format!("{:?}", bi));
 }
+
+#[test]
+fn entity_stable_hash_ignores_attribute_order() {
+    use stable_hash_legacy::crypto::SetHasher;
+    use stable_hash_legacy::utils::stable_hash;
+
+    let attrs: Vec<(String, Value)> = (0..32)
+        .map(|i| {
+            let value = match i % 4 {
+                0 => Value::from(format!("value{}", i)),
+                1 => Value::Int(i),
+                2 => Value::BigInt(scalar::BigInt::from(-i)),
+                _ => Value::BigDecimal(scalar::BigDecimal::from(i as f64 / 8.0)),
+            };
+            (format!("attr{}", i), value)
+        })
+        .collect();
+
+    let mut forward = Entity::new();
+    for (key, value) in attrs.iter().cloned() {
+        forward.set(key, value);
+    }
+    let mut backward = Entity::new();
+    for (key, value) in attrs.into_iter().rev() {
+        backward.set(key, value);
+    }
+
+    assert_eq!(
+        stable_hash::<SetHasher, _>(&forward),
+        stable_hash::<SetHasher, _>(&backward)
+    );
+}
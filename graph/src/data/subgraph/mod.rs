@@ -19,14 +19,17 @@ use serde::ser;
 use serde_yaml;
 use slog::{debug, info, Logger};
 use stable_hash_legacy::prelude::*;
-use std::{collections::BTreeSet, marker::PhantomData};
+use std::{
+    collections::{BTreeSet, HashMap},
+    marker::PhantomData,
+};
 use thiserror::Error;
 use wasmparser;
 use web3::types::Address;
 
 use crate::data::store::Entity;
 use crate::data::{
-    schema::{Schema, SchemaImportError, SchemaValidationError},
+    schema::{Schema, SchemaImportError, SchemaReference, SchemaValidationError},
     subgraph::features::validate_subgraph_features,
 };
 use crate::prelude::{r, CheapClone, ENV_VARS};
@@ -349,6 +352,12 @@ pub enum SubgraphManifestValidationError {
     FeatureValidationError(#[from] SubgraphFeatureValidationError),
     #[error("data source {0} is invalid: {1}")]
     DataSourceValidation(String, Error),
+    #[error("data source template {0} is invalid: {1}")]
+    DataSourceTemplateValidation(String, Error),
+    #[error("data source template {0} has kind `{1}` but no data source has that kind")]
+    TemplateKindNotDeclared(String, String),
+    #[error("failed to resolve subgraph manifest: {0}")]
+    ResolveError(#[from] SubgraphManifestResolveError),
 }
 
 #[derive(Error, Debug)]
@@ -538,48 +547,7 @@ impl<C: Blockchain> UnvalidatedSubgraphManifest<C> {
     ) -> Result<SubgraphManifest<C>, Vec<SubgraphManifestValidationError>> {
         let (schemas, _) = self.0.schema.resolve_schema_references(store.clone());
 
-        let mut errors: Vec<SubgraphManifestValidationError> = vec![];
-
-        // Validate that the manifest has at least one data source
-        if self.0.data_sources.is_empty() {
-            errors.push(SubgraphManifestValidationError::NoDataSources);
-        }
-
-        for ds in &self.0.data_sources {
-            errors.extend(ds.validate().into_iter().map(|e| {
-                SubgraphManifestValidationError::DataSourceValidation(ds.name().to_owned(), e)
-            }));
-        }
-
-        // For API versions newer than 0.0.5, validate that all mappings uses the same api_version
-        if let Err(different_api_versions) = self.0.unified_mapping_api_version() {
-            errors.push(different_api_versions.into());
-        };
-
-        let mut networks = self
-            .0
-            .data_sources
-            .iter()
-            .filter_map(|d| d.network().map(|n| n.to_string()))
-            .collect::<Vec<String>>();
-        networks.sort();
-        networks.dedup();
-        match networks.len() {
-            0 => errors.push(SubgraphManifestValidationError::EthereumNetworkRequired),
-            1 => (),
-            _ => errors.push(SubgraphManifestValidationError::MultipleEthereumNetworks),
-        }
-
-        self.0
-            .schema
-            .validate(&schemas)
-            .err()
-            .into_iter()
-            .for_each(|schema_errors| {
-                errors.push(SubgraphManifestValidationError::SchemaValidationError(
-                    schema_errors,
-                ));
-            });
+        let mut errors = self.0.validate_without_store(&schemas);
 
         if let Some(graft) = &self.0.graft {
             if ENV_VARS.disable_grafts {
@@ -592,13 +560,6 @@ impl<C: Blockchain> UnvalidatedSubgraphManifest<C> {
             }
         }
 
-        // Validate subgraph feature usage and declaration.
-        if self.0.spec_version >= SPEC_VERSION_0_0_4 {
-            if let Err(feature_validation_error) = validate_subgraph_features(&self.0) {
-                errors.push(feature_validation_error.into())
-            }
-        }
-
         match errors.is_empty() {
             true => Ok(self.0),
             false => Err(errors),
@@ -636,6 +597,115 @@ impl<C: Blockchain> SubgraphManifest<C> {
             .map_err(SubgraphManifestResolveError::ResolveError)
     }
 
+    /// Resolve the manifest of the subgraph `id` and check it for errors
+    /// without deploying it or writing anything to the store, for example
+    /// to catch mistakes in a manifest before it gets deployed. Besides the
+    /// checks that `UnvalidatedSubgraphManifest::validate` does, this also
+    /// makes sure that every template has a kind that one of the data
+    /// sources uses. Since there is no store, schema imports and the graft
+    /// base are not checked. An empty result means the manifest is valid
+    pub async fn validate(
+        id: DeploymentHash,
+        resolver: &Arc<dyn LinkResolver>,
+        logger: &Logger,
+        max_spec_version: semver::Version,
+    ) -> Vec<SubgraphManifestValidationError> {
+        let raw: serde_yaml::Mapping = match resolver.cat(logger, &id.to_ipfs_link()).await {
+            Ok(bytes) => match serde_yaml::from_slice(&bytes) {
+                Ok(raw) => raw,
+                Err(e) => return vec![SubgraphManifestResolveError::from(e).into()],
+            },
+            Err(e) => return vec![SubgraphManifestResolveError::ResolveError(e).into()],
+        };
+
+        let manifest =
+            match Self::resolve_from_raw(id, raw, resolver, logger, max_spec_version).await {
+                Ok(manifest) => manifest,
+                Err(e) => return vec![e.into()],
+            };
+
+        let mut errors = manifest.validate_without_store(&HashMap::new());
+
+        let kinds: BTreeSet<_> = manifest.data_sources.iter().map(|ds| ds.kind()).collect();
+        for template in &manifest.templates {
+            if !kinds.contains(template.kind()) {
+                errors.push(SubgraphManifestValidationError::TemplateKindNotDeclared(
+                    template.name().to_owned(),
+                    template.kind().to_owned(),
+                ));
+            }
+        }
+
+        errors
+    }
+
+    /// Run all checks on the manifest that do not need access to a store,
+    /// i.e., everything but checking schema imports and the graft base.
+    /// Imported schemas must be passed in `schemas`
+    fn validate_without_store(
+        &self,
+        schemas: &HashMap<SchemaReference, Arc<Schema>>,
+    ) -> Vec<SubgraphManifestValidationError> {
+        let mut errors: Vec<SubgraphManifestValidationError> = vec![];
+
+        // Validate that the manifest has at least one data source
+        if self.data_sources.is_empty() {
+            errors.push(SubgraphManifestValidationError::NoDataSources);
+        }
+
+        for ds in &self.data_sources {
+            errors.extend(ds.validate().into_iter().map(|e| {
+                SubgraphManifestValidationError::DataSourceValidation(ds.name().to_owned(), e)
+            }));
+        }
+
+        for template in &self.templates {
+            errors.extend(template.validate().into_iter().map(|e| {
+                SubgraphManifestValidationError::DataSourceTemplateValidation(
+                    template.name().to_owned(),
+                    e,
+                )
+            }));
+        }
+
+        // For API versions newer than 0.0.5, validate that all mappings uses the same api_version
+        if let Err(different_api_versions) = self.unified_mapping_api_version() {
+            errors.push(different_api_versions.into());
+        };
+
+        let mut networks = self
+            .data_sources
+            .iter()
+            .filter_map(|d| d.network().map(|n| n.to_string()))
+            .collect::<Vec<String>>();
+        networks.sort();
+        networks.dedup();
+        match networks.len() {
+            0 => errors.push(SubgraphManifestValidationError::EthereumNetworkRequired),
+            1 => (),
+            _ => errors.push(SubgraphManifestValidationError::MultipleEthereumNetworks),
+        }
+
+        self.schema
+            .validate(schemas)
+            .err()
+            .into_iter()
+            .for_each(|schema_errors| {
+                errors.push(SubgraphManifestValidationError::SchemaValidationError(
+                    schema_errors,
+                ));
+            });
+
+        // Validate subgraph feature usage and declaration.
+        if self.spec_version >= SPEC_VERSION_0_0_4 {
+            if let Err(feature_validation_error) = validate_subgraph_features(self) {
+                errors.push(feature_validation_error.into())
+            }
+        }
+
+        errors
+    }
+
     pub fn network_name(&self) -> String {
         // Assume the manifest has been validated, ensuring network names are homogenous
         self.data_sources
@@ -10,7 +10,7 @@ pub struct EnvVarsMapping {
     /// kilobytes). The default value is 10 megabytes.
     pub entity_cache_size: usize,
     /// Set by the environment variable `GRAPH_MAX_API_VERSION`. The default
-    /// value is `0.0.6`.
+    /// value is `0.0.7`.
     pub max_api_version: Version,
     /// Set by the environment variable `GRAPH_MAPPING_HANDLER_TIMEOUT`
     /// (expressed in seconds). No default is provided.
@@ -40,14 +40,19 @@ pub struct EnvVarsMapping {
     /// Sets the `ipfs.cat` file size limit.
     ///
     /// Set by the environment variable `GRAPH_MAX_IPFS_FILE_BYTES` (expressed in
-    /// bytes). No default value is provided.
+    /// bytes). The default value is 256MiB.
     ///
     /// FIXME: Having an env variable here is a problem for consensus.
     /// Index Nodes should not disagree on whether the file should be read.
-    pub max_ipfs_file_bytes: Option<usize>,
+    pub max_ipfs_file_bytes: usize,
     /// Set by the flag `GRAPH_ALLOW_NON_DETERMINISTIC_IPFS`. Off by
     /// default.
     pub allow_non_deterministic_ipfs: bool,
+    /// Check that the content `ipfs.cat` and `ipfs.map` receive matches
+    /// the requested CID where that is possible.
+    ///
+    /// Set by the flag `GRAPH_IPFS_VERIFY_CONTENT`. On by default.
+    pub verify_ipfs_content: bool,
 }
 
 // This does not print any values avoid accidentally leaking any sensitive env vars
@@ -70,8 +75,9 @@ impl From<InnerMappingHandlers> for EnvVarsMapping {
             max_ipfs_cache_size: x.max_ipfs_cache_size,
             ipfs_timeout: Duration::from_secs(x.ipfs_timeout_in_secs),
             max_ipfs_map_file_size: x.max_ipfs_map_file_size.0,
-            max_ipfs_file_bytes: x.max_ipfs_file_bytes,
+            max_ipfs_file_bytes: x.max_ipfs_file_bytes.0,
             allow_non_deterministic_ipfs: x.allow_non_deterministic_ipfs.0,
+            verify_ipfs_content: x.verify_ipfs_content.0,
         }
     }
 }
@@ -96,8 +102,10 @@ pub struct InnerMappingHandlers {
     ipfs_timeout_in_secs: u64,
     #[envconfig(from = "GRAPH_MAX_IPFS_MAP_FILE_SIZE", default = "")]
     max_ipfs_map_file_size: WithDefaultUsize<usize, { 256 * 1024 * 1024 }>,
-    #[envconfig(from = "GRAPH_MAX_IPFS_FILE_BYTES")]
-    max_ipfs_file_bytes: Option<usize>,
+    #[envconfig(from = "GRAPH_MAX_IPFS_FILE_BYTES", default = "")]
+    max_ipfs_file_bytes: WithDefaultUsize<usize, { 256 * 1024 * 1024 }>,
     #[envconfig(from = "GRAPH_ALLOW_NON_DETERMINISTIC_IPFS", default = "false")]
     allow_non_deterministic_ipfs: EnvVarBoolean,
+    #[envconfig(from = "GRAPH_IPFS_VERIFY_CONTENT", default = "true")]
+    verify_ipfs_content: EnvVarBoolean,
 }
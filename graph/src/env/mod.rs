@@ -168,6 +168,12 @@ pub struct EnvVars {
     /// Set by the environment variable `GRAPH_SUBGRAPH_MAX_DATA_SOURCES`. No
     /// default value is provided.
     pub subgraph_max_data_sources: Option<usize>,
+    /// Maximum number of runtime hosts that are built concurrently while
+    /// starting subgraphs; starts that would exceed it wait for a slot.
+    ///
+    /// Set by the environment variable `GRAPH_MAX_CONCURRENT_HOSTS`. No
+    /// default value is provided.
+    pub max_concurrent_hosts: Option<usize>,
     /// Keep deterministic errors non-fatal even if the subgraph is pending.
     /// Used for testing Graph Node itself.
     ///
@@ -249,6 +255,7 @@ impl EnvVars {
             kill_if_unresponsive: inner.kill_if_unresponsive.0,
             poi_access_token: inner.poi_access_token,
             subgraph_max_data_sources: inner.subgraph_max_data_sources,
+            max_concurrent_hosts: inner.max_concurrent_hosts,
             disable_fail_fast: inner.disable_fail_fast.0,
             subgraph_error_retry_ceil: Duration::from_secs(inner.subgraph_error_retry_ceil_in_secs),
             enable_select_by_specific_attributes: inner.enable_select_by_specific_attributes.0,
@@ -348,6 +355,8 @@ struct Inner {
     poi_access_token: Option<String>,
     #[envconfig(from = "GRAPH_SUBGRAPH_MAX_DATA_SOURCES")]
     subgraph_max_data_sources: Option<usize>,
+    #[envconfig(from = "GRAPH_MAX_CONCURRENT_HOSTS")]
+    max_concurrent_hosts: Option<usize>,
     #[envconfig(from = "GRAPH_DISABLE_FAIL_FAST", default = "false")]
     disable_fail_fast: EnvVarBoolean,
     #[envconfig(from = "GRAPH_SUBGRAPH_ERROR_RETRY_CEIL_SECS", default = "1800")]
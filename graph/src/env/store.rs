@@ -92,6 +92,47 @@ pub struct EnvVarsStore {
     /// Set by the environment variable `GRAPH_STORE_CONNECTION_IDLE_TIMEOUT`
     /// (expressed in seconds). The default value is 600s.
     pub connection_idle_timeout: Duration,
+    /// Whether to check that a connection is still usable by running
+    /// `SELECT 1` on it before handing it out from the pool. Connections
+    /// that fail the check are discarded.
+    ///
+    /// Set by the flag `GRAPH_STORE_CONNECTION_TEST_ON_CHECK_OUT`. Enabled
+    /// by default.
+    pub connection_test_on_check_out: bool,
+    /// How many of the most recent block pointers to remember for each
+    /// deployment so that reverts to one of them can be validated and
+    /// done in one step.
+    ///
+    /// Set by the environment variable `GRAPH_STORE_BLOCK_HISTORY_SIZE`.
+    /// The default value is 250.
+    pub block_history_size: i32,
+    /// When `find_many` is asked to look up entities of more than this
+    /// many entity types, look up each type in parallel with its own
+    /// database connection. A value of `0` turns parallel lookups off.
+    ///
+    /// Set by the environment variable
+    /// `GRAPH_STORE_FIND_MANY_PARALLEL_THRESHOLD`. The default value is 0.
+    pub find_many_parallel_threshold: usize,
+    /// The longest chain of grafts we allow; deploying a subgraph that is
+    /// grafted onto a base that would make the chain of graft bases longer
+    /// than this fails.
+    ///
+    /// Set by the environment variable `GRAPH_MAX_GRAFT_DEPTH`. The default
+    /// value is 50.
+    pub max_graft_depth: usize,
+    /// Entity queries that take longer than this are logged at `warn` level
+    /// and counted in the `store_slow_query_total` metric.
+    ///
+    /// Set by the environment variable `GRAPH_STORE_SLOW_QUERY_THRESHOLD`
+    /// (expressed in milliseconds). Not set by default, which turns slow
+    /// query reporting off.
+    pub slow_query_threshold: Option<Duration>,
+    /// How many layouts each shard keeps in its layout cache. When the
+    /// cache is full, the least recently used layout is evicted.
+    ///
+    /// Set by the environment variable `GRAPH_STORE_LAYOUT_CACHE_SIZE`.
+    /// The default value is 1000.
+    pub layout_cache_size: usize,
 
     /// The size of the write queue; this many blocks can be buffered for
     /// writing before calls to transact block operations will block.
@@ -99,6 +140,25 @@ pub struct EnvVarsStore {
     /// done synchronously.
     pub write_queue_size: usize,
 
+    /// How many queued blocks the background writer combines into one
+    /// database transaction. Blocks are only combined when they are
+    /// already waiting in the write queue, which happens when a subgraph
+    /// processes blocks faster than they can be written, e.g., while it is
+    /// far behind the chain head. Close to the head, every block is still
+    /// written as soon as it has been processed.
+    ///
+    /// Set by the environment variable `GRAPH_STORE_WRITE_BATCH_SIZE`. The
+    /// default value is 1, which writes every block in its own transaction.
+    pub write_batch_size: usize,
+
+    /// Bounds how much processing time one write batch covers: only blocks
+    /// that were queued within this long after the first block of a batch
+    /// are added to it.
+    ///
+    /// Set by the environment variable `GRAPH_STORE_WRITE_BATCH_DURATION`
+    /// (expressed in seconds). The default value is 60s.
+    pub write_batch_duration: Duration,
+
     /// This is just in case new behavior causes issues. This can be removed
     /// once the new behavior has run in the hosted service for a few days
     /// without issues.
@@ -143,7 +203,15 @@ impl From<InnerStore> for EnvVarsStore {
             connection_timeout: Duration::from_millis(x.connection_timeout_in_millis),
             connection_min_idle: x.connection_min_idle,
             connection_idle_timeout: Duration::from_secs(x.connection_idle_timeout_in_secs),
+            connection_test_on_check_out: x.connection_test_on_check_out.0,
+            block_history_size: x.block_history_size,
+            find_many_parallel_threshold: x.find_many_parallel_threshold,
+            max_graft_depth: x.max_graft_depth,
+            slow_query_threshold: x.slow_query_threshold_in_millis.map(Duration::from_millis),
+            layout_cache_size: x.layout_cache_size,
             write_queue_size: x.write_queue_size,
+            write_batch_size: x.write_batch_size.max(1),
+            write_batch_duration: Duration::from_secs(x.write_batch_duration_in_secs),
             disable_error_for_toplevel_parents: x.disable_error_for_toplevel_parents.0,
         }
     }
@@ -188,8 +256,24 @@ pub struct InnerStore {
     connection_min_idle: Option<u32>,
     #[envconfig(from = "GRAPH_STORE_CONNECTION_IDLE_TIMEOUT", default = "600")]
     connection_idle_timeout_in_secs: u64,
+    #[envconfig(from = "GRAPH_STORE_CONNECTION_TEST_ON_CHECK_OUT", default = "true")]
+    connection_test_on_check_out: EnvVarBoolean,
+    #[envconfig(from = "GRAPH_STORE_BLOCK_HISTORY_SIZE", default = "250")]
+    block_history_size: i32,
+    #[envconfig(from = "GRAPH_STORE_FIND_MANY_PARALLEL_THRESHOLD", default = "0")]
+    find_many_parallel_threshold: usize,
+    #[envconfig(from = "GRAPH_MAX_GRAFT_DEPTH", default = "50")]
+    max_graft_depth: usize,
+    #[envconfig(from = "GRAPH_STORE_SLOW_QUERY_THRESHOLD")]
+    slow_query_threshold_in_millis: Option<u64>,
+    #[envconfig(from = "GRAPH_STORE_LAYOUT_CACHE_SIZE", default = "1000")]
+    layout_cache_size: usize,
     #[envconfig(from = "GRAPH_STORE_WRITE_QUEUE", default = "5")]
     write_queue_size: usize,
+    #[envconfig(from = "GRAPH_STORE_WRITE_BATCH_SIZE", default = "1")]
+    write_batch_size: usize,
+    #[envconfig(from = "GRAPH_STORE_WRITE_BATCH_DURATION", default = "60")]
+    write_batch_duration_in_secs: u64,
     #[envconfig(from = "GRAPH_DISABLE_ERROR_FOR_TOPLEVEL_PARENTS", default = "false")]
     disable_error_for_toplevel_parents: EnvVarBoolean,
 }
@@ -1,7 +1,7 @@
 use crate::prelude::CheapClone;
 use anyhow::Error;
-use bytes::Bytes;
-use futures03::Stream;
+use bytes::{Bytes, BytesMut};
+use futures03::{Stream, StreamExt};
 use http::header::CONTENT_LENGTH;
 use http::Uri;
 use reqwest::multipart;
@@ -70,12 +70,28 @@ impl IpfsClient {
             .await
     }
 
-    /// Download the entire contents.
-    pub async fn cat_all(&self, cid: String, timeout: Duration) -> Result<Bytes, reqwest::Error> {
-        self.call(self.url("cat", cid), None, Some(timeout))
+    /// Download the entire contents. If the contents turn out to be larger
+    /// than `max_file_size`, stop downloading and return `None`.
+    pub async fn cat_all(
+        &self,
+        cid: String,
+        timeout: Duration,
+        max_file_size: usize,
+    ) -> Result<Option<Bytes>, reqwest::Error> {
+        let mut stream = self
+            .call(self.url("cat", cid), None, Some(timeout))
             .await?
-            .bytes()
-            .await
+            .bytes_stream();
+
+        let mut data = BytesMut::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            if data.len() + chunk.len() > max_file_size {
+                return Ok(None);
+            }
+            data.extend_from_slice(&chunk);
+        }
+        Ok(Some(data.freeze()))
     }
 
     pub async fn cat(
@@ -102,7 +102,9 @@ pub mod prelude {
     pub use crate::components::graphql::{
         GraphQlRunner, QueryLoadManager, SubscriptionResultFuture,
     };
-    pub use crate::components::link_resolver::{JsonStreamValue, JsonValueStream, LinkResolver};
+    pub use crate::components::link_resolver::{
+        IpfsContentMismatch, JsonStreamValue, JsonValueStream, LinkResolver,
+    };
     pub use crate::components::metrics::{
         aggregate::Aggregate, stopwatch::StopwatchMetrics, Collector, Counter, CounterVec, Gauge,
         GaugeVec, Histogram, HistogramOpts, HistogramVec, MetricsRegistry, Opts, PrometheusError,
@@ -117,8 +119,8 @@ pub mod prelude {
         AttributeNames, BlockNumber, CachedEthereumCall, ChainStore, ChildMultiplicity,
         EntityCache, EntityChange, EntityChangeOperation, EntityCollection, EntityFilter,
         EntityKey, EntityLink, EntityModification, EntityOperation, EntityOrder, EntityQuery,
-        EntityRange, EntityWindow, EthereumCallCache, ParentLink, PartialBlockPtr, PoolWaitStats,
-        QueryStore, QueryStoreManager, StoreError, StoreEvent, StoreEventStream,
+        EntityRange, EntityWindow, EthereumCallCache, OrderDirection, ParentLink, PartialBlockPtr,
+        PoolWaitStats, QueryStore, QueryStoreManager, StoreError, StoreEvent, StoreEventStream,
         StoreEventStreamBox, SubgraphStore, UnfailOutcome, WindowAttribute, BLOCK_NUMBER_MAX,
     };
     pub use crate::components::subgraph::{
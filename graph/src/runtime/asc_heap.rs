@@ -44,6 +44,23 @@ where
     AscPtr::alloc_obj(obj, heap, gas)
 }
 
+/// Instantiate `rust_obj` as an Asc object of class `C` if it is `Some`.
+/// Returns a pointer to the Asc heap, which is null if `rust_obj` is `None`.
+pub fn asc_new_or_null<C, T: ?Sized, H: AscHeap + ?Sized>(
+    heap: &mut H,
+    rust_obj: Option<&T>,
+    gas: &GasCounter,
+) -> Result<AscPtr<C>, DeterministicHostError>
+where
+    C: AscType + AscIndexId,
+    T: ToAscObj<C>,
+{
+    match rust_obj {
+        Some(rust_obj) => asc_new(heap, rust_obj, gas),
+        None => Ok(AscPtr::null()),
+    }
+}
+
 ///  Read the rust representation of an Asc object of class `C`.
 ///
 ///  This operation is expensive as it requires a call to `get` for every
@@ -60,6 +77,23 @@ where
     T::from_asc_obj(asc_ptr.read_ptr(heap, gas)?, heap, gas)
 }
 
+///  Read the rust representation of an Asc object of class `C`, treating
+///  a null pointer as `None`.
+pub fn asc_get_optional<T, C, H: AscHeap + ?Sized>(
+    heap: &H,
+    asc_ptr: AscPtr<C>,
+    gas: &GasCounter,
+) -> Result<Option<T>, DeterministicHostError>
+where
+    C: AscType + AscIndexId,
+    T: FromAscObj<C>,
+{
+    if asc_ptr.is_null() {
+        return Ok(None);
+    }
+    asc_get(heap, asc_ptr, gas).map(Some)
+}
+
 pub fn try_asc_get<T, C, H: AscHeap + ?Sized>(
     heap: &H,
     asc_ptr: AscPtr<C>,
@@ -1,13 +1,17 @@
+use rand::Rng;
 use std::time::Duration;
 
 /// Facilitate sleeping with an exponential backoff. Sleep durations will
 /// increase by a factor of 2 from `base` until they reach `ceiling`, at
 /// which point any call to `sleep` or `sleep_async` will sleep for
-/// `ceiling`
+/// `ceiling`. With `with_jitter`, each sleep is additionally lengthened by
+/// a random fraction of the delay, but never beyond `ceiling`, so that many
+/// clients backing off at the same time do not retry in lockstep
 pub struct ExponentialBackoff {
     pub attempt: u64,
     base: Duration,
     ceiling: Duration,
+    jitter: f64,
 }
 
 impl ExponentialBackoff {
@@ -16,9 +20,17 @@ impl ExponentialBackoff {
             attempt: 0,
             base,
             ceiling,
+            jitter: 0.0,
         }
     }
 
+    /// Add up to `jitter * delay` of random extra time to every sleep.
+    /// `jitter` is clamped to `[0, 1]`
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
     /// Record that we made an attempt and sleep for the appropriate amount
     /// of time. Do not use this from async contexts since it uses
     /// `thread::sleep`
@@ -33,7 +45,10 @@ impl ExponentialBackoff {
     }
 
     pub fn delay(&self) -> Duration {
-        let mut delay = self.base.saturating_mul(1 << self.attempt);
+        let factor = 1u32
+            .checked_shl(self.attempt.min(u32::MAX as u64) as u32)
+            .unwrap_or(u32::MAX);
+        let mut delay = self.base.saturating_mul(factor);
         if delay > self.ceiling {
             delay = self.ceiling;
         }
@@ -41,12 +56,25 @@ impl ExponentialBackoff {
     }
 
     fn next_attempt(&mut self) -> Duration {
-        let delay = self.delay();
+        let mut delay = self.delay();
+        if self.jitter > 0.0 {
+            delay += delay.mul_f64(rand::thread_rng().gen_range(0.0..self.jitter));
+        }
         self.attempt += 1;
-        delay
+        delay.min(self.ceiling)
     }
 
     pub fn reset(&mut self) {
         self.attempt = 0;
     }
 }
+
+#[test]
+fn jitter_stays_below_ceiling() {
+    let ceiling = Duration::from_millis(100);
+    let mut backoff = ExponentialBackoff::new(Duration::from_millis(10), ceiling).with_jitter(1.0);
+    for _ in 0..20 {
+        assert!(backoff.next_attempt() <= ceiling);
+    }
+    assert_eq!(ceiling, backoff.delay());
+}
@@ -86,6 +86,14 @@ impl<T: Clone> BoundedQueue<T> {
         item.clone()
     }
 
+    /// Return copies of up to `n` items from the front of the queue,
+    /// oldest first, without removing them. Unlike `peek`, this does not
+    /// block and returns an empty vector if the queue is empty
+    pub fn peek_many(&self, n: usize) -> Vec<T> {
+        let queue = self.queue.lock().unwrap();
+        queue.iter().take(n).cloned().collect()
+    }
+
     /// Push an item into the queue. If the queue is currently full this method
     /// blocks until an item is available
     pub async fn push(&self, item: T) {
@@ -1,4 +1,5 @@
 use crate::ext::futures::FutureExtension;
+use futures03::future::BoxFuture;
 use futures03::{Future, FutureExt, TryFutureExt};
 use slog::{debug, trace, warn, Logger};
 use std::fmt::Debug;
@@ -136,6 +137,7 @@ where
         RetryConfigWithTimeout {
             inner: self,
             timeout,
+            before_attempt: None,
         }
     }
 
@@ -145,9 +147,12 @@ where
     }
 }
 
+type BeforeAttempt = Arc<dyn Fn() -> BoxFuture<'static, ()> + Send + Sync>;
+
 pub struct RetryConfigWithTimeout<I, E> {
     inner: RetryConfig<I, E>,
     timeout: Duration,
+    before_attempt: Option<BeforeAttempt>,
 }
 
 impl<I, E> RetryConfigWithTimeout<I, E>
@@ -155,6 +160,19 @@ where
     I: Debug + Send + 'static,
     E: Debug + Send + Send + Sync + 'static,
 {
+    /// Wait for the future returned by `ready` before every attempt. The
+    /// timeout for an attempt only starts once that future has completed,
+    /// so that time spent waiting, e.g., for a rate limit does not count
+    /// against the attempt.
+    pub fn before_attempt<F, R>(mut self, ready: F) -> Self
+    where
+        F: Fn() -> R + Send + Sync + 'static,
+        R: Future<Output = ()> + Send + 'static,
+    {
+        self.before_attempt = Some(Arc::new(move || ready().boxed()));
+        self
+    }
+
     /// Rerun the provided function as many times as needed.
     pub fn run<F, R>(self, mut try_it: F) -> impl Future<Output = Result<I, TimeoutError<E>>>
     where
@@ -168,6 +186,7 @@ where
         let warn_after = self.inner.warn_after;
         let limit_opt = self.inner.limit.unwrap(&operation_name, "limit");
         let timeout = self.timeout;
+        let before_attempt = self.before_attempt;
 
         trace!(logger, "Run with retry: {}", operation_name);
 
@@ -179,11 +198,19 @@ where
             warn_after,
             limit_opt,
             move || {
-                try_it()
-                    .timeout(timeout)
-                    .map_err(|_| TimeoutError::Elapsed)
-                    .and_then(|res| std::future::ready(res.map_err(TimeoutError::Inner)))
-                    .boxed()
+                let ready = before_attempt.as_ref().map(|ready| ready());
+                let attempt = try_it();
+                async move {
+                    if let Some(ready) = ready {
+                        ready.await;
+                    }
+                    attempt
+                        .timeout(timeout)
+                        .await
+                        .map_err(|_| TimeoutError::Elapsed)?
+                        .map_err(TimeoutError::Inner)
+                }
+                .boxed()
             },
         )
     }
@@ -545,4 +572,26 @@ mod tests {
 
         assert_eq!(result, Ok(10));
     }
+
+    #[test]
+    fn before_attempt_does_not_count_against_timeout() {
+        let logger = Logger::root(::slog::Discard, o!());
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let result = runtime.block_on({
+            retry("test", &logger)
+                .no_logging()
+                .limit(1)
+                .timeout_millis(50)
+                .before_attempt(|| tokio::time::sleep(Duration::from_millis(200)))
+                .run(move || async {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    Ok::<_, ()>(1)
+                })
+        });
+        assert_eq!(result.unwrap(), 1);
+    }
 }
@@ -5,20 +5,41 @@ use prometheus::Gauge;
 
 use crate::prelude::ENV_VARS;
 
+/// The number of buckets in the histogram that bins keep when percentiles
+/// are enabled. Bucket `0` counts durations below 1ms, bucket `k` counts
+/// durations in `[2^(k-1)ms, 2^k ms)`, and the last bucket also counts
+/// everything that is even longer
+const HISTOGRAM_BUCKETS: usize = 24;
+
+/// The histogram bucket for `duration`
+fn bucket(duration: Duration) -> usize {
+    let millis = duration.as_millis();
+    let bucket = (u128::BITS - millis.leading_zeros()) as usize;
+    bucket.min(HISTOGRAM_BUCKETS - 1)
+}
+
 /// One bin of durations. The bin starts at time `start`, and we've added `count`
-/// entries to it whose durations add up to `duration`
+/// entries to it whose durations add up to `duration`. If percentiles are
+/// enabled, `histogram` counts entries per bucket; otherwise it is empty
 struct Bin {
     start: Instant,
     duration: Duration,
     count: u32,
+    histogram: Vec<u32>,
 }
 
 impl Bin {
-    fn new(start: Instant) -> Self {
+    fn new(start: Instant, percentiles: bool) -> Self {
+        let histogram = if percentiles {
+            vec![0; HISTOGRAM_BUCKETS]
+        } else {
+            vec![]
+        };
         Self {
             start,
             duration: Duration::from_millis(0),
             count: 0,
+            histogram,
         }
     }
 
@@ -26,6 +47,9 @@ impl Bin {
     fn add(&mut self, duration: Duration) {
         self.count += 1;
         self.duration += duration;
+        if !self.histogram.is_empty() {
+            self.histogram[bucket(duration)] += 1;
+        }
     }
 
     /// Remove the measurements for `other` from this bin. Only used to
@@ -33,6 +57,9 @@ impl Bin {
     fn remove(&mut self, other: &Bin) {
         self.count -= other.count;
         self.duration -= other.duration;
+        for (mine, theirs) in self.histogram.iter_mut().zip(other.histogram.iter()) {
+            *mine -= theirs;
+        }
     }
 
     /// Return `true` if the average of measurements in this bin is above
@@ -63,6 +90,8 @@ pub struct MovingStats {
     /// Sum over the values in `elements` The `start` of this bin
     /// is meaningless
     total: Bin,
+    /// Whether bins keep a histogram so that we can compute percentiles
+    percentiles: bool,
 }
 
 /// Create `MovingStats` that use the window and bin sizes configured in
@@ -92,10 +121,50 @@ impl MovingStats {
             window_size,
             bin_size,
             bins: VecDeque::with_capacity(capacity),
-            total: Bin::new(Instant::now()),
+            total: Bin::new(Instant::now(), false),
+            percentiles: false,
         }
     }
 
+    /// Also keep a histogram of measurements so that `percentile` can be
+    /// used. This needs a few hundred bytes per bin, which is why it is
+    /// not enabled by default
+    pub fn with_percentiles(mut self) -> Self {
+        self.percentiles = true;
+        self.reset();
+        self
+    }
+
+    /// Forget all measurements and start over with an empty window
+    pub fn reset(&mut self) {
+        self.bins.clear();
+        self.total = Bin::new(Instant::now(), self.percentiles);
+    }
+
+    /// Return an upper bound for the `p`-th percentile, with `p` between 0
+    /// and 100, of the measurements within the current window. Since
+    /// measurements are kept in buckets whose bounds are powers of two
+    /// milliseconds, the result is the upper bound of the bucket that
+    /// contains the percentile. Returns `None` if there are no
+    /// measurements or percentiles were not enabled with
+    /// `with_percentiles`
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        if self.total.count == 0 || self.total.histogram.is_empty() {
+            return None;
+        }
+        let p = p.max(0.0).min(100.0);
+        let rank = ((p / 100.0 * self.total.count as f64).ceil() as u32).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.total.histogram.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Some(Duration::from_millis(1 << bucket));
+            }
+        }
+        // Can't happen since the histogram counts add up to `total.count`
+        None
+    }
+
     /// Return `true` if the average of measurements in within `window_size`
     /// is above `duration`
     pub fn average_gt(&self, duration: Duration) -> bool {
@@ -128,7 +197,7 @@ impl MovingStats {
             .map(|bin| now.saturating_duration_since(bin.start) >= self.bin_size)
             .unwrap_or(true);
         if need_new_bin {
-            self.bins.push_back(Bin::new(now));
+            self.bins.push_back(Bin::new(now, self.percentiles));
         }
         self.expire_bins(now);
         // unwrap is fine because we just added a bin if there wasn't one
@@ -219,4 +288,40 @@ mod tests {
         assert_eq!(20, stats.total.count);
         assert_eq!(Duration::from_secs(5 * 86 + 16 * 10), stats.total.duration);
     }
+
+    #[test]
+    fn percentiles() {
+        let mut stats =
+            MovingStats::new(Duration::from_secs(5), Duration::from_secs(1)).with_percentiles();
+        assert_eq!(None, stats.percentile(50.0));
+
+        let start = Instant::now();
+        // 90 fast measurements of 3ms and 10 slow ones of 100ms
+        for i in 0..100 {
+            let duration = if i % 10 == 9 { 100 } else { 3 };
+            stats.add_at(
+                start + Duration::from_millis(10 * i),
+                Duration::from_millis(duration),
+            );
+        }
+        assert_eq!(Some(Duration::from_millis(4)), stats.percentile(50.0));
+        assert_eq!(Some(Duration::from_millis(4)), stats.percentile(90.0));
+        assert_eq!(Some(Duration::from_millis(128)), stats.percentile(95.0));
+        assert_eq!(Some(Duration::from_millis(128)), stats.percentile(100.0));
+
+        // Expiring bins also removes their measurements from the histogram
+        stats.add_at(start + Duration::from_secs(10), Duration::from_millis(0));
+        assert_eq!(1, stats.total.count);
+        assert_eq!(Some(Duration::from_millis(1)), stats.percentile(99.0));
+
+        stats.reset();
+        assert_eq!(0, stats.bins.len());
+        assert_eq!(None, stats.percentile(50.0));
+        assert_eq!(None, stats.average());
+
+        // Without `with_percentiles`, there are no percentiles
+        let mut stats = MovingStats::new(Duration::from_secs(5), Duration::from_secs(1));
+        stats.add(Duration::from_millis(3));
+        assert_eq!(None, stats.percentile(50.0));
+    }
 }
@@ -7,7 +7,7 @@ use slog::Logger;
 use std::collections::BTreeMap;
 use std::sync::Arc;
 
-use graph::components::store::{EntityType, StoredDynamicDataSource, WritableStore};
+use graph::components::store::{EntityType, StoredDynamicDataSource, WritableStore, WriteBatch};
 use graph::{
     components::store::{DeploymentId, DeploymentLocator},
     prelude::{anyhow, DeploymentHash, Entity, EntityCache, EntityKey, EntityModification, Value},
@@ -153,6 +153,10 @@ impl WritableStore for MockStore {
     async fn flush(&self) -> Result<(), StoreError> {
         unimplemented!()
     }
+
+    fn set_write_batch(&self, _: WriteBatch) {
+        unimplemented!()
+    }
 }
 
 fn make_band(id: &'static str, data: Vec<(&str, Value)>) -> (EntityKey, Entity) {
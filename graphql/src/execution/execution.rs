@@ -143,6 +143,10 @@ where
 
     /// Records whether this was a cache hit, used for logging.
     pub(crate) cache_status: AtomicCell<CacheStatus>,
+
+    /// Cancels the store queries for this execution once nobody is
+    /// waiting for its result anymore
+    pub cancel: Option<CancelHandle>,
 }
 
 pub(crate) fn get_field<'a>(
@@ -175,6 +179,7 @@ where
 
             // `cache_status` is a dead value for the introspection context.
             cache_status: AtomicCell::new(CacheStatus::Miss),
+            cancel: self.cancel.clone(),
         }
     }
 }
@@ -233,6 +238,7 @@ pub(crate) async fn execute_root_selection_set<R: Resolver>(
     selection_set: Arc<a::SelectionSet>,
     root_type: sast::ObjectType,
     block_ptr: Option<BlockPtr>,
+    cancel_guard: CancelGuard,
 ) -> Arc<QueryResult> {
     // Cache the cache key to not have to calculate it twice - once for lookup
     // and once for insert.
@@ -282,6 +288,10 @@ pub(crate) async fn execute_root_selection_set<R: Resolver>(
     let execute_selection_set = selection_set.cheap_clone();
     let execute_root_type = root_type.cheap_clone();
     let run_query = async move {
+        // The herd cache shares this future between everybody sending the
+        // same query; the guard cancels the store queries for it once the
+        // last of them has gone away and the future gets dropped
+        let _cancel_guard = cancel_guard;
         let _permit = execute_ctx.resolver.query_permit().await;
 
         let logger = execute_ctx.logger.clone();
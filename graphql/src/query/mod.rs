@@ -1,4 +1,4 @@
-use graph::prelude::{BlockPtr, CheapClone, QueryExecutionError, QueryResult};
+use graph::prelude::{BlockPtr, CancelGuard, CheapClone, QueryExecutionError, QueryResult};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -41,6 +41,7 @@ where
     R: Resolver,
 {
     // Create a fresh execution context
+    let cancel_guard = CancelGuard::new();
     let ctx = Arc::new(ExecutionContext {
         logger: query.logger.clone(),
         resolver: options.resolver,
@@ -49,6 +50,7 @@ where
         max_first: options.max_first,
         max_skip: options.max_skip,
         cache_status: Default::default(),
+        cancel: Some(cancel_guard.handle()),
     });
 
     if !query.is_query() {
@@ -68,6 +70,7 @@ where
         selection_set.cheap_clone(),
         query_type,
         block_ptr.clone(),
+        cancel_guard,
     )
     .await;
     let elapsed = start.elapsed();
@@ -16,8 +16,8 @@ use graph::{components::store::EntityType, data::graphql::*};
 use graph::{
     data::graphql::ext::DirectiveFinder,
     prelude::{
-        s, ApiSchema, AttributeNames, BlockNumber, ChildMultiplicity, EntityCollection,
-        EntityFilter, EntityLink, EntityOrder, EntityWindow, Logger, ParentLink,
+        s, ApiSchema, AttributeNames, BlockNumber, CancelHandle, ChildMultiplicity,
+        EntityCollection, EntityFilter, EntityLink, EntityOrder, EntityWindow, Logger, ParentLink,
         QueryExecutionError, QueryStore, StoreError, Value as StoreValue, WindowAttribute,
         ENV_VARS,
     },
@@ -631,6 +631,7 @@ fn execute_field(
         ctx.max_skip,
         ctx.query.query_id.clone(),
         selected_attrs,
+        ctx.cancel.clone(),
     )
     .map_err(|e| vec![e])
 }
@@ -651,6 +652,7 @@ fn fetch(
     max_skip: u32,
     query_id: String,
     selected_attrs: SelectedAttributes,
+    cancel: Option<CancelHandle>,
 ) -> Result<Vec<Node>, QueryExecutionError> {
     let mut query = build_query(
         join.child_type,
@@ -662,6 +664,7 @@ fn fetch(
         selected_attrs,
     )?;
     query.query_id = Some(query_id);
+    query.cancel = cancel;
 
     if multiplicity == ChildMultiplicity::Single {
         // Suppress 'order by' in lookups of scalar values since
@@ -10,12 +10,6 @@ use crate::schema::ast as sast;
 
 use super::prefetch::SelectedAttributes;
 
-#[derive(Debug)]
-enum OrderDirection {
-    Ascending,
-    Descending,
-}
-
 /// Builds a EntityQuery from GraphQL arguments.
 ///
 /// Panics if `entity` is not present in `schema`.
@@ -96,6 +96,7 @@ fn create_source_event_stream(
         max_first: options.max_first,
         max_skip: options.max_skip,
         cache_status: Default::default(),
+        cancel: None,
     };
 
     let subscription_type = ctx
@@ -201,6 +202,7 @@ async fn execute_subscription_event(
     let block_ptr = resolver.block_ptr.clone();
 
     // Create a fresh execution context with deadline.
+    let cancel_guard = CancelGuard::new();
     let ctx = Arc::new(ExecutionContext {
         logger,
         resolver,
@@ -209,6 +211,7 @@ async fn execute_subscription_event(
         max_first,
         max_skip,
         cache_status: Default::default(),
+        cancel: Some(cancel_guard.handle()),
     });
 
     let subscription_type = match ctx.query.schema.subscription_type.as_ref() {
@@ -221,6 +224,7 @@ async fn execute_subscription_event(
         ctx.query.selection_set.cheap_clone(),
         subscription_type.into(),
         block_ptr,
+        cancel_guard,
     )
     .await
 }
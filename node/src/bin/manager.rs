@@ -378,12 +378,13 @@ pub enum StatsCommand {
         /// The name of a table to fully count
         table: Option<String>,
     },
-    /// Perform a SQL ANALYZE in a Entity table
+    /// Perform a SQL ANALYZE in a Entity table, or in all tables of the
+    /// deployment if no entity is given
     Analyze {
         /// The deployment (see `help info`).
         deployment: DeploymentSearch,
         /// The name of the Entity to ANALYZE, in camel case
-        entity: String,
+        entity: Option<String>,
     },
 }
 
@@ -885,8 +886,13 @@ async fn main() {
                 Analyze { deployment, entity } => {
                     let (store, primary_pool) = ctx.store_and_primary();
                     let subgraph_store = store.subgraph_store();
-                    commands::stats::analyze(subgraph_store, primary_pool, deployment, &entity)
-                        .await
+                    commands::stats::analyze(
+                        subgraph_store,
+                        primary_pool,
+                        deployment,
+                        entity.as_deref(),
+                    )
+                    .await
                 }
             }
         }
@@ -148,6 +148,7 @@ pub async fn create_ethereum_networks(
                             transport,
                             eth_rpc_metrics.clone(),
                             supports_eip_1898,
+                            web3.rate_limit,
                         )
                         .await,
                     ),
@@ -457,6 +457,7 @@ impl ChainSection {
                         url: url.to_string(),
                         features,
                         headers: Default::default(),
+                        rate_limit: None,
                     }),
                 };
                 let entry = chains.entry(name.to_string()).or_insert_with(|| Chain {
@@ -559,6 +560,10 @@ pub struct Web3Provider {
         deserialize_with = "deserialize_http_headers"
     )]
     pub headers: HeaderMap,
+
+    /// Limit the rate of requests sent to this provider
+    #[serde(default)]
+    pub rate_limit: Option<ethereum::RateLimit>,
 }
 
 impl Web3Provider {
@@ -664,6 +669,7 @@ impl<'de> Deserialize<'de> for Provider {
                 let mut transport = None;
                 let mut features = None;
                 let mut headers = None;
+                let mut rate_limit = None;
 
                 while let Some(key) = map.next_key()? {
                     match key {
@@ -705,6 +711,12 @@ impl<'de> Deserialize<'de> for Provider {
                             let raw_headers: BTreeMap<String, String> = map.next_value()?;
                             headers = Some(btree_map_to_http_headers(raw_headers));
                         }
+                        ProviderField::RateLimit => {
+                            if rate_limit.is_some() {
+                                return Err(serde::de::Error::duplicate_field("rate_limit"));
+                            }
+                            rate_limit = Some(map.next_value()?);
+                        }
                     }
                 }
 
@@ -715,8 +727,9 @@ impl<'de> Deserialize<'de> for Provider {
                             || transport.is_some()
                             || features.is_some()
                             || headers.is_some()
+                            || rate_limit.is_some()
                         {
-                            return Err(serde::de::Error::custom("when `details` field is provided, deprecated `url`, `transport`, `features` and `headers` cannot be specified"));
+                            return Err(serde::de::Error::custom("when `details` field is provided, deprecated `url`, `transport`, `features`, `headers` and `rate_limit` cannot be specified"));
                         }
 
                         v
@@ -727,6 +740,7 @@ impl<'de> Deserialize<'de> for Provider {
                         features: features
                             .ok_or_else(|| serde::de::Error::missing_field("features"))?,
                         headers: headers.unwrap_or_else(|| HeaderMap::new()),
+                        rate_limit,
                     }),
                 };
 
@@ -741,6 +755,7 @@ impl<'de> Deserialize<'de> for Provider {
             "url",
             "features",
             "headers",
+            "rate_limit",
         ];
         deserializer.deserialize_struct("Provider", FIELDS, ProviderVisitor)
     }
@@ -757,6 +772,8 @@ enum ProviderField {
     Transport,
     Features,
     Headers,
+    #[serde(rename = "rate_limit")]
+    RateLimit,
 }
 
 #[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq)]
@@ -1023,7 +1040,8 @@ where
 mod tests {
 
     use super::{
-        Chain, Config, FirehoseProvider, Provider, ProviderDetails, Transport, Web3Provider,
+        ethereum, Chain, Config, FirehoseProvider, Provider, ProviderDetails, Transport,
+        Web3Provider,
     };
     use graph::blockchain::BlockchainKind;
     use http::{HeaderMap, HeaderValue};
@@ -1108,6 +1126,7 @@ mod tests {
                     url: "http://localhost:8545".to_owned(),
                     features: BTreeSet::new(),
                     headers: HeaderMap::new(),
+                    rate_limit: None,
                 }),
             },
             actual
@@ -1133,6 +1152,7 @@ mod tests {
                     url: "http://localhost:8545".to_owned(),
                     features: BTreeSet::new(),
                     headers: HeaderMap::new(),
+                    rate_limit: None,
                 }),
             },
             actual
@@ -1197,6 +1217,7 @@ mod tests {
                     url: "http://localhost:8545".to_owned(),
                     features,
                     headers,
+                    rate_limit: None,
                 }),
             },
             actual
@@ -1221,6 +1242,35 @@ mod tests {
                     url: "http://localhost:8545".to_owned(),
                     features: BTreeSet::new(),
                     headers: HeaderMap::new(),
+                    rate_limit: None,
+                }),
+            },
+            actual
+        );
+    }
+
+    #[test]
+    fn it_works_on_web3_provider_with_rate_limit_from_toml() {
+        let actual = toml::from_str(
+            r#"
+            label = "peering"
+            details = { type = "web3", url = "http://localhost:8545", features = [], rate_limit = { requests_per_second = 20, burst = 50 } }
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Provider {
+                label: "peering".to_owned(),
+                details: ProviderDetails::Web3(Web3Provider {
+                    transport: Transport::Rpc,
+                    url: "http://localhost:8545".to_owned(),
+                    features: BTreeSet::new(),
+                    headers: HeaderMap::new(),
+                    rate_limit: Some(ethereum::RateLimit {
+                        requests_per_second: 20,
+                        burst: Some(50),
+                    }),
                 }),
             },
             actual
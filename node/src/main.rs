@@ -5,7 +5,7 @@ use ethereum::{
 use git_testament::{git_testament, render_testament};
 use graph::blockchain::firehose_block_ingestor::FirehoseBlockIngestor;
 use graph::blockchain::{Block as BlockchainBlock, Blockchain, BlockchainKind, BlockchainMap};
-use graph::components::store::BlockStore;
+use graph::components::store::{BlockStore, WriteBatch};
 use graph::data::graphql::effort::LoadManager;
 use graph::env::EnvVars;
 use graph::firehose::{FirehoseEndpoints, FirehoseNetworks};
@@ -375,6 +375,8 @@ async fn main() {
             metrics_registry.clone(),
             link_resolver.clone(),
             static_filters,
+            ENV_VARS.max_concurrent_hosts,
+            WriteBatch::default(),
         );
 
         // Create IPFS-based subgraph provider
@@ -13,7 +13,7 @@ use futures::TryFutureExt;
 use graph::anyhow::{bail, format_err, Error};
 use graph::blockchain::{BlockchainKind, BlockchainMap, ChainIdentifier};
 use graph::cheap_clone::CheapClone;
-use graph::components::store::{BlockStore as _, DeploymentLocator};
+use graph::components::store::{BlockStore as _, DeploymentLocator, WriteBatch};
 use graph::env::EnvVars;
 use graph::firehose::{FirehoseEndpoint, FirehoseEndpoints, FirehoseNetworks};
 use graph::ipfs_client::IpfsClient;
@@ -151,6 +151,8 @@ pub async fn run(
         metrics_registry.clone(),
         link_resolver.cheap_clone(),
         static_filters,
+        ENV_VARS.max_concurrent_hosts,
+        WriteBatch::default(),
     );
 
     // Create IPFS-based subgraph provider
@@ -392,6 +394,7 @@ async fn create_ethereum_networks(
                             transport,
                             eth_rpc_metrics.clone(),
                             supports_eip_1898,
+                            web3.rate_limit,
                         )
                         .await,
                     ),
@@ -147,12 +147,18 @@ pub async fn analyze(
     store: Arc<SubgraphStore>,
     pool: ConnectionPool,
     search: DeploymentSearch,
-    entity_name: &str,
+    entity_name: Option<&str>,
 ) -> Result<(), anyhow::Error> {
     let locator = search.locate_unique(&pool)?;
-    println!("Analyzing table sgd{}.{entity_name}", locator.id);
-    store
-        .analyze(&locator, entity_name)
-        .await
-        .map_err(|e| anyhow!(e))
+    match entity_name {
+        Some(entity_name) => {
+            println!("Analyzing table sgd{}.{entity_name}", locator.id);
+            store.analyze(&locator, entity_name).await
+        }
+        None => {
+            println!("Analyzing all tables in sgd{}", locator.id);
+            store.analyze_all(&locator).await
+        }
+    }
+    .map_err(|e| anyhow!(e))
 }
@@ -8,7 +8,7 @@ use graph::{components::store::*, ipfs_client::IpfsClient};
 use graph_chain_ethereum::{Chain, DataSource};
 use graph_mock::MockMetricsRegistry;
 use graph_runtime_wasm::asc_abi::class::{Array, AscBigInt, AscEntity, AscString, Uint8Array};
-use graph_runtime_wasm::{ExperimentalFeatures, ValidModule, WasmInstance};
+use graph_runtime_wasm::{ExperimentalFeatures, ValidModule, ValidModuleCache, WasmInstance};
 use hex;
 use semver::Version;
 use std::collections::{BTreeMap, HashMap};
@@ -776,6 +776,148 @@ async fn abort_v0_0_5() {
     .await;
 }
 
+/// A drain that remembers the level and message of everything logged to it
+#[derive(Clone, Default)]
+struct CapturingDrain {
+    records: Arc<std::sync::Mutex<Vec<(slog::Level, String)>>>,
+}
+
+impl slog::Drain for CapturingDrain {
+    type Ok = ();
+    type Err = slog::Never;
+
+    fn log(&self, record: &slog::Record<'_>, _: &slog::OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        self.records
+            .lock()
+            .unwrap()
+            .push((record.level(), record.msg().to_string()));
+        Ok(())
+    }
+}
+
+async fn test_log_log(api_version: Version) {
+    let mut module = test_module(
+        "logLog",
+        mock_data_source(
+            &wasm_file_path("abort.wasm", api_version.clone()),
+            api_version.clone(),
+        ),
+        api_version,
+    )
+    .await;
+    let drain = CapturingDrain::default();
+    module.instance_ctx_mut().ctx.logger = Logger::root(drain.clone(), o!());
+    let gas = module.gas.cheap_clone();
+    let msg: AscPtr<AscString> = asc_new(&mut module, "logged from a mapping", &gas).unwrap();
+
+    // Levels from debug (4) up to error (1) only log, and unknown levels
+    // log at the debug level
+    for level in [4, 3, 2, 1, 17] {
+        module
+            .instance_ctx_mut()
+            .log_log(&gas, level, msg)
+            .expect("logging does not trap");
+    }
+
+    // Logging at the critical level (0) fails the handler
+    let err = module.instance_ctx_mut().log_log(&gas, 0, msg).unwrap_err();
+    assert!(err.to_string().contains("Critical error logged in mapping"));
+
+    let msg = "logged from a mapping".to_string();
+    assert_eq!(
+        vec![
+            (slog::Level::Debug, msg.clone()),
+            (slog::Level::Info, msg.clone()),
+            (slog::Level::Warning, msg.clone()),
+            (slog::Level::Error, msg.clone()),
+            (slog::Level::Debug, msg.clone()),
+            (slog::Level::Critical, msg),
+        ],
+        *drain.records.lock().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn log_log_v0_0_4() {
+    test_log_log(API_VERSION_0_0_4).await;
+}
+
+#[tokio::test]
+async fn log_log_v0_0_5() {
+    test_log_log(API_VERSION_0_0_5).await;
+}
+
+async fn test_data_source_address_and_network(api_version: Version) {
+    let data_source = mock_data_source(
+        &wasm_file_path("abort.wasm", api_version.clone()),
+        api_version.clone(),
+    );
+    let expected_address = data_source.source.address.unwrap();
+    let module = test_module("dataSourceAddressAndNetwork", data_source, api_version).await;
+    let gas = module.gas.cheap_clone();
+
+    let address_ptr = module.instance_ctx_mut().data_source_address(&gas).unwrap();
+    let address: Vec<u8> = asc_get(&module, address_ptr, &gas).unwrap();
+    assert_eq!(address, expected_address.as_bytes());
+
+    let network_ptr = module.instance_ctx_mut().data_source_network(&gas).unwrap();
+    let network: String = asc_get(&module, network_ptr, &gas).unwrap();
+    assert_eq!(network, "mainnet");
+}
+
+#[tokio::test]
+async fn data_source_address_and_network_v0_0_4() {
+    test_data_source_address_and_network(API_VERSION_0_0_4).await;
+}
+
+#[tokio::test]
+async fn data_source_address_and_network_v0_0_5() {
+    test_data_source_address_and_network(API_VERSION_0_0_5).await;
+}
+
+async fn test_data_source_address_without_source_address(api_version: Version) {
+    let mut data_source = mock_data_source(
+        &wasm_file_path("abort.wasm", api_version.clone()),
+        api_version.clone(),
+    );
+    data_source.source.address = None;
+    let module = test_module("dataSourceAddressNone", data_source, api_version).await;
+    let gas = module.gas.cheap_clone();
+
+    // Data sources without a fixed address report the zero address
+    let address_ptr = module.instance_ctx_mut().data_source_address(&gas).unwrap();
+    let address: Vec<u8> = asc_get(&module, address_ptr, &gas).unwrap();
+    assert_eq!(address, H160::zero().as_bytes());
+}
+
+#[tokio::test]
+async fn data_source_address_without_source_address_v0_0_4() {
+    test_data_source_address_without_source_address(API_VERSION_0_0_4).await;
+}
+
+#[tokio::test]
+async fn data_source_address_without_source_address_v0_0_5() {
+    test_data_source_address_without_source_address(API_VERSION_0_0_5).await;
+}
+
+#[test]
+fn valid_module_cache_reuses_modules() {
+    let cache = ValidModuleCache::new(Arc::new(MockMetricsRegistry::new()));
+    let abort = std::fs::read(wasm_file_path("abort.wasm", API_VERSION_0_0_5)).unwrap();
+    let crypto = std::fs::read(wasm_file_path("crypto.wasm", API_VERSION_0_0_5)).unwrap();
+
+    let first = cache.get_or_compile(&abort).unwrap();
+    let second = cache.get_or_compile(&abort).unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+
+    let other = cache.get_or_compile(&crypto).unwrap();
+    assert!(!Arc::ptr_eq(&first, &other));
+
+    // The cache does not keep modules alive by itself
+    assert_eq!(2, Arc::strong_count(&first));
+    assert_eq!(1, Arc::strong_count(&other));
+}
+
 async fn test_bytes_to_base58(api_version: Version, gas_used: u64) {
     let mut module = test_module(
         "bytesToBase58",
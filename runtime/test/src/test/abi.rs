@@ -104,6 +104,87 @@ async fn abi_array_v0_0_5() {
     test_abi_array(API_VERSION_0_0_5, 1636130).await;
 }
 
+async fn test_abi_nested_array(api_version: Version) {
+    let mut module = test_module(
+        "abiNestedArray",
+        mock_data_source(
+            &wasm_file_path("abi_classes.wasm", api_version.clone()),
+            api_version.clone(),
+        ),
+        api_version,
+    )
+    .await;
+    let gas = module.gas.cheap_clone();
+
+    // Inner arrays of different lengths, including an empty one, so that
+    // element boundaries would be noticeable if they got lost
+    let nested: Vec<Vec<u8>> = vec![vec![1, 2, 3], vec![], vec![4], vec![5, 6]];
+    let nested_obj: AscPtr<Array<AscPtr<Uint8Array>>> =
+        asc_new(&mut module, &nested[..], &gas).unwrap();
+    let new_nested: Vec<Vec<u8>> = asc_get(&module, nested_obj, &gas).unwrap();
+    assert_eq!(new_nested, nested);
+
+    let strings = vec!["a".to_owned(), "".to_owned(), "bc".to_owned()];
+    let strings_obj: AscPtr<Array<AscPtr<AscString>>> =
+        asc_new(&mut module, &strings[..], &gas).unwrap();
+    let new_strings: Vec<String> = asc_get(&module, strings_obj, &gas).unwrap();
+    assert_eq!(new_strings, strings);
+}
+
+async fn test_abi_bool_bitmap(api_version: Version) {
+    let mut module = test_module(
+        "abiBoolBitmap",
+        mock_data_source(
+            &wasm_file_path("abi_classes.wasm", api_version.clone()),
+            api_version.clone(),
+        ),
+        api_version,
+    )
+    .await;
+    let gas = module.gas.cheap_clone();
+
+    for len in [0, 1, 7, 8, 9, 15, 16, 17, 100] {
+        let bools: Vec<bool> = (0..len).map(|i| i % 3 == 0 || i % 7 == 1).collect();
+        let bitmap: AscPtr<Uint8Array> = asc_new(&mut module, &bools[..], &gas).unwrap();
+
+        let bytes: Vec<u8> = asc_get(&module, bitmap, &gas).unwrap();
+        assert_eq!((len + 7) / 8, bytes.len());
+
+        let unpacked: Vec<bool> = asc_get(&module, bitmap, &gas).unwrap();
+        assert_eq!(bytes.len() * 8, unpacked.len());
+        assert_eq!(&bools[..], &unpacked[..len]);
+        assert!(unpacked[len..].iter().all(|bit| !bit));
+    }
+
+    // Bits are stored least significant bit first
+    let bools = vec![
+        true, false, false, false, false, false, false, false, false, true,
+    ];
+    let bitmap: AscPtr<Uint8Array> = asc_new(&mut module, &bools[..], &gas).unwrap();
+    let bytes: Vec<u8> = asc_get(&module, bitmap, &gas).unwrap();
+    assert_eq!(vec![0b0000_0001, 0b0000_0010], bytes);
+}
+
+#[tokio::test]
+async fn abi_bool_bitmap_v0_0_4() {
+    test_abi_bool_bitmap(API_VERSION_0_0_4).await;
+}
+
+#[tokio::test]
+async fn abi_bool_bitmap_v0_0_5() {
+    test_abi_bool_bitmap(API_VERSION_0_0_5).await;
+}
+
+#[tokio::test]
+async fn abi_nested_array_v0_0_4() {
+    test_abi_nested_array(API_VERSION_0_0_4).await;
+}
+
+#[tokio::test]
+async fn abi_nested_array_v0_0_5() {
+    test_abi_nested_array(API_VERSION_0_0_5).await;
+}
+
 async fn test_abi_subarray(api_version: Version) {
     let mut module = test_module(
         "abiSubarray",
@@ -246,6 +327,16 @@ async fn test_abi_ethabi_token_identity(api_version: Version) {
     let new_token: Token = asc_get(&module, new_token_ptr, &module.gas).unwrap();
 
     assert_eq!(new_token, token_array_nested);
+
+    // Token::Tuple, as returned for structs
+    let token_tuple = Token::Tuple(vec![
+        Token::Uint(U256::from(1_000_000_007u64)),
+        Token::Address(address),
+    ]);
+    let token_tuple_ptr = asc_new(&mut module, &token_tuple, &gas).unwrap();
+    let new_token: Token = asc_get(&module, token_tuple_ptr, &gas).unwrap();
+
+    assert_eq!(new_token, token_tuple);
 }
 
 /// Test a roundtrip Token -> Payload -> Token identity conversion through asc,
@@ -477,11 +568,19 @@ async fn test_big_int_to_string(api_version: Version) {
     )
     .await;
 
-    let big_int_str = "30145144166666665000000000000000000";
-    let big_int = BigInt::from_str(big_int_str).unwrap();
-    let string_obj: AscPtr<AscString> = module.invoke_export1("big_int_to_string", &big_int);
-    let string: String = asc_get(&module, string_obj, &module.gas).unwrap();
-    assert_eq!(string, big_int_str);
+    for big_int_str in &[
+        "30145144166666665000000000000000000",
+        "-30145144166666665000000000000000000",
+        "0",
+        "-1",
+        "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+        "-57896044618658097711785492504343953926634992332820282019728792003956564819968",
+    ] {
+        let big_int = BigInt::from_str(big_int_str).unwrap();
+        let string_obj: AscPtr<AscString> = module.invoke_export1("big_int_to_string", &big_int);
+        let string: String = asc_get(&module, string_obj, &module.gas).unwrap();
+        assert_eq!(&string, big_int_str);
+    }
 }
 
 #[tokio::test]
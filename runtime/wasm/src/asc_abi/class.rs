@@ -310,7 +310,7 @@ impl AscIndexId for Array<bool> {
     const INDEX_ASC_TYPE_ID: IndexForAscTypeId = IndexForAscTypeId::ArrayBool;
 }
 
-impl AscIndexId for Array<Uint8Array> {
+impl AscIndexId for Array<AscPtr<Uint8Array>> {
     const INDEX_ASC_TYPE_ID: IndexForAscTypeId = IndexForAscTypeId::ArrayUint8Array;
 }
 
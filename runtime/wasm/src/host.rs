@@ -14,7 +14,7 @@ use graph::prelude::{
     RuntimeHost as RuntimeHostTrait, RuntimeHostBuilder as RuntimeHostBuilderTrait, *,
 };
 
-use crate::mapping::{MappingContext, MappingRequest};
+use crate::mapping::{MappingContext, MappingRequest, ValidModuleCache};
 use crate::{host_exports::HostExports, module::ExperimentalFeatures};
 use graph::runtime::gas::Gas;
 
@@ -22,6 +22,7 @@ pub struct RuntimeHostBuilder<C: Blockchain> {
     runtime_adapter: Arc<dyn RuntimeAdapter<C>>,
     link_resolver: Arc<dyn LinkResolver>,
     ens_lookup: Arc<dyn EnsLookup>,
+    module_cache: Arc<ValidModuleCache>,
 }
 
 impl<C: Blockchain> Clone for RuntimeHostBuilder<C> {
@@ -30,6 +31,7 @@ impl<C: Blockchain> Clone for RuntimeHostBuilder<C> {
             runtime_adapter: self.runtime_adapter.cheap_clone(),
             link_resolver: self.link_resolver.cheap_clone(),
             ens_lookup: self.ens_lookup.cheap_clone(),
+            module_cache: self.module_cache.cheap_clone(),
         }
     }
 }
@@ -39,11 +41,13 @@ impl<C: Blockchain> RuntimeHostBuilder<C> {
         runtime_adapter: Arc<dyn RuntimeAdapter<C>>,
         link_resolver: Arc<dyn LinkResolver>,
         ens_lookup: Arc<dyn EnsLookup>,
+        module_cache: Arc<ValidModuleCache>,
     ) -> Self {
         RuntimeHostBuilder {
             runtime_adapter,
             link_resolver,
             ens_lookup,
+            module_cache,
         }
     }
 }
@@ -53,7 +57,8 @@ impl<C: Blockchain> RuntimeHostBuilderTrait<C> for RuntimeHostBuilder<C> {
     type Req = MappingRequest<C>;
 
     fn spawn_mapping(
-        raw_module: Vec<u8>,
+        &self,
+        raw_module: &[u8],
         logger: Logger,
         subgraph_id: DeploymentHash,
         metrics: Arc<HostMetrics>,
@@ -62,7 +67,7 @@ impl<C: Blockchain> RuntimeHostBuilderTrait<C> for RuntimeHostBuilder<C> {
             allow_non_deterministic_ipfs: ENV_VARS.mappings.allow_non_deterministic_ipfs,
         };
         crate::mapping::spawn_module(
-            raw_module,
+            self.module_cache.get_or_compile(raw_module)?,
             logger,
             subgraph_id,
             metrics,
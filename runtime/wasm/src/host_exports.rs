@@ -1,10 +1,13 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::ops::Deref;
 use std::str::FromStr;
 use std::time::{Duration, Instant};
 
+use hmac::{Hmac, Mac, NewMac};
 use never::Never;
 use semver::Version;
+use sha2::Sha256;
 use wasmtime::Trap;
 use web3::types::H160;
 
@@ -54,6 +57,40 @@ impl IntoTrap for HostExportError {
     }
 }
 
+/// The arguments of a call to `abort` from a mapping. Any of them can be
+/// missing; the mapping passes a null pointer or `0` for those.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct AbortMessage {
+    pub message: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+impl fmt::Display for AbortMessage {
+    /// Formats as `Mapping aborted at <file>, line <l>, column <c>, with
+    /// message: <message>`, leaving out what is not known
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Mapping aborted at ")?;
+        match (&self.file, self.line, self.column) {
+            (None, None, None) => write!(f, "an unknown location")?,
+            (file, line, column) => {
+                write!(f, "{}", file.as_deref().unwrap_or("an unknown file"))?;
+                if let Some(line) = line {
+                    write!(f, ", line {}", line)?;
+                }
+                if let Some(column) = column {
+                    write!(f, ", column {}", column)?;
+                }
+            }
+        }
+        match &self.message {
+            Some(message) => write!(f, ", with message: {}", message),
+            None => write!(f, ", with no message"),
+        }
+    }
+}
+
 pub struct HostExports<C: Blockchain> {
     pub(crate) subgraph_id: DeploymentHash,
     pub api_version: Version,
@@ -84,7 +121,12 @@ impl<C: Blockchain> HostExports<C> {
             subgraph_id,
             api_version: data_source.api_version(),
             data_source_name: data_source.name().to_owned(),
-            data_source_address: data_source.address().unwrap_or_default().to_owned(),
+            // Templates that are not bound to a fixed address report the
+            // zero address
+            data_source_address: data_source
+                .address()
+                .unwrap_or(H160::zero().as_bytes())
+                .to_owned(),
             data_source_context: data_source.context().cheap_clone(),
             causality_region: CausalityRegion::from_network(&data_source_network),
             data_source_network,
@@ -96,34 +138,12 @@ impl<C: Blockchain> HostExports<C> {
 
     pub(crate) fn abort(
         &self,
-        message: Option<String>,
-        file_name: Option<String>,
-        line_number: Option<u32>,
-        column_number: Option<u32>,
+        message: AbortMessage,
         gas: &GasCounter,
     ) -> Result<Never, DeterministicHostError> {
         gas.consume_host_fn(Gas::new(gas::DEFAULT_BASE_COST))?;
 
-        let message = message
-            .map(|message| format!("message: {}", message))
-            .unwrap_or_else(|| "no message".into());
-        let location = match (file_name, line_number, column_number) {
-            (None, None, None) => "an unknown location".into(),
-            (Some(file_name), None, None) => file_name,
-            (Some(file_name), Some(line_number), None) => {
-                format!("{}, line {}", file_name, line_number)
-            }
-            (Some(file_name), Some(line_number), Some(column_number)) => format!(
-                "{}, line {}, column {}",
-                file_name, line_number, column_number
-            ),
-            _ => unreachable!(),
-        };
-        Err(DeterministicHostError::from(anyhow::anyhow!(
-            "Mapping aborted at {}, with {}",
-            location,
-            message
-        )))
+        Err(DeterministicHostError::from(anyhow::anyhow!("{}", message)))
     }
 
     pub(crate) fn store_set(
@@ -376,6 +396,16 @@ impl<C: Blockchain> HostExports<C> {
         Ok(tiny_keccak::keccak256(data))
     }
 
+    pub(crate) fn crypto_hmac_sha256(
+        &self,
+        key: Vec<u8>,
+        message: Vec<u8>,
+        gas: &GasCounter,
+    ) -> Result<[u8; 32], DeterministicHostError> {
+        gas.consume_host_fn(gas::DEFAULT_GAS_OP.with_args(complexity::Linear, (&key, &message)))?;
+        Ok(hmac_sha256(&key, &message))
+    }
+
     pub(crate) fn big_int_plus(
         &self,
         x: BigInt,
@@ -768,6 +798,15 @@ fn string_to_h160(string: &str) -> Result<H160, DeterministicHostError> {
         .map_err(DeterministicHostError::from)
 }
 
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC can take a key of any size");
+    mac.update(message);
+
+    let mut output = [0u8; 32];
+    output.copy_from_slice(&mac.finalize().into_bytes());
+    output
+}
+
 fn bytes_to_string(logger: &Logger, bytes: Vec<u8>) -> String {
     let s = String::from_utf8_lossy(&bytes);
 
@@ -787,6 +826,52 @@ fn bytes_to_string(logger: &Logger, bytes: Vec<u8>) -> String {
     s.trim_end_matches('\u{0000}').to_string()
 }
 
+#[test]
+fn abort_message() {
+    let message = AbortMessage {
+        message: Some("not true".to_owned()),
+        file: Some("~lib/assembly.ts".to_owned()),
+        line: Some(6),
+        column: Some(2),
+    };
+    assert_eq!(
+        "Mapping aborted at ~lib/assembly.ts, line 6, column 2, with message: not true",
+        message.to_string()
+    );
+
+    let message = AbortMessage {
+        file: Some("~lib/assembly.ts".to_owned()),
+        ..Default::default()
+    };
+    assert_eq!(
+        "Mapping aborted at ~lib/assembly.ts, with no message",
+        message.to_string()
+    );
+
+    let message = AbortMessage {
+        line: Some(6),
+        ..Default::default()
+    };
+    assert_eq!(
+        "Mapping aborted at an unknown file, line 6, with no message",
+        message.to_string()
+    );
+
+    assert_eq!(
+        "Mapping aborted at an unknown location, with no message",
+        AbortMessage::default().to_string()
+    );
+}
+
+#[test]
+fn hmac_sha256_rfc4231() {
+    // Test case 2 from RFC 4231
+    assert_eq!(
+        hex::decode("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843").unwrap(),
+        hmac_sha256(b"Jefe", b"what do ya want for nothing?")
+    );
+}
+
 #[test]
 fn test_string_to_h160_with_0x() {
     assert_eq!(
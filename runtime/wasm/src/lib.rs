@@ -19,7 +19,7 @@ mod gas_rules;
 
 pub use host::RuntimeHostBuilder;
 pub use host_exports::HostExports;
-pub use mapping::{MappingContext, ValidModule};
+pub use mapping::{MappingContext, ValidModule, ValidModuleCache};
 pub use module::{ExperimentalFeatures, WasmInstance};
 
 #[cfg(debug_assertions)]
@@ -5,15 +5,16 @@ use futures03::channel::oneshot::Sender;
 use graph::blockchain::{Blockchain, HostFn, TriggerWithHandler};
 use graph::components::store::SubgraphFork;
 use graph::components::subgraph::{MappingError, SharedProofOfIndexing};
+use graph::prelude::web3::types::H256;
 use graph::prelude::*;
 use graph::runtime::gas::Gas;
-use std::collections::BTreeMap;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, Weak};
 use std::thread;
 
 /// Spawn a wasm module in its own thread.
 pub fn spawn_module<C: Blockchain>(
-    raw_module: Vec<u8>,
+    valid_module: Arc<ValidModule>,
     logger: Logger,
     subgraph_id: DeploymentHash,
     host_metrics: Arc<HostMetrics>,
@@ -21,8 +22,6 @@ pub fn spawn_module<C: Blockchain>(
     timeout: Option<Duration>,
     experimental_features: ExperimentalFeatures,
 ) -> Result<mpsc::Sender<MappingRequest<C>>, anyhow::Error> {
-    let valid_module = Arc::new(ValidModule::new(&raw_module)?);
-
     // Create channel for event handling requests
     let (mapping_request_sender, mapping_request_receiver) = mpsc::channel(100);
 
@@ -192,3 +191,67 @@ impl ValidModule {
         })
     }
 }
+
+/// A cache of `ValidModule`s keyed by the hash of the raw WASM bytes, so
+/// that data sources that use the same mapping, which is common with
+/// templates and with subgraphs that are deployed more than once, only
+/// compile it once. The cache only holds weak references; a module is
+/// dropped once no running mapping uses it anymore and gets compiled again
+/// the next time it is needed.
+pub struct ValidModuleCache {
+    modules: Mutex<HashMap<H256, Weak<ValidModule>>>,
+    hits: Counter,
+    misses: Counter,
+}
+
+impl ValidModuleCache {
+    pub fn new(registry: Arc<dyn MetricsRegistry>) -> Self {
+        let hits = registry
+            .global_counter(
+                "wasm_module_cache_hits",
+                "Counts how often a compiled WASM module could be reused",
+                HashMap::new(),
+            )
+            .expect("failed to create `wasm_module_cache_hits` counter");
+        let misses = registry
+            .global_counter(
+                "wasm_module_cache_misses",
+                "Counts how often a WASM module had to be compiled",
+                HashMap::new(),
+            )
+            .expect("failed to create `wasm_module_cache_misses` counter");
+        ValidModuleCache {
+            modules: Mutex::new(HashMap::new()),
+            hits,
+            misses,
+        }
+    }
+
+    /// Return the compiled module for `raw_module`, compiling it if it is
+    /// not in the cache yet
+    pub fn get_or_compile(&self, raw_module: &[u8]) -> Result<Arc<ValidModule>, anyhow::Error> {
+        let hash = H256::from(tiny_keccak::keccak256(raw_module));
+
+        if let Some(module) = self
+            .modules
+            .lock()
+            .unwrap()
+            .get(&hash)
+            .and_then(Weak::upgrade)
+        {
+            self.hits.inc();
+            return Ok(module);
+        }
+
+        // Compile without holding the lock since that can take a while. If
+        // another thread compiles the same module at the same time, we
+        // just keep whichever module gets inserted last
+        self.misses.inc();
+        let module = Arc::new(ValidModule::new(raw_module)?);
+
+        let mut modules = self.modules.lock().unwrap();
+        modules.retain(|_, module| module.strong_count() > 0);
+        modules.insert(hash, Arc::downgrade(&module));
+        Ok(module)
+    }
+}
@@ -20,7 +20,7 @@ use graph::runtime::{AscHeap, IndexForAscTypeId};
 use graph::{components::subgraph::MappingError, runtime::AscPtr};
 use graph::{
     data::subgraph::schema::SubgraphError,
-    runtime::{asc_get, asc_new, try_asc_get, DeterministicHostError},
+    runtime::{asc_get, asc_new, asc_new_or_null, try_asc_get, DeterministicHostError},
 };
 pub use into_wasm_ret::IntoWasmRet;
 pub use stopwatch::TimeoutStopwatch;
@@ -29,7 +29,7 @@ use crate::asc_abi::class::*;
 use crate::error::DeterminismLevel;
 use crate::gas_rules::{GAS_COST_LOAD, GAS_COST_STORE};
 pub use crate::host_exports;
-use crate::host_exports::HostExports;
+use crate::host_exports::{AbortMessage, HostExports};
 use crate::mapping::MappingContext;
 use crate::mapping::ValidModule;
 
@@ -499,6 +499,12 @@ impl<C: Blockchain> WasmInstance<C> {
         link!("json.toBigInt", json_to_big_int, ptr);
 
         link!("crypto.keccak256", crypto_keccak_256, ptr);
+        link!(
+            "crypto.hmacSha256",
+            crypto_hmac_sha256,
+            key_ptr,
+            message_ptr
+        );
 
         link!("bigInt.plus", big_int_plus, x_ptr, y_ptr);
         link!("bigInt.minus", big_int_minus, x_ptr, y_ptr);
@@ -811,26 +817,26 @@ impl<C: Blockchain> WasmInstanceContext<C> {
         line_number: u32,
         column_number: u32,
     ) -> Result<Never, DeterministicHostError> {
-        let message = match message_ptr.is_null() {
-            false => Some(asc_get(self, message_ptr, gas)?),
-            true => None,
-        };
-        let file_name = match file_name_ptr.is_null() {
-            false => Some(asc_get(self, file_name_ptr, gas)?),
-            true => None,
-        };
-        let line_number = match line_number {
-            0 => None,
-            _ => Some(line_number),
-        };
-        let column_number = match column_number {
-            0 => None,
-            _ => Some(column_number),
+        let message = AbortMessage {
+            message: match message_ptr.is_null() {
+                false => Some(asc_get(self, message_ptr, gas)?),
+                true => None,
+            },
+            file: match file_name_ptr.is_null() {
+                false => Some(asc_get(self, file_name_ptr, gas)?),
+                true => None,
+            },
+            line: match line_number {
+                0 => None,
+                _ => Some(line_number),
+            },
+            column: match column_number {
+                0 => None,
+                _ => Some(column_number),
+            },
         };
 
-        self.ctx
-            .host_exports
-            .abort(message, file_name, line_number, column_number, gas)
+        self.ctx.host_exports.abort(message, gas)
     }
 
     /// function store.set(entity: string, id: string, data: Entity): void
@@ -1085,6 +1091,10 @@ impl<C: Blockchain> WasmInstanceContext<C> {
         match ipfs_res {
             Ok(bytes) => asc_new(self, &*bytes, gas).map_err(Into::into),
 
+            // Content that does not match its CID means that an IPFS node
+            // misbehaved, not that the file is unavailable
+            Err(e) if e.is::<IpfsContentMismatch>() => Err(HostExportError::Unknown(e)),
+
             // Return null in case of error.
             Err(e) => {
                 info!(&self.ctx.logger, "Failed ipfs.cat, returning `null`";
@@ -1213,6 +1223,21 @@ impl<C: Blockchain> WasmInstanceContext<C> {
         asc_new(self, input.as_ref(), gas)
     }
 
+    /// function crypto.hmacSha256(key: Bytes, message: Bytes): Bytes
+    pub fn crypto_hmac_sha256(
+        &mut self,
+        gas: &GasCounter,
+        key_ptr: AscPtr<Uint8Array>,
+        message_ptr: AscPtr<Uint8Array>,
+    ) -> Result<AscPtr<Uint8Array>, DeterministicHostError> {
+        let mac = self.ctx.host_exports.crypto_hmac_sha256(
+            asc_get(self, key_ptr, gas)?,
+            asc_get(self, message_ptr, gas)?,
+            gas,
+        )?;
+        asc_new(self, mac.as_ref(), gas)
+    }
+
     /// function bigInt.plus(x: BigInt, y: BigInt): BigInt
     pub fn big_int_plus(
         &mut self,
@@ -1584,8 +1609,7 @@ impl<C: Blockchain> WasmInstanceContext<C> {
         let name = self.ctx.host_exports.ens_name_by_hash(&*hash)?;
 
         // map `None` to `null`, and `Some(s)` to a runtime string
-        name.map(|name| asc_new(self, &*name, gas).map_err(Into::into))
-            .unwrap_or(Ok(AscPtr::null()))
+        asc_new_or_null(self, name.as_deref(), gas).map_err(Into::into)
     }
 
     pub fn log_log(
@@ -1613,8 +1637,7 @@ impl<C: Blockchain> WasmInstanceContext<C> {
             .ethereum_encode(asc_get(self, token_ptr, gas)?, gas);
 
         // return `null` if it fails
-        data.map(|bytes| asc_new(self, &*bytes, gas))
-            .unwrap_or(Ok(AscPtr::null()))
+        asc_new_or_null(self, data.as_deref(), gas)
     }
 
     /// function decode(types: String, data: Bytes): ethereum.Value | null
@@ -1631,9 +1654,7 @@ impl<C: Blockchain> WasmInstanceContext<C> {
         );
 
         // return `null` if it fails
-        result
-            .map(|param| asc_new(self, &param, gas))
-            .unwrap_or(Ok(AscPtr::null()))
+        asc_new_or_null(self, result.as_ref(), gas)
     }
 
     /// function arweave.transactionData(txId: string): Bytes | null
@@ -8,7 +8,7 @@ use graph::{data::store, runtime::DeterministicHostError};
 use graph::{prelude::serde_json, runtime::FromAscObj};
 use graph::{prelude::web3::types as web3, runtime::AscHeap};
 use graph::{
-    prelude::{BigDecimal, BigInt},
+    prelude::{hex, tiny_keccak, BigDecimal, BigInt},
     runtime::TryFromAscObj,
 };
 
@@ -24,6 +24,40 @@ impl ToAscObj<Uint8Array> for web3::H160 {
     }
 }
 
+/// Format `addr` as a `0x`-prefixed hex string with the mixed-case
+/// checksum from EIP-55
+fn checksum_address(addr: &web3::H160) -> String {
+    let hex = hex::encode(addr.as_bytes());
+    let hash = tiny_keccak::keccak256(hex.as_bytes());
+
+    let mut checksummed = String::with_capacity(42);
+    checksummed.push_str("0x");
+    for (i, c) in hex.chars().enumerate() {
+        // Each hex digit of the address is uppercased if the corresponding
+        // nibble of the hash of the lowercase address is 8 or larger
+        let nibble = if i % 2 == 0 {
+            hash[i / 2] >> 4
+        } else {
+            hash[i / 2] & 0x0f
+        };
+        if nibble >= 8 {
+            checksummed.push(c.to_ascii_uppercase());
+        } else {
+            checksummed.push(c);
+        }
+    }
+    checksummed
+}
+
+/// Write `addr` to the heap as an EIP-55 checksummed, `0x`-prefixed string
+pub fn h160_to_checksum_asc_string<H: AscHeap + ?Sized>(
+    addr: &web3::H160,
+    heap: &mut H,
+    gas: &GasCounter,
+) -> Result<AscPtr<AscString>, DeterministicHostError> {
+    asc_new(heap, checksum_address(addr).as_str(), gas)
+}
+
 impl FromAscObj<Uint8Array> for web3::H160 {
     fn from_asc_obj<H: AscHeap + ?Sized>(
         typed_array: Uint8Array,
@@ -414,3 +448,19 @@ where
         })
     }
 }
+
+#[test]
+fn checksum_address_matches_eip55() {
+    use std::str::FromStr;
+
+    // Test vectors from EIP-55
+    for expected in [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ] {
+        let addr = web3::H160::from_str(&expected[2..]).unwrap();
+        assert_eq!(expected, checksum_address(&addr));
+    }
+}
@@ -17,6 +17,8 @@ use crate::asc_abi::class::*;
 ///! Standard Rust types go in `mod.rs` and external types in `external.rs`.
 mod external;
 
+pub use external::h160_to_checksum_asc_string;
+
 impl<T: AscValue> ToAscObj<TypedArray<T>> for [T] {
     fn to_asc_obj<H: AscHeap + ?Sized>(
         &self,
@@ -89,6 +91,40 @@ impl<T: AscValue> FromAscObj<TypedArray<T>> for [T; 4] {
     }
 }
 
+/// Pack booleans into a bitmap with one bit per value. Value `i` is stored
+/// in byte `i / 8` at bit `i % 8`, counting from the least significant
+/// bit. Unused bits in the last byte are zero
+impl ToAscObj<Uint8Array> for [bool] {
+    fn to_asc_obj<H: AscHeap + ?Sized>(
+        &self,
+        heap: &mut H,
+        gas: &GasCounter,
+    ) -> Result<Uint8Array, DeterministicHostError> {
+        let mut bitmap = vec![0u8; (self.len() + 7) / 8];
+        for (i, _) in self.iter().enumerate().filter(|(_, bit)| **bit) {
+            bitmap[i / 8] |= 1 << (i % 8);
+        }
+        TypedArray::new(&bitmap, heap, gas)
+    }
+}
+
+/// Unpack a bitmap produced by `ToAscObj<Uint8Array> for [bool]`. Since
+/// the bitmap does not record how many values it holds, the result always
+/// has eight entries per byte; padding bits come back as `false`
+impl FromAscObj<Uint8Array> for Vec<bool> {
+    fn from_asc_obj<H: AscHeap + ?Sized>(
+        bitmap: Uint8Array,
+        heap: &H,
+        gas: &GasCounter,
+    ) -> Result<Self, DeterministicHostError> {
+        Ok(bitmap
+            .to_vec(heap, gas)?
+            .into_iter()
+            .flat_map(|byte| (0..8).map(move |bit| byte & (1 << bit) != 0))
+            .collect())
+    }
+}
+
 impl ToAscObj<AscString> for str {
     fn to_asc_obj<H: AscHeap + ?Sized>(
         &self,
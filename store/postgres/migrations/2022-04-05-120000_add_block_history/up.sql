@@ -0,0 +1,10 @@
+-- The most recent block pointers for each deployment, most of the time
+-- the last GRAPH_STORE_BLOCK_HISTORY_SIZE blocks that were processed
+create table subgraphs.subgraph_deployment_block_history(
+   id            int not null
+                     references subgraphs.subgraph_deployment(id)
+                            on delete cascade,
+   block_number  int not null,
+   block_hash    bytea not null,
+   primary key(id, block_number)
+);
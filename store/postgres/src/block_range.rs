@@ -92,10 +92,13 @@ impl ToSql<Range<Integer>, Pg> for BlockRange {
     }
 }
 
+/// Selects the versions that were created in the blocks `(from, to]` and
+/// are still current at `to`
 #[derive(Constructor)]
 pub struct BlockRangeLowerBoundClause<'a> {
     _table_prefix: &'a str,
-    block: BlockNumber,
+    from: BlockNumber,
+    to: BlockNumber,
 }
 
 impl<'a> QueryFragment<Pg> for BlockRangeLowerBoundClause<'a> {
@@ -104,17 +107,23 @@ impl<'a> QueryFragment<Pg> for BlockRangeLowerBoundClause<'a> {
 
         out.push_sql("lower(");
         out.push_identifier(BLOCK_RANGE_COLUMN)?;
-        out.push_sql(") = ");
-        out.push_bind_param::<Integer, _>(&self.block)?;
+        out.push_sql(") > ");
+        out.push_bind_param::<Integer, _>(&self.from)?;
+        out.push_sql(" and ");
+        out.push_identifier(BLOCK_RANGE_COLUMN)?;
+        out.push_sql(" @> ");
+        out.push_bind_param::<Integer, _>(&self.to)?;
 
         Ok(())
     }
 }
 
+/// Selects the versions that were clamped in the blocks `(from, to]`
 #[derive(Constructor)]
 pub struct BlockRangeUpperBoundClause<'a> {
     _table_prefix: &'a str,
-    block: BlockNumber,
+    from: BlockNumber,
+    to: BlockNumber,
 }
 
 impl<'a> QueryFragment<Pg> for BlockRangeUpperBoundClause<'a> {
@@ -123,8 +132,12 @@ impl<'a> QueryFragment<Pg> for BlockRangeUpperBoundClause<'a> {
 
         out.push_sql("coalesce(upper(");
         out.push_identifier(BLOCK_RANGE_COLUMN)?;
-        out.push_sql("), 2147483647) = ");
-        out.push_bind_param::<Integer, _>(&self.block)?;
+        out.push_sql("), 2147483647) > ");
+        out.push_bind_param::<Integer, _>(&self.from)?;
+        out.push_sql(" and coalesce(upper(");
+        out.push_identifier(BLOCK_RANGE_COLUMN)?;
+        out.push_sql("), 2147483647) <= ");
+        out.push_bind_param::<Integer, _>(&self.to)?;
 
         Ok(())
     }
@@ -205,6 +218,25 @@ impl<'a> BlockRangeColumn<'a> {
         }
     }
 
+    /// Output SQL that matches rows whose block range starts at or before
+    /// `block`, i.e., rows for versions of an entity that were created at
+    /// or before `block`, no matter whether they are still current
+    pub fn starts_at_or_before(&self, out: &mut AstPass<Pg>) -> QueryResult<()> {
+        match self {
+            BlockRangeColumn::Mutable { block, .. } => {
+                out.push_sql("lower(");
+                self.name(out);
+                out.push_sql(") <= ");
+                out.push_bind_param::<Integer, _>(block)
+            }
+            BlockRangeColumn::Immutable { block, .. } => {
+                self.name(out);
+                out.push_sql(" <= ");
+                out.push_bind_param::<Integer, _>(block)
+            }
+        }
+    }
+
     /// Output the qualified name of the block range column
     pub fn name(&self, out: &mut AstPass<Pg>) {
         match self {
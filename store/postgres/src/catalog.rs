@@ -172,6 +172,32 @@ pub fn current_servers(conn: &PgConnection) -> Result<Vec<String>, StoreError> {
         .collect())
 }
 
+/// Return the process id of the database backend serving `conn`
+pub fn backend_pid(conn: &PgConnection) -> Result<i32, StoreError> {
+    #[derive(QueryableByName)]
+    struct Pid {
+        #[sql_type = "Integer"]
+        pid: i32,
+    }
+    Ok(sql_query("select pg_backend_pid() as pid")
+        .get_result::<Pid>(conn)?
+        .pid)
+}
+
+/// Ask the database backend with process id `pid` to cancel the statement
+/// it is currently running. Return `true` if the request could be sent
+pub fn cancel_backend(conn: &PgConnection, pid: i32) -> Result<bool, StoreError> {
+    #[derive(QueryableByName)]
+    struct Canceled {
+        #[sql_type = "Bool"]
+        canceled: bool,
+    }
+    Ok(sql_query("select pg_cancel_backend($1) as canceled")
+        .bind::<Integer, _>(pid)
+        .get_result::<Canceled>(conn)?
+        .canceled)
+}
+
 /// Return the options for the foreign server `name` as a map of option
 /// names to values
 pub fn server_options(
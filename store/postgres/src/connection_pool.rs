@@ -23,9 +23,10 @@ use graph::{
     util::security::SafeDisplay,
 };
 
+use std::cell::Cell;
 use std::fmt::{self, Write};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use std::{collections::HashMap, sync::RwLock};
 
@@ -213,6 +214,11 @@ impl ForeignServer {
 /// them on idle. This is much shorter than the default of 10 minutes.
 const FDW_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
 
+/// The default for `max_connections` in Postgres. A single pool that is
+/// larger than this will most likely not get all the connections it asks
+/// for since the database will refuse them
+const POSTGRES_DEFAULT_MAX_CONNECTIONS: u32 = 100;
+
 /// A pool goes through several states, and this enum tracks what state we
 /// are in, together with the `state_tracker` field on `ConnectionPool`.
 /// When first created, the pool is in state `Created`; once we successfully
@@ -427,6 +433,14 @@ impl ConnectionPool {
         self.get_ready()?.get()
     }
 
+    /// Get a connection together with the process id of its database
+    /// backend; see `PoolInner::get_with_backend_pid`
+    pub fn get_with_backend_pid(
+        &self,
+    ) -> Result<(PooledConnection<ConnectionManager<PgConnection>>, i32), StoreError> {
+        self.get_ready()?.get_with_backend_pid()
+    }
+
     /// Get a connection from the pool for foreign data wrapper access;
     /// since that pool can be very contended, periodically log that we are
     /// still waiting for a connection
@@ -479,12 +493,31 @@ impl ConnectionPool {
         pool.query_permit().await
     }
 
+    /// The maximum number of connections this pool will open
+    pub(crate) fn max_size(&self) -> u32 {
+        match &*self.inner.lock(&self.logger) {
+            PoolState::Created(pool, _) | PoolState::Ready(pool) => pool.pool.max_size(),
+        }
+    }
+
     pub(crate) fn wait_stats(&self) -> PoolWaitStats {
         match &*self.inner.lock(&self.logger) {
             PoolState::Created(pool, _) | PoolState::Ready(pool) => pool.wait_stats.clone(),
         }
     }
 
+    /// Return an upper bound for the `p`-th percentile of the time it took
+    /// to check out a connection from this pool over the recent past, or
+    /// `None` if no connections were checked out recently
+    pub fn wait_time_percentile(&self, p: f64) -> Option<Duration> {
+        self.wait_stats().read().unwrap().percentile(p)
+    }
+
+    /// Forget all measurements of connection wait times for this pool
+    pub fn reset_wait_stats(&self) {
+        self.wait_stats().write().unwrap().reset();
+    }
+
     /// Mirror key tables from the primary into our own schema. We do this
     /// by manually inserting or deleting rows through comparing it with the
     /// table on the primary. Once we drop support for PG 9.6, we can
@@ -515,14 +548,21 @@ fn brief_error_msg(error: &dyn std::error::Error) -> String {
 struct ErrorHandler {
     logger: Logger,
     counter: Counter,
+    validation_counter: Counter,
     state_tracker: PoolStateTracker,
 }
 
 impl ErrorHandler {
-    fn new(logger: Logger, counter: Counter, state_tracker: PoolStateTracker) -> Self {
+    fn new(
+        logger: Logger,
+        counter: Counter,
+        validation_counter: Counter,
+        state_tracker: PoolStateTracker,
+    ) -> Self {
         Self {
             logger,
             counter,
+            validation_counter,
             state_tracker,
         }
     }
@@ -535,6 +575,19 @@ impl std::fmt::Debug for ErrorHandler {
 
 impl r2d2::HandleError<r2d2::Error> for ErrorHandler {
     fn handle_error(&self, error: r2d2::Error) {
+        // When `test_on_check_out` is turned on, r2d2 runs `SELECT 1` on
+        // every connection before handing it out. A failure of that query
+        // is reported as a `QueryError`; r2d2 discards the connection and
+        // tries another one, so this does not mean that the database is
+        // unavailable. Errors establishing a connection are always
+        // reported as a `ConnectionError`
+        if let r2d2::Error::QueryError(e) = &error {
+            self.validation_counter.inc();
+            debug!(self.logger, "Discarding connection that failed validation";
+                   "error" => brief_error_msg(e));
+            return;
+        }
+
         let msg = brief_error_msg(&error);
 
         // Don't count canceling statements for timeouts etc. as a
@@ -563,6 +616,18 @@ impl r2d2::HandleError<r2d2::Error> for ErrorHandler {
     }
 }
 
+/// The process ids of the database backends for the connections in a
+/// pool, keyed by the id `r2d2` gives each connection
+type BackendPids = Arc<Mutex<HashMap<u64, i32>>>;
+
+thread_local! {
+    /// The id of the connection that the last checkout on this thread
+    /// handed out. `r2d2` reports checkouts synchronously on the thread
+    /// that checks the connection out, which lets us find out which
+    /// connection `Pool::get` returned
+    static LAST_CHECKOUT: Cell<Option<u64>> = Cell::new(None);
+}
+
 #[derive(Clone)]
 struct EventHandler {
     logger: Logger,
@@ -571,6 +636,7 @@ struct EventHandler {
     size_gauge: Gauge,
     wait_stats: PoolWaitStats,
     state_tracker: PoolStateTracker,
+    backend_pids: BackendPids,
 }
 
 impl EventHandler {
@@ -580,6 +646,7 @@ impl EventHandler {
         wait_stats: PoolWaitStats,
         const_labels: HashMap<String, String>,
         state_tracker: PoolStateTracker,
+        backend_pids: BackendPids,
     ) -> Self {
         let count_gauge = registry
             .global_gauge(
@@ -609,6 +676,7 @@ impl EventHandler {
             wait_stats,
             size_gauge,
             state_tracker,
+            backend_pids,
         }
     }
 
@@ -632,11 +700,16 @@ impl HandleEvent for EventHandler {
         self.state_tracker.mark_available();
     }
 
-    fn handle_release(&self, _: e::ReleaseEvent) {
+    fn handle_release(&self, event: e::ReleaseEvent) {
         self.size_gauge.dec();
+        self.backend_pids
+            .lock()
+            .unwrap()
+            .remove(&event.connection_id());
     }
 
     fn handle_checkout(&self, event: e::CheckoutEvent) {
+        LAST_CHECKOUT.with(|last| last.set(Some(event.connection_id())));
         self.count_gauge.inc();
         self.add_conn_wait_time(event.duration());
         self.state_tracker.mark_available();
@@ -684,6 +757,10 @@ pub struct PoolInner {
     query_semaphore: Arc<tokio::sync::Semaphore>,
     semaphore_wait_stats: Arc<RwLock<MovingStats>>,
     semaphore_wait_gauge: Box<Gauge>,
+
+    // Caches the backend process id of each connection so that we only
+    // need to ask the database for it once per connection
+    backend_pids: BackendPids,
 }
 
 impl PoolInner {
@@ -712,20 +789,53 @@ impl PoolInner {
                 const_labels.clone(),
             )
             .expect("failed to create `store_connection_error_count` counter");
+        let validation_counter = registry
+            .global_counter(
+                "store_connection_validation_failures",
+                "The number of Postgres connections that failed the check on checkout",
+                const_labels.clone(),
+            )
+            .expect("failed to create `store_connection_validation_failures` counter");
         let error_handler = Box::new(ErrorHandler::new(
             logger_pool.clone(),
             error_counter,
+            validation_counter,
             state_tracker.clone(),
         ));
-        let wait_stats = Arc::new(RwLock::new(MovingStats::default()));
+        let wait_stats = Arc::new(RwLock::new(MovingStats::default().with_percentiles()));
+        let backend_pids = BackendPids::default();
         let event_handler = Box::new(EventHandler::new(
             logger_pool.clone(),
             registry.cheap_clone(),
             wait_stats.clone(),
             const_labels.clone(),
             state_tracker,
+            backend_pids.cheap_clone(),
         ));
 
+        // Checking connections on checkout costs a roundtrip to the
+        // database; don't slow down our own tests with that
+        let test_on_check_out = ENV_VARS.store.connection_test_on_check_out && !cfg!(test);
+
+        // A pool without connections can never hand any out, and every
+        // attempt to use it would just hang
+        if pool_size == 0 || fdw_pool_size == Some(0) {
+            panic!(
+                "the connection pool {} for shard {} must have at least one connection, \
+                 but its size is {} and its fdw pool size is {:?}",
+                pool_name, shard_name, pool_size, fdw_pool_size
+            );
+        }
+        if pool_size > POSTGRES_DEFAULT_MAX_CONNECTIONS {
+            warn!(
+                logger_pool,
+                "Connection pool size {} exceeds the default Postgres max_connections of {}; \
+                 make sure the database allows enough connections for all pools of all nodes",
+                pool_size,
+                POSTGRES_DEFAULT_MAX_CONNECTIONS
+            );
+        }
+
         // Connect to Postgres
         let conn_manager = ConnectionManager::new(postgres_url.clone());
         let min_idle = ENV_VARS.store.connection_min_idle.filter(|min_idle| {
@@ -745,6 +855,7 @@ impl PoolInner {
             .error_handler(error_handler.clone())
             .event_handler(event_handler.clone())
             .connection_timeout(ENV_VARS.store.connection_timeout)
+            .test_on_check_out(test_on_check_out)
             .max_size(pool_size)
             .min_idle(min_idle)
             .idle_timeout(Some(ENV_VARS.store.connection_idle_timeout));
@@ -755,6 +866,7 @@ impl PoolInner {
                 .error_handler(error_handler)
                 .event_handler(event_handler)
                 .connection_timeout(ENV_VARS.store.connection_timeout)
+                .test_on_check_out(test_on_check_out)
                 .max_size(pool_size)
                 .min_idle(Some(1))
                 .idle_timeout(Some(FDW_IDLE_TIMEOUT));
@@ -785,6 +897,7 @@ impl PoolInner {
             semaphore_wait_stats: Arc::new(RwLock::new(MovingStats::default())),
             query_semaphore,
             semaphore_wait_gauge,
+            backend_pids,
         }
     }
 
@@ -880,6 +993,26 @@ impl PoolInner {
         self.pool.get().map_err(|_| StoreError::DatabaseUnavailable)
     }
 
+    /// Get a connection together with the process id of the database
+    /// backend that serves it. We only query the database for the process
+    /// id the first time a connection is handed out
+    pub fn get_with_backend_pid(
+        &self,
+    ) -> Result<(PooledConnection<ConnectionManager<PgConnection>>, i32), StoreError> {
+        LAST_CHECKOUT.with(|last| last.set(None));
+        let conn = self.get()?;
+        let id = match LAST_CHECKOUT.with(|last| last.take()) {
+            Some(id) => id,
+            None => return Ok((conn, catalog::backend_pid(&conn)?)),
+        };
+        if let Some(pid) = self.backend_pids.lock().unwrap().get(&id) {
+            return Ok((conn, *pid));
+        }
+        let pid = catalog::backend_pid(&conn)?;
+        self.backend_pids.lock().unwrap().insert(id, pid);
+        Ok((conn, pid))
+    }
+
     pub fn get_with_timeout_warning(
         &self,
         logger: &Logger,
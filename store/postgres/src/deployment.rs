@@ -13,8 +13,9 @@ use diesel::{
 use diesel::{expression::SqlLiteral, pg::PgConnection, sql_types::Numeric};
 use diesel::{
     prelude::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl},
+    result::{DatabaseErrorKind, Error as DieselError},
     sql_query,
-    sql_types::{Nullable, Text},
+    sql_types::{Binary, Nullable, Text},
 };
 use graph::data::subgraph::schema::SubgraphError;
 use graph::data::subgraph::{
@@ -22,10 +23,12 @@ use graph::data::subgraph::{
     SubgraphFeature,
 };
 use graph::prelude::{
-    anyhow, bigdecimal::ToPrimitive, hex, web3::types::H256, BigDecimal, BlockNumber, BlockPtr,
-    DeploymentHash, DeploymentState, Schema, StoreError,
+    anyhow, bigdecimal::ToPrimitive, hex, warn, web3::types::H256, BigDecimal, BlockNumber,
+    BlockPtr, DeploymentHash, DeploymentState, Logger, Schema, StoreError, ENV_VARS,
 };
+use rand::{thread_rng, Rng};
 use stable_hash_legacy::crypto::SetHasher;
+use std::time::Duration;
 use std::{collections::BTreeSet, convert::TryFrom, ops::Bound};
 use std::{str::FromStr, sync::Arc};
 
@@ -126,6 +129,14 @@ table! {
     }
 }
 
+table! {
+    subgraphs.subgraph_deployment_block_history (id, block_number) {
+        id -> Integer,
+        block_number -> Integer,
+        block_hash -> Binary,
+    }
+}
+
 allow_tables_to_appear_in_same_query!(subgraph_deployment, subgraph_error);
 
 /// Look up the graft point for the given subgraph in the database and
@@ -195,6 +206,52 @@ pub fn graft_point(
     graft(conn, id, false)
 }
 
+/// Return the deployments that are grafted onto `base`. If `pending_only`
+/// is `true`, only return deployments that have not progressed past their
+/// graft point yet and therefore still need the data of `base`
+fn dependents(
+    conn: &PgConnection,
+    base: &DeploymentHash,
+    pending_only: bool,
+) -> Result<Vec<DeploymentHash>, StoreError> {
+    use subgraph_deployment as sd;
+
+    let query = sd::table
+        .select(sd::deployment)
+        .filter(sd::graft_base.eq(base.as_str()))
+        .order_by(sd::deployment);
+    let ids = if pending_only {
+        query
+            .filter(sd::latest_ethereum_block_number.is_null())
+            .load::<String>(conn)?
+    } else {
+        query.load::<String>(conn)?
+    };
+    ids.into_iter()
+        .map(|id| {
+            DeploymentHash::new(id)
+                .map_err(|id| constraint_violation!("illegal deployment id: {}", id))
+        })
+        .collect()
+}
+
+/// Return the deployments in this shard that are grafted onto `base`
+pub fn graft_dependents(
+    conn: &PgConnection,
+    base: &DeploymentHash,
+) -> Result<Vec<DeploymentHash>, StoreError> {
+    dependents(conn, base, false)
+}
+
+/// Return the deployments in this shard that are grafted onto `base` and
+/// have not copied the data from `base` yet
+pub fn pending_graft_dependents(
+    conn: &PgConnection,
+    base: &DeploymentHash,
+) -> Result<Vec<DeploymentHash>, StoreError> {
+    dependents(conn, base, true)
+}
+
 /// Look up the debug fork for the given subgraph in the database and
 /// return it. Returns `None` if the deployment does not have
 /// a debug fork.
@@ -245,6 +302,33 @@ pub fn manifest_info(
         .map(|schema| (schema, description, repository))
 }
 
+/// The descriptive parts of a deployment's manifest
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ManifestMetadata {
+    pub spec_version: String,
+    pub description: Option<String>,
+    pub repository: Option<String>,
+}
+
+/// Read the spec version, description, and repository from the manifest
+/// of the deployment `site`. If the deployment has no manifest entry, all
+/// fields are empty
+pub fn manifest_metadata(conn: &PgConnection, site: &Site) -> Result<ManifestMetadata, StoreError> {
+    use subgraph_manifest as sm;
+
+    Ok(sm::table
+        .select((sm::spec_version, sm::description, sm::repository))
+        .filter(sm::id.eq(site.id))
+        .first::<(String, Option<String>, Option<String>)>(conn)
+        .optional()?
+        .map(|(spec_version, description, repository)| ManifestMetadata {
+            spec_version,
+            description,
+            repository,
+        })
+        .unwrap_or_default())
+}
+
 #[allow(dead_code)]
 pub fn features(conn: &PgConnection, site: &Site) -> Result<BTreeSet<SubgraphFeature>, StoreError> {
     use subgraph_manifest as sm;
@@ -310,7 +394,7 @@ pub fn transact_block(
 
     match row_count {
         // Common case: A single row was updated.
-        1 => Ok(()),
+        1 => record_block_ptr(conn, site, ptr),
 
         // No matching rows were found. This is an error. By the filter conditions, this can only be
         // due to a missing deployment (which `block_ptr` catches) or duplicate block processing.
@@ -330,9 +414,86 @@ pub fn transact_block(
     }
 }
 
+/// How often callers that do not need a specific limit let
+/// `retry_on_transaction_conflict` run an operation before giving up
+pub(crate) const TRANSACTION_CONFLICT_ATTEMPTS: usize = 5;
+
+/// The base delay between attempts in `retry_on_transaction_conflict`;
+/// it doubles with every attempt and gets a random jitter added
+const TRANSACTION_CONFLICT_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Errors that can tell whether the database aborted a transaction
+/// because of a conflict with a concurrent transaction, so that running
+/// the transaction again might succeed
+pub(crate) trait TransactionConflict {
+    fn is_transaction_conflict(&self) -> bool;
+}
+
+impl TransactionConflict for DieselError {
+    fn is_transaction_conflict(&self) -> bool {
+        match self {
+            DieselError::DatabaseError(DatabaseErrorKind::SerializationFailure, _) => true,
+            // Postgres reports deadlocks with SQLSTATE `40P01`, but diesel
+            // does not give us access to that code, and we have to
+            // recognize them by their message instead
+            DieselError::DatabaseError(_, info) => info.message() == "deadlock detected",
+            _ => false,
+        }
+    }
+}
+
+impl TransactionConflict for StoreError {
+    fn is_transaction_conflict(&self) -> bool {
+        match self {
+            StoreError::Unknown(e) => e
+                .downcast_ref::<DieselError>()
+                .map_or(false, |e| e.is_transaction_conflict()),
+            _ => false,
+        }
+    }
+}
+
+/// Run `f` and rerun it with a jittered, exponentially growing delay if
+/// the database aborted it because of a serialization failure or a
+/// deadlock. Since such a failure aborts the surrounding transaction, `f`
+/// must run the entire transaction, not just a part of it. All other
+/// errors are returned immediately, and once `f` has run `attempts`
+/// times, the last conflict is returned.
+///
+/// This blocks the current thread while it waits between attempts and
+/// must therefore only be called from code that runs on a blocking
+/// thread, like everything else that uses a database connection
+pub(crate) fn retry_on_transaction_conflict<T, E, F>(
+    logger: &Logger,
+    attempts: usize,
+    mut f: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Result<T, E>,
+    E: TransactionConflict + std::fmt::Display,
+{
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Err(e) if attempt < attempts && e.is_transaction_conflict() => {
+                let backoff = TRANSACTION_CONFLICT_BACKOFF * 2u32.pow(attempt as u32 - 1);
+                let jitter = thread_rng().gen_range(0..=backoff.as_millis() as u64);
+                let delay = backoff + Duration::from_millis(jitter);
+                warn!(logger, "Retrying transaction after conflict with another transaction";
+                      "attempt" => attempt,
+                      "delay_ms" => delay.as_millis(),
+                      "error" => e.to_string());
+                std::thread::sleep(delay);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
 pub fn forward_block_ptr(
     conn: &PgConnection,
-    id: &DeploymentHash,
+    site: &Site,
     ptr: &BlockPtr,
 ) -> Result<(), StoreError> {
     use crate::diesel::BoolExpressionMethods;
@@ -342,7 +503,7 @@ pub fn forward_block_ptr(
     let number = format!("{}::numeric", ptr.number);
 
     let row_count = update(
-        d::table.filter(d::deployment.eq(id.as_str())).filter(
+        d::table.filter(d::id.eq(site.id)).filter(
             // Asserts that the processing direction is forward.
             d::latest_ethereum_block_number
                 .lt(sql(&number))
@@ -359,14 +520,14 @@ pub fn forward_block_ptr(
 
     match row_count {
         // Common case: A single row was updated.
-        1 => Ok(()),
+        1 => record_block_ptr(conn, site, ptr),
 
         // No matching rows were found. This is an error. By the filter conditions, this can only be
         // due to a missing deployment (which `block_ptr` catches) or duplicate block processing.
-        0 => match block_ptr(conn, id)? {
-            Some(block_ptr_from) if block_ptr_from.number >= ptr.number => {
-                Err(StoreError::DuplicateBlockProcessing(id.clone(), ptr.number))
-            }
+        0 => match block_ptr(conn, &site.deployment)? {
+            Some(block_ptr_from) if block_ptr_from.number >= ptr.number => Err(
+                StoreError::DuplicateBlockProcessing(site.deployment.clone(), ptr.number),
+            ),
             None | Some(_) => Err(StoreError::Unknown(anyhow!(
                 "unknown error forwarding block ptr"
             ))),
@@ -420,17 +581,13 @@ pub fn update_firehose_cursor(
         .map_err(|e| e.into())
 }
 
-pub fn revert_block_ptr(
-    conn: &PgConnection,
-    id: &DeploymentHash,
-    ptr: BlockPtr,
-) -> Result<(), StoreError> {
+pub fn revert_block_ptr(conn: &PgConnection, site: &Site, ptr: BlockPtr) -> Result<(), StoreError> {
     use subgraph_deployment as d;
 
     // Work around a Diesel issue with serializing BigDecimals to numeric
     let number = format!("{}::numeric", ptr.number);
 
-    update(d::table.filter(d::deployment.eq(id.as_str())))
+    update(d::table.filter(d::id.eq(site.id)))
         .set((
             d::latest_ethereum_block_number.eq(sql(&number)),
             d::latest_ethereum_block_hash.eq(ptr.hash_slice()),
@@ -438,9 +595,181 @@ pub fn revert_block_ptr(
             d::current_reorg_depth.eq(d::current_reorg_depth + 1),
             d::max_reorg_depth.eq(sql("greatest(current_reorg_depth + 1, max_reorg_depth)")),
         ))
-        .execute(conn)
-        .map(|_| ())
-        .map_err(|e| e.into())
+        .execute(conn)?;
+    forget_block_history_after(conn, site, ptr.number)
+}
+
+/// We only prune the block history when the block number crosses a
+/// multiple of this, so that recording a block pointer normally costs
+/// just one insert. The history therefore holds up to this many more
+/// entries than `GRAPH_STORE_BLOCK_HISTORY_SIZE`
+const BLOCK_HISTORY_PRUNE_INTERVAL: BlockNumber = 100;
+
+#[derive(QueryableByName)]
+struct PreviousBlock {
+    #[sql_type = "Nullable<Integer>"]
+    number: Option<BlockNumber>,
+}
+
+/// Remember `ptr` in the block history of the deployment, and every
+/// `BLOCK_HISTORY_PRUNE_INTERVAL` blocks forget the block pointers that
+/// have fallen out of the window of `GRAPH_STORE_BLOCK_HISTORY_SIZE`
+/// blocks that we retain
+fn record_block_ptr(conn: &PgConnection, site: &Site, ptr: &BlockPtr) -> Result<(), StoreError> {
+    use subgraph_deployment_block_history as h;
+
+    // Returns the latest block number in the history before this insert
+    const RECORD_QUERY: &str = "
+        with previous as (
+          select max(block_number) as number
+            from subgraphs.subgraph_deployment_block_history
+           where id = $1)
+        insert into subgraphs.subgraph_deployment_block_history(id, block_number, block_hash)
+        values ($1, $2, $3)
+            on conflict(id, block_number) do update set block_hash = excluded.block_hash
+        returning (select number from previous) as number";
+
+    let previous = sql_query(RECORD_QUERY)
+        .bind::<Integer, _>(site.id)
+        .bind::<Integer, _>(ptr.number)
+        .bind::<Binary, _>(ptr.hash_slice())
+        .get_result::<PreviousBlock>(conn)?
+        .number;
+
+    let crossed_interval = previous
+        .map(|previous| {
+            previous / BLOCK_HISTORY_PRUNE_INTERVAL != ptr.number / BLOCK_HISTORY_PRUNE_INTERVAL
+        })
+        .unwrap_or(false);
+    if crossed_interval {
+        let oldest = ptr.number.saturating_sub(ENV_VARS.store.block_history_size);
+        delete(
+            h::table
+                .filter(h::id.eq(site.id))
+                .filter(h::block_number.le(oldest)),
+        )
+        .execute(conn)?;
+    }
+    Ok(())
+}
+
+/// Forget all block pointers after `number` in the block history of the
+/// deployment since they have been reverted
+fn forget_block_history_after(
+    conn: &PgConnection,
+    site: &Site,
+    number: BlockNumber,
+) -> Result<(), StoreError> {
+    use subgraph_deployment_block_history as h;
+
+    delete(
+        h::table
+            .filter(h::id.eq(site.id))
+            .filter(h::block_number.gt(number)),
+    )
+    .execute(conn)?;
+    Ok(())
+}
+
+/// Reset the deployment `site` so that it looks like it has not processed
+/// any blocks, has no entities and is not synced. This does not touch the
+/// deployment's data, which the caller needs to remove separately, nor its
+/// errors or graft
+pub fn clear_block_ptr(conn: &PgConnection, site: &Site) -> Result<(), StoreError> {
+    use subgraph_deployment as d;
+
+    update(d::table.filter(d::id.eq(site.id)))
+        .set((
+            d::latest_ethereum_block_number.eq(None::<BigDecimal>),
+            d::latest_ethereum_block_hash.eq(None::<Vec<u8>>),
+            d::firehose_cursor.eq(None::<String>),
+            d::current_reorg_depth.eq(0),
+            d::entity_count.eq(BigDecimal::from(0)),
+            d::synced.eq(false),
+        ))
+        .execute(conn)?;
+    forget_block_history_after(conn, site, -1)
+}
+
+/// Return `true` if `ptr` is one of the block pointers in the block
+/// history of the deployment
+pub fn block_history_contains(
+    conn: &PgConnection,
+    site: &Site,
+    ptr: &BlockPtr,
+) -> Result<bool, StoreError> {
+    use subgraph_deployment_block_history as h;
+
+    let hash = h::table
+        .filter(h::id.eq(site.id))
+        .filter(h::block_number.eq(ptr.number))
+        .select(h::block_hash)
+        .first::<Vec<u8>>(conn)
+        .optional()?;
+    Ok(hash.as_deref() == Some(ptr.hash_slice()))
+}
+
+/// Revert the block pointer of the deployment to `target` in one step,
+/// no matter how many blocks that goes back. The `target` must be in the
+/// block history of the deployment; it is an error if it is older than
+/// the oldest block pointer we still retain, or if it does not match the
+/// block pointer we recorded for its block number. Like
+/// `revert_block_ptr`, this only changes the block pointer and the reorg
+/// statistics and leaves reverting entities to the caller
+pub fn revert_to_block(
+    conn: &PgConnection,
+    site: &Site,
+    target: &BlockPtr,
+) -> Result<(), StoreError> {
+    use subgraph_deployment as d;
+    use subgraph_deployment_block_history as h;
+
+    let oldest = h::table
+        .filter(h::id.eq(site.id))
+        .select(diesel::dsl::min(h::block_number))
+        .first::<Option<BlockNumber>>(conn)?;
+    match oldest {
+        Some(oldest) if oldest <= target.number => { /* within the window */ }
+        _ => {
+            return Err(StoreError::Unknown(anyhow!(
+                "can not revert deployment {} to block {} since that block is \
+                 older than the blocks retained in its block history",
+                site.deployment,
+                target
+            )))
+        }
+    }
+    if !block_history_contains(conn, site, target)? {
+        return Err(StoreError::Unknown(anyhow!(
+            "can not revert deployment {} to block {} since that block is \
+             not in its block history",
+            site.deployment,
+            target
+        )));
+    }
+
+    // Work around a Diesel issue with serializing BigDecimals to numeric
+    let number = format!("{}::numeric", target.number);
+    let depth = format!("(latest_ethereum_block_number - {})::int4", target.number);
+
+    update(
+        d::table
+            .filter(d::id.eq(site.id))
+            .filter(d::latest_ethereum_block_number.gt(sql(&number))),
+    )
+    .set((
+        d::latest_ethereum_block_number.eq(sql(&number)),
+        d::latest_ethereum_block_hash.eq(target.hash_slice()),
+        d::reorg_count.eq(d::reorg_count + 1),
+        d::current_reorg_depth.eq(sql(&format!("current_reorg_depth + {}", depth))),
+        d::max_reorg_depth.eq(sql(&format!(
+            "greatest(current_reorg_depth + {}, max_reorg_depth)",
+            depth
+        ))),
+    ))
+    .execute(conn)?;
+
+    forget_block_history_after(conn, site, target.number)
 }
 
 pub fn block_ptr(conn: &PgConnection, id: &DeploymentHash) -> Result<Option<BlockPtr>, StoreError> {
@@ -531,6 +860,25 @@ fn latest_as_block_number(
     }
 }
 
+fn make_state(
+    id: DeploymentHash,
+    reorg_count: i32,
+    max_reorg_depth: i32,
+    latest_ethereum_block_number: Option<BigDecimal>,
+) -> Result<DeploymentState, StoreError> {
+    let reorg_count = convert_to_u32(Some(reorg_count), "reorg_count", id.as_str())?;
+    let max_reorg_depth = convert_to_u32(Some(max_reorg_depth), "max_reorg_depth", id.as_str())?;
+    let latest_ethereum_block_number =
+        latest_as_block_number(latest_ethereum_block_number, id.as_str())?;
+
+    Ok(DeploymentState {
+        id,
+        reorg_count,
+        max_reorg_depth,
+        latest_ethereum_block_number,
+    })
+}
+
 pub fn state(conn: &PgConnection, id: DeploymentHash) -> Result<DeploymentState, StoreError> {
     use subgraph_deployment as d;
 
@@ -549,23 +897,75 @@ pub fn state(conn: &PgConnection, id: DeploymentHash) -> Result<DeploymentState,
             "No data found for subgraph {}",
             id
         ))),
-        Some((_, reorg_count, max_reorg_depth, latest_ethereum_block_number)) => {
-            let reorg_count = convert_to_u32(Some(reorg_count), "reorg_count", id.as_str())?;
-            let max_reorg_depth =
-                convert_to_u32(Some(max_reorg_depth), "max_reorg_depth", id.as_str())?;
-            let latest_ethereum_block_number =
-                latest_as_block_number(latest_ethereum_block_number, id.as_str())?;
-
-            Ok(DeploymentState {
-                id,
-                reorg_count,
-                max_reorg_depth,
-                latest_ethereum_block_number,
-            })
-        }
+        Some((_, reorg_count, max_reorg_depth, latest_ethereum_block_number)) => make_state(
+            id,
+            reorg_count,
+            max_reorg_depth,
+            latest_ethereum_block_number,
+        ),
     }
 }
 
+/// Load the states of all deployments in `ids` with a single query.
+/// Deployments that do not exist in this shard are left out of the result
+pub fn states(
+    conn: &PgConnection,
+    ids: &[DeploymentHash],
+) -> Result<Vec<DeploymentState>, StoreError> {
+    use subgraph_deployment as d;
+
+    let ids: Vec<_> = ids.iter().map(|id| id.as_str()).collect();
+    d::table
+        .filter(d::deployment.eq_any(&ids))
+        .select((
+            d::deployment,
+            d::reorg_count,
+            d::max_reorg_depth,
+            d::latest_ethereum_block_number,
+        ))
+        .load::<(String, i32, i32, Option<BigDecimal>)>(conn)?
+        .into_iter()
+        .map(
+            |(id, reorg_count, max_reorg_depth, latest_ethereum_block_number)| {
+                let id = DeploymentHash::new(id)
+                    .map_err(|id| constraint_violation!("illegal deployment id: {}", id))?;
+                make_state(
+                    id,
+                    reorg_count,
+                    max_reorg_depth,
+                    latest_ethereum_block_number,
+                )
+            },
+        )
+        .collect()
+}
+
+/// Return all deployments whose `current_reorg_depth` is greater than
+/// `threshold` together with that depth, deepest first. A deployment that
+/// stays in a deep reorg for a long time often points to an unstable or
+/// misconfigured RPC provider
+pub fn deployments_exceeding_reorg_depth(
+    conn: &PgConnection,
+    threshold: u32,
+) -> Result<Vec<(DeploymentHash, u32)>, StoreError> {
+    use subgraph_deployment as d;
+
+    let threshold = i32::try_from(threshold).unwrap_or(i32::MAX);
+    d::table
+        .filter(d::current_reorg_depth.gt(threshold))
+        .order_by((d::current_reorg_depth.desc(), d::deployment))
+        .select((d::deployment, d::current_reorg_depth))
+        .load::<(String, i32)>(conn)?
+        .into_iter()
+        .map(|(id, depth)| {
+            let depth = convert_to_u32(Some(depth), "current_reorg_depth", &id)?;
+            let id = DeploymentHash::new(id)
+                .map_err(|id| constraint_violation!("illegal deployment id: {}", id))?;
+            Ok((id, depth))
+        })
+        .collect()
+}
+
 /// Mark the deployment `id` as synced
 pub fn set_synced(conn: &PgConnection, id: &DeploymentHash) -> Result<(), StoreError> {
     use subgraph_deployment as d;
@@ -580,6 +980,35 @@ pub fn set_synced(conn: &PgConnection, id: &DeploymentHash) -> Result<(), StoreE
     Ok(())
 }
 
+fn deployments_with_synced(
+    conn: &PgConnection,
+    synced: bool,
+) -> Result<Vec<DeploymentHash>, StoreError> {
+    use subgraph_deployment as d;
+
+    d::table
+        .filter(d::synced.eq(synced))
+        .select(d::deployment)
+        .order_by(d::deployment)
+        .load::<String>(conn)?
+        .into_iter()
+        .map(|id| {
+            DeploymentHash::new(id)
+                .map_err(|id| constraint_violation!("illegal deployment id: {}", id))
+        })
+        .collect()
+}
+
+/// Return all deployments in this shard that have finished syncing
+pub fn synced_deployments(conn: &PgConnection) -> Result<Vec<DeploymentHash>, StoreError> {
+    deployments_with_synced(conn, true)
+}
+
+/// Return all deployments in this shard that are still syncing
+pub fn syncing_deployments(conn: &PgConnection) -> Result<Vec<DeploymentHash>, StoreError> {
+    deployments_with_synced(conn, false)
+}
+
 /// Returns `true` if the deployment (as identified by `site.id`)
 pub fn exists(conn: &PgConnection, site: &Site) -> Result<bool, StoreError> {
     use subgraph_deployment as d;
@@ -1017,6 +1446,28 @@ pub fn update_entity_count(
     Ok(())
 }
 
+/// Return the entity count stored for the deployment together with the
+/// number of entities that `full_count_query` actually finds
+pub fn entity_counts(
+    conn: &PgConnection,
+    site: &Site,
+    full_count_query: &str,
+) -> Result<(i64, i64), StoreError> {
+    use subgraph_deployment as d;
+
+    let stored = d::table
+        .filter(d::id.eq(site.id))
+        .select(d::entity_count)
+        .get_result::<BigDecimal>(conn)?
+        .to_i64()
+        .ok_or_else(|| {
+            constraint_violation!("entity count for {} does not fit into i64", site.deployment)
+        })?;
+    let full_count_query = format!("coalesce(({}), 0)::int8", full_count_query);
+    let actual = select(sql::<diesel::sql_types::BigInt>(&full_count_query)).get_result(conn)?;
+    Ok((stored, actual))
+}
+
 /// Set the deployment's entity count to whatever `full_count_query` produces
 pub fn set_entity_count(
     conn: &PgConnection,
@@ -1031,3 +1482,65 @@ pub fn set_entity_count(
         .execute(conn)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graph::prelude::{o, slog};
+
+    fn database_error(kind: DatabaseErrorKind, message: &str) -> StoreError {
+        StoreError::from(DieselError::DatabaseError(
+            kind,
+            Box::new(message.to_string()),
+        ))
+    }
+
+    #[test]
+    fn retries_transaction_conflicts() {
+        let logger = Logger::root(slog::Discard, o!());
+
+        // Serialization failures and deadlocks are retried until `f`
+        // succeeds
+        let mut errors = vec![
+            database_error(
+                DatabaseErrorKind::SerializationFailure,
+                "could not serialize",
+            ),
+            database_error(DatabaseErrorKind::__Unknown, "deadlock detected"),
+        ];
+        let mut calls = 0;
+        let res = retry_on_transaction_conflict(&logger, TRANSACTION_CONFLICT_ATTEMPTS, || {
+            calls += 1;
+            match errors.pop() {
+                Some(e) => Err(e),
+                None => Ok(calls),
+            }
+        });
+        assert_eq!(3, res.unwrap());
+
+        // Other errors are returned right away
+        let mut calls = 0;
+        let res: Result<(), _> =
+            retry_on_transaction_conflict(&logger, TRANSACTION_CONFLICT_ATTEMPTS, || {
+                calls += 1;
+                Err(database_error(
+                    DatabaseErrorKind::UniqueViolation,
+                    "duplicate key",
+                ))
+            });
+        assert!(res.is_err());
+        assert_eq!(1, calls);
+
+        // A conflict that persists is returned after the last attempt
+        let mut calls = 0;
+        let res: Result<(), _> = retry_on_transaction_conflict(&logger, 3, || {
+            calls += 1;
+            Err(database_error(
+                DatabaseErrorKind::SerializationFailure,
+                "could not serialize",
+            ))
+        });
+        assert!(res.unwrap_err().is_transaction_conflict());
+        assert_eq!(3, calls);
+    }
+}
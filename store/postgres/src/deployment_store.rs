@@ -6,6 +6,9 @@ use diesel::prelude::*;
 use diesel::r2d2::{ConnectionManager, PooledConnection};
 use graph::components::store::{EntityType, StoredDynamicDataSource};
 use graph::data::subgraph::status;
+use graph::ext::futures::Canceler;
+use graph::prelude::futures03::channel::oneshot;
+use graph::prelude::futures03::future::{self, Either};
 use graph::prelude::{
     tokio, CancelHandle, CancelToken, CancelableError, EntityOperation, PoolWaitStats,
     SubgraphDeploymentEntity,
@@ -19,7 +22,7 @@ use std::iter::FromIterator;
 use std::ops::Bound;
 use std::ops::Deref;
 use std::sync::{atomic::AtomicUsize, Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use graph::components::store::EntityCollection;
 use graph::components::subgraph::ProofOfIndexingFinisher;
@@ -27,9 +30,9 @@ use graph::constraint_violation;
 use graph::data::subgraph::schema::{DeploymentCreate, SubgraphError, POI_OBJECT};
 use graph::prelude::{
     anyhow, debug, info, o, warn, web3, ApiSchema, AttributeNames, BlockNumber, BlockPtr,
-    CheapClone, DeploymentHash, DeploymentState, Entity, EntityKey, EntityModification,
-    EntityQuery, Error, Logger, QueryExecutionError, Schema, StopwatchMetrics, StoreError,
-    StoreEvent, UnfailOutcome, Value, ENV_VARS,
+    CheapClone, CounterVec, DeploymentHash, DeploymentState, Entity, EntityKey, EntityModification,
+    EntityQuery, Error, HistogramVec, Logger, MetricsRegistry, QueryExecutionError, Schema,
+    StopwatchMetrics, StoreError, StoreEvent, UnfailOutcome, Value, ENV_VARS,
 };
 use graph_graphql::prelude::api_schema;
 use web3::types::Address;
@@ -93,6 +96,64 @@ pub struct StoreInner {
     /// hosts this because it lives long enough, but it is managed from
     /// the entities module
     pub(crate) layout_cache: LayoutCache,
+
+    /// Entity queries that take longer than this are reported as slow
+    slow_query_threshold: Option<Duration>,
+    /// Counts slow entity queries by entity type
+    slow_queries: CounterVec,
+    /// Counts entity queries by deployment
+    query_count: CounterVec,
+    /// Measures how long entity queries take by deployment
+    query_execution_time: HistogramVec,
+
+    /// Limits how many entity types `get_many` looks up in parallel
+    /// across all deployments so that parallel lookups can not use up
+    /// the connection pool
+    find_many_permits: Arc<tokio::sync::Semaphore>,
+}
+
+/// Keeps the query on a database backend cancelable; see
+/// `DeploymentStore::cancel_on`
+pub(crate) struct BackendCanceler {
+    running: Arc<Mutex<bool>>,
+    _done: oneshot::Sender<()>,
+}
+
+impl Drop for BackendCanceler {
+    fn drop(&mut self) {
+        *self.running.lock().unwrap() = false;
+    }
+}
+
+/// Run `f` inside a savepoint on `conn` so that, if it fails, only the
+/// changes it made are rolled back, and the enclosing transaction can
+/// continue. This is meant for isolating part of the work that
+/// `DeploymentStore::transact_block_operations` does in its transaction on
+/// the shard connection. Diesel turns the nested transaction into
+/// `SAVEPOINT`, `RELEASE SAVEPOINT` and `ROLLBACK TO SAVEPOINT`. It is an
+/// error to call this when `conn` is not in a transaction
+pub fn savepoint<T, F>(conn: &PgConnection, f: F) -> Result<T, StoreError>
+where
+    F: FnOnce() -> Result<T, StoreError>,
+{
+    use diesel::connection::TransactionManager;
+
+    if TransactionManager::<PgConnection>::get_transaction_depth(conn.transaction_manager()) == 0 {
+        return Err(constraint_violation!(
+            "a savepoint can only be used inside a transaction"
+        ));
+    }
+    conn.transaction(f)
+}
+
+/// The changes that processing one block made, as written by
+/// `DeploymentStore::transact_block_batch`
+pub(crate) struct BlockWrite<'a> {
+    pub block_ptr: &'a BlockPtr,
+    pub firehose_cursor: Option<&'a str>,
+    pub mods: &'a [EntityModification],
+    pub data_sources: &'a [StoredDynamicDataSource],
+    pub deterministic_errors: &'a [SubgraphError],
 }
 
 /// Storage of the data for individual deployments. Each `DeploymentStore`
@@ -115,6 +176,7 @@ impl DeploymentStore {
         pool: ConnectionPool,
         read_only_pools: Vec<ConnectionPool>,
         mut pool_weights: Vec<usize>,
+        registry: Arc<dyn MetricsRegistry>,
     ) -> Self {
         // Create a store-specific logger
         let logger = logger.new(o!("component" => "Store"));
@@ -140,7 +202,39 @@ impl DeploymentStore {
         replica_order.shuffle(&mut rng);
         debug!(logger, "Using postgres host order {:?}", replica_order);
 
+        let slow_queries = registry
+            .global_counter_vec(
+                "store_slow_query_total",
+                "Number of entity queries that took longer than GRAPH_STORE_SLOW_QUERY_THRESHOLD",
+                &["entity_type"],
+            )
+            .expect("failed to create `store_slow_query_total` counter");
+        let query_count = registry
+            .global_counter_vec(
+                "store_query_count",
+                "Number of entity queries run against a deployment",
+                &["deployment"],
+            )
+            .expect("failed to create `store_query_count` counter");
+        let query_execution_time = registry
+            .global_histogram_vec(
+                "store_query_execution_time",
+                "Execution time of entity queries against a deployment in seconds",
+                &["deployment"],
+            )
+            .expect("failed to create `store_query_execution_time` histogram");
+
+        let layout_cache = LayoutCache::new(
+            ENV_VARS.store.query_stats_refresh_interval,
+            ENV_VARS.store.layout_cache_size,
+            registry.as_ref(),
+            pool.shard.as_str(),
+        );
+
         // Create the store
+        let find_many_permits = Arc::new(tokio::sync::Semaphore::new(
+            (pool.max_size() as usize / 2).max(1),
+        ));
         let store = StoreInner {
             logger: logger.clone(),
             pool,
@@ -148,7 +242,12 @@ impl DeploymentStore {
             replica_order,
             conn_round_robin_counter: AtomicUsize::new(0),
             subgraph_cache: Mutex::new(LruCache::with_capacity(100)),
-            layout_cache: LayoutCache::new(ENV_VARS.store.query_stats_refresh_interval),
+            layout_cache,
+            slow_query_threshold: ENV_VARS.store.slow_query_threshold,
+            slow_queries,
+            query_count,
+            query_execution_time,
+            find_many_permits,
         };
 
         DeploymentStore(Arc::new(store))
@@ -201,6 +300,14 @@ impl DeploymentStore {
         })
     }
 
+    pub(crate) fn manifest_metadata(
+        &self,
+        site: &Site,
+    ) -> Result<deployment::ManifestMetadata, StoreError> {
+        let conn = self.get_conn()?;
+        deployment::manifest_metadata(&conn, site)
+    }
+
     pub(crate) fn load_deployment(
         &self,
         site: &Site,
@@ -226,10 +333,26 @@ impl DeploymentStore {
         site: Arc<Site>,
         query: EntityQuery,
     ) -> Result<Vec<T>, QueryExecutionError> {
+        let cancel = query.cancel.clone();
+        let is_canceled = || cancel.as_ref().map_or(false, |cancel| cancel.is_canceled());
+        if is_canceled() {
+            return Err(QueryExecutionError::Cancelled);
+        }
+
         let layout = self.layout(conn, site)?;
 
         let logger = query.logger.unwrap_or_else(|| self.logger.clone());
-        layout.query(
+        // We only need the entity types to report slow queries
+        let entity_types = self.slow_query_threshold.map(|_| match &query.collection {
+            EntityCollection::All(types) => types.iter().map(|(et, _)| et.clone()).collect(),
+            EntityCollection::Window(windows) => windows
+                .iter()
+                .map(|window| window.child_type.clone())
+                .collect::<Vec<_>>(),
+        });
+
+        let start = Instant::now();
+        let res = layout.query(
             &logger,
             conn,
             query.collection,
@@ -238,7 +361,94 @@ impl DeploymentStore {
             query.range,
             query.block,
             query.query_id,
-        )
+        );
+        let elapsed = start.elapsed();
+
+        let deployment = layout.site.deployment.as_str();
+        self.query_count.with_label_values(&[deployment]).inc();
+        self.query_execution_time
+            .with_label_values(&[deployment])
+            .observe(elapsed.as_secs_f64());
+
+        if let (Some(threshold), Some(entity_types)) = (self.slow_query_threshold, entity_types) {
+            if elapsed > threshold {
+                let names: Vec<_> = entity_types.iter().map(|et| et.as_str()).collect();
+                warn!(logger, "Slow entity query";
+                      "deployment" => deployment,
+                      "entity_types" => names.join(", "),
+                      "time_ms" => elapsed.as_millis());
+                for entity_type in &entity_types {
+                    self.slow_queries
+                        .with_label_values(&[entity_type.as_str()])
+                        .inc();
+                }
+            }
+        }
+        // A query that we canceled fails with a database error; report
+        // the cancelation instead
+        res.map_err(|e| {
+            if is_canceled() {
+                QueryExecutionError::Cancelled
+            } else {
+                e
+            }
+        })
+    }
+
+    /// Cancel the statement that the backend with process id `pid` on
+    /// `replica` is running as soon as `handle` is canceled. We stop
+    /// watching `handle` when the returned `BackendCanceler` is dropped,
+    /// which must happen before the connection to that backend is
+    /// returned to the pool
+    pub(crate) fn cancel_on(
+        &self,
+        replica: ReplicaId,
+        pid: i32,
+        handle: &CancelHandle,
+    ) -> BackendCanceler {
+        let (cancel_sender, cancel_receiver) = oneshot::channel();
+        let (done_sender, done_receiver) = oneshot::channel();
+        handle.add_cancel_sender(cancel_sender);
+
+        let running = Arc::new(Mutex::new(true));
+        let canceler = BackendCanceler {
+            running: running.cheap_clone(),
+            _done: done_sender,
+        };
+
+        let store = self.cheap_clone();
+        graph::spawn(async move {
+            if let Either::Right(_) = future::select(done_receiver, cancel_receiver).await {
+                graph::spawn_blocking_allow_panic(move || {
+                    // Check the connection for the cancel request out
+                    // before taking the lock: the query we cancel can only
+                    // return its connection to the pool once it has the
+                    // lock, and with an exhausted pool we would otherwise
+                    // wait for that connection while the query waits for us
+                    let conn = match store.get_replica_conn(replica) {
+                        Ok(conn) => conn,
+                        Err(e) => {
+                            warn!(store.logger, "Failed to cancel query";
+                                  "pid" => pid, "error" => e.to_string());
+                            return;
+                        }
+                    };
+                    // Holding the lock keeps the query from finishing and
+                    // the connection from going back into the pool while
+                    // we cancel, so that we never cancel an unrelated
+                    // statement on the same backend
+                    let running = running.lock().unwrap();
+                    if !*running {
+                        return;
+                    }
+                    if let Err(e) = catalog::cancel_backend(&conn, pid) {
+                        warn!(store.logger, "Failed to cancel query";
+                              "pid" => pid, "error" => e.to_string());
+                    }
+                });
+            }
+        });
+        canceler
     }
 
     fn check_interface_entity_uniqueness(
@@ -484,6 +694,20 @@ impl DeploymentStore {
         Ok(conn)
     }
 
+    /// Like `get_replica_conn`, but also return the process id of the
+    /// database backend for the connection so that queries on it can be
+    /// canceled with `cancel_on`
+    pub(crate) fn get_replica_conn_with_backend_pid(
+        &self,
+        replica: ReplicaId,
+    ) -> Result<(PooledConnection<ConnectionManager<PgConnection>>, i32), StoreError> {
+        let pool = match replica {
+            ReplicaId::Main => &self.pool,
+            ReplicaId::ReadOnly(idx) => &self.read_only_pools[idx],
+        };
+        pool.get_with_backend_pid()
+    }
+
     pub(crate) async fn query_permit(
         &self,
         replica: ReplicaId,
@@ -601,6 +825,25 @@ impl DeploymentStore {
         })
     }
 
+    /// Compare the entity count stored for each of `sites` with the actual
+    /// number of entities in the deployment and return the deployments
+    /// where the two differ as `(deployment, stored, actual)`
+    pub(crate) fn audit_entity_counts(
+        &self,
+        sites: &[Arc<Site>],
+    ) -> Result<Vec<(DeploymentHash, i64, i64)>, StoreError> {
+        let conn = self.get_conn()?;
+        let mut mismatches = Vec::new();
+        for site in sites {
+            let layout = self.layout(&conn, site.cheap_clone())?;
+            let (stored, actual) = deployment::entity_counts(&conn, site, &layout.count_query)?;
+            if stored != actual {
+                mismatches.push((site.deployment.clone(), stored, actual));
+            }
+        }
+        Ok(mismatches)
+    }
+
     pub(crate) fn deployment_exists_and_synced(
         &self,
         id: &DeploymentHash,
@@ -609,6 +852,36 @@ impl DeploymentStore {
         deployment::exists_and_synced(&conn, id.as_str())
     }
 
+    /// Return the deployments in this shard that have finished syncing if
+    /// `synced` is `true`, and the ones that are still syncing otherwise
+    pub(crate) fn deployments_by_synced(
+        &self,
+        synced: bool,
+    ) -> Result<Vec<DeploymentHash>, StoreError> {
+        let conn = self.get_conn()?;
+        if synced {
+            deployment::synced_deployments(&conn)
+        } else {
+            deployment::syncing_deployments(&conn)
+        }
+    }
+
+    /// Return the deployments in this shard that are grafted onto `base`.
+    /// If `pending_only` is `true`, only return the ones that still need
+    /// to copy data from `base`
+    pub(crate) fn graft_dependents(
+        &self,
+        base: &DeploymentHash,
+        pending_only: bool,
+    ) -> Result<Vec<DeploymentHash>, StoreError> {
+        let conn = self.get_conn()?;
+        if pending_only {
+            deployment::pending_graft_dependents(&conn, base)
+        } else {
+            deployment::graft_dependents(&conn, base)
+        }
+    }
+
     pub(crate) fn deployment_synced(&self, id: &DeploymentHash) -> Result<(), StoreError> {
         let conn = self.get_conn()?;
         conn.transaction(|| deployment::set_synced(&conn, id))
@@ -675,6 +948,17 @@ impl DeploymentStore {
         .await
     }
 
+    /// Runs the SQL `ANALYZE` command on all tables of a deployment.
+    pub(crate) async fn analyze_all(&self, site: Arc<Site>) -> Result<(), StoreError> {
+        let store = self.clone();
+        self.with_conn(move |conn, _| {
+            let layout = store.layout(conn, site)?;
+            layout.analyze_tables(conn)?;
+            Ok(())
+        })
+        .await
+    }
+
     /// Creates a new index in the specified Entity table if it doesn't already exist.
     ///
     /// This is a potentially time-consuming operation.
@@ -804,6 +1088,17 @@ impl DeploymentStore {
         .map_err(Into::into)
     }
 
+    pub(crate) fn poi_for_region(
+        &self,
+        site: Arc<Site>,
+        region: &str,
+        block: BlockNumber,
+    ) -> Result<Option<Entity>, StoreError> {
+        let conn = self.get_conn()?;
+        let layout = self.layout(&conn, site)?;
+        layout.poi_for_region(&conn, region, block)
+    }
+
     pub(crate) async fn get_proof_of_indexing(
         &self,
         site: Arc<Site>,
@@ -918,12 +1213,71 @@ impl DeploymentStore {
         if ids_for_type.is_empty() {
             return Ok(BTreeMap::new());
         }
+        let threshold = ENV_VARS.store.find_many_parallel_threshold;
+        #[cfg(debug_assertions)]
+        let threshold = test_support::find_many_parallel_threshold(site.id).unwrap_or(threshold);
+        if threshold > 0 && ids_for_type.len() > threshold {
+            return self.get_many_parallel(site, ids_for_type, block);
+        }
+
         let conn = self.get_conn()?;
         let layout = self.layout(&conn, site)?;
 
         layout.find_many(&conn, ids_for_type, block)
     }
 
+    /// Like `get_many`, but look up the entities of each entity type in a
+    /// separate task on the blocking thread pool, with its own connection.
+    /// At most half as many lookups as the pool has connections run at the
+    /// same time, no matter how many callers there are. If any of the
+    /// lookups fails, the error for the first entity type that failed is
+    /// returned
+    pub(crate) fn get_many_parallel(
+        &self,
+        site: Arc<Site>,
+        ids_for_type: &BTreeMap<&EntityType, Vec<&str>>,
+        block: BlockNumber,
+    ) -> Result<BTreeMap<EntityType, Vec<Entity>>, StoreError> {
+        // Return the connection before we fan out so that we do not hold
+        // on to it while the lookups wait for connections of their own
+        let layout = {
+            let conn = self.get_conn()?;
+            self.layout(&conn, site)?
+        };
+
+        let lookups = ids_for_type.iter().map(|(entity_type, ids)| {
+            let layout = layout.cheap_clone();
+            let pool = self.pool.clone();
+            let permits = self.find_many_permits.cheap_clone();
+            let entity_type = (*entity_type).clone();
+            let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+            async move {
+                let _permit = permits
+                    .acquire_owned()
+                    .await
+                    .map_err(|e| constraint_violation!("find_many permits are gone: {}", e))?;
+                graph::spawn_blocking_allow_panic(move || {
+                    let conn = pool.get()?;
+                    let ids = ids.iter().map(|id| id.as_str()).collect();
+                    let ids_for_type = BTreeMap::from([(&entity_type, ids)]);
+                    layout.find_many(&conn, &ids_for_type, block)
+                })
+                .await
+                .map_err(|e| constraint_violation!("lookup for find_many panicked: {}", e))?
+            }
+        });
+        // We are called from synchronous code that might itself run inside
+        // `graph::block_on`, and therefore can not use the tokio runtime to
+        // wait for the lookups
+        let results = graph::prelude::futures03::executor::block_on(future::join_all(lookups));
+
+        let mut entities_for_type = BTreeMap::new();
+        for entities in results {
+            entities_for_type.extend(entities?);
+        }
+        Ok(entities_for_type)
+    }
+
     pub(crate) fn get_changes(
         &self,
         site: Arc<Site>,
@@ -956,10 +1310,32 @@ impl DeploymentStore {
         stopwatch: &StopwatchMetrics,
         data_sources: &[StoredDynamicDataSource],
         deterministic_errors: &[SubgraphError],
+    ) -> Result<StoreEvent, StoreError> {
+        let write = BlockWrite {
+            block_ptr: block_ptr_to,
+            firehose_cursor,
+            mods,
+            data_sources,
+            deterministic_errors,
+        };
+        self.transact_block_batch(site, &[write], stopwatch)
+    }
+
+    /// Write the changes for several consecutive blocks in one
+    /// transaction. The blocks are applied in the order in which they
+    /// appear in `writes`, exactly as if each of them had been written
+    /// with `transact_block_operations`, but without the overhead of a
+    /// separate transaction for each of them
+    pub(crate) fn transact_block_batch(
+        &self,
+        site: Arc<Site>,
+        writes: &[BlockWrite],
+        stopwatch: &StopwatchMetrics,
     ) -> Result<StoreEvent, StoreError> {
         // All operations should apply only to data or metadata for this subgraph
-        if mods
+        if writes
             .iter()
+            .flat_map(|write| write.mods.iter())
             .map(|modification| modification.entity_key())
             .any(|key| key.subgraph_id != site.deployment)
         {
@@ -974,46 +1350,51 @@ impl DeploymentStore {
             self.get_conn()?
         };
 
-        let event = conn.transaction(|| -> Result<_, StoreError> {
-            // Emit a store event for the changes we are about to make. We
-            // wait with sending it until we have done all our other work
-            // so that we do not hold a lock on the notification queue
-            // for longer than we have to
-            let event: StoreEvent = mods.iter().collect();
-
-            // Make the changes
-            let layout = self.layout(&conn, site.clone())?;
-            let section = stopwatch.start_section("apply_entity_modifications");
-            let count = self.apply_entity_modifications(
-                &conn,
-                layout.as_ref(),
-                mods,
-                block_ptr_to,
-                stopwatch,
-            )?;
-            section.end();
-
-            dynds::insert(&conn, &site.deployment, data_sources, block_ptr_to)?;
-
-            if !deterministic_errors.is_empty() {
-                deployment::insert_subgraph_errors(
-                    &conn,
-                    &site.deployment,
-                    deterministic_errors,
-                    block_ptr_to.block_number(),
-                )?;
-            }
+        let attempts = deployment::TRANSACTION_CONFLICT_ATTEMPTS;
+        let event = deployment::retry_on_transaction_conflict(&self.logger, attempts, || {
+            conn.transaction(|| -> Result<_, StoreError> {
+                // Emit a store event for the changes we are about to make. We
+                // wait with sending it until we have done all our other work
+                // so that we do not hold a lock on the notification queue
+                // for longer than we have to
+                let event: StoreEvent = writes.iter().flat_map(|write| write.mods.iter()).collect();
+
+                let layout = self.layout(&conn, site.clone())?;
+                for write in writes {
+                    // Make the changes
+                    let section = stopwatch.start_section("apply_entity_modifications");
+                    let count = self.apply_entity_modifications(
+                        &conn,
+                        layout.as_ref(),
+                        write.mods,
+                        write.block_ptr,
+                        stopwatch,
+                    )?;
+                    section.end();
+
+                    dynds::insert(&conn, &site.deployment, write.data_sources, write.block_ptr)?;
+
+                    if !write.deterministic_errors.is_empty() {
+                        deployment::insert_subgraph_errors(
+                            &conn,
+                            &site.deployment,
+                            write.deterministic_errors,
+                            write.block_ptr.block_number(),
+                        )?;
+                    }
 
-            deployment::transact_block(
-                &conn,
-                &site,
-                block_ptr_to,
-                firehose_cursor,
-                layout.count_query.as_str(),
-                count,
-            )?;
+                    deployment::transact_block(
+                        &conn,
+                        &site,
+                        write.block_ptr,
+                        write.firehose_cursor,
+                        layout.count_query.as_str(),
+                        count,
+                    )?;
+                }
 
-            Ok(event)
+                Ok(event)
+            })
         })?;
 
         Ok(event)
@@ -1043,7 +1424,14 @@ impl DeploymentStore {
                 }
             }
 
-            deployment::revert_block_ptr(conn, &site.deployment, block_ptr_to.clone())?;
+            // Revert in one step if we still know about the block we are
+            // reverting to; older deployments might not have enough
+            // history yet, and we revert the plain way for them
+            if deployment::block_history_contains(conn, &site, &block_ptr_to)? {
+                deployment::revert_to_block(conn, &site, &block_ptr_to)?;
+            } else {
+                deployment::revert_block_ptr(conn, &site, block_ptr_to.clone())?;
+            }
 
             if let Some(cursor) = firehose_cursor {
                 deployment::update_firehose_cursor(conn, &site.deployment, cursor)
@@ -1080,6 +1468,29 @@ impl DeploymentStore {
         Ok(event)
     }
 
+    /// Remove all entities and dynamic data sources of the deployment and
+    /// reset its block pointer so that it will be indexed again from the
+    /// start. The deployment must not be running while this happens.
+    /// Grafted deployments can not be truncated since their data up to the
+    /// graft point would have to be copied again
+    pub(crate) fn truncate(&self, site: Arc<Site>) -> Result<StoreEvent, StoreError> {
+        let conn = self.get_conn()?;
+        conn.transaction(|| -> Result<_, StoreError> {
+            if let Some((base, _)) = deployment::graft_point(&conn, &site.deployment)? {
+                return Err(StoreError::Unknown(anyhow!(
+                    "can not truncate deployment {} since it is grafted onto {}",
+                    site.deployment,
+                    base
+                )));
+            }
+            let layout = self.layout(&conn, site.cheap_clone())?;
+            let event = layout.truncate(&conn)?;
+            crate::dynds::drop(&conn, &site.deployment)?;
+            deployment::clear_block_ptr(&conn, &site)?;
+            Ok(event)
+        })
+    }
+
     pub(crate) fn rewind(
         &self,
         site: Arc<Site>,
@@ -1131,6 +1542,24 @@ impl DeploymentStore {
             .await
     }
 
+    /// Load the states of the deployments in `ids` in one query. Deployments
+    /// that do not exist are left out of the result
+    pub(crate) fn deployment_states_from_ids(
+        &self,
+        ids: &[DeploymentHash],
+    ) -> Result<Vec<DeploymentState>, StoreError> {
+        let conn = self.get_conn()?;
+        deployment::states(&conn, ids)
+    }
+
+    pub(crate) fn deployments_exceeding_reorg_depth(
+        &self,
+        threshold: u32,
+    ) -> Result<Vec<(DeploymentHash, u32)>, StoreError> {
+        let conn = self.get_conn()?;
+        deployment::deployments_exceeding_reorg_depth(&conn, threshold)
+    }
+
     pub(crate) async fn fail_subgraph(
         &self,
         id: DeploymentHash,
@@ -1186,6 +1615,14 @@ impl DeploymentStore {
         .await
     }
 
+    pub(crate) fn graft_point(
+        &self,
+        id: &DeploymentHash,
+    ) -> Result<Option<(DeploymentHash, BlockPtr)>, StoreError> {
+        let conn = self.get_conn()?;
+        deployment::graft_point(&conn, id)
+    }
+
     pub(crate) fn graft_pending(
         &self,
         id: &DeploymentHash,
@@ -1275,7 +1712,7 @@ impl DeploymentStore {
 
                 // Set the block ptr to the graft point to signal that we successfully
                 // performed the graft
-                crate::deployment::forward_block_ptr(&conn, &dst.site.deployment, &block)?;
+                crate::deployment::forward_block_ptr(&conn, &dst.site, &block)?;
                 info!(logger, "Subgraph successfully initialized";
                     "time_ms" => start.elapsed().as_millis());
                 Ok(())
@@ -1524,3 +1961,44 @@ fn resolve_column_names<'a, T: AsRef<str>>(
         })
         .collect()
 }
+
+/// Support for overriding `GRAPH_STORE_FIND_MANY_PARALLEL_THRESHOLD` for
+/// individual deployments, only for use in tests. In release builds, the
+/// override is compiled out
+#[cfg(debug_assertions)]
+pub(crate) mod test_support {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use graph::{
+        components::store::{DeploymentId, DeploymentLocator},
+        prelude::lazy_static,
+    };
+
+    lazy_static! {
+        static ref FIND_MANY_PARALLEL_THRESHOLD: Mutex<HashMap<DeploymentId, usize>> =
+            Mutex::new(HashMap::new());
+    }
+
+    pub(super) fn find_many_parallel_threshold(deployment: DeploymentId) -> Option<usize> {
+        FIND_MANY_PARALLEL_THRESHOLD
+            .lock()
+            .unwrap()
+            .get(&deployment)
+            .copied()
+    }
+
+    /// Use `threshold` instead of `GRAPH_STORE_FIND_MANY_PARALLEL_THRESHOLD`
+    /// when deciding whether to look up entities of `deployment` in
+    /// parallel; `None` goes back to using the environment variable
+    pub fn set_find_many_parallel_threshold(
+        deployment: &DeploymentLocator,
+        threshold: Option<usize>,
+    ) {
+        let mut thresholds = FIND_MANY_PARALLEL_THRESHOLD.lock().unwrap();
+        match threshold {
+            Some(threshold) => thresholds.insert(deployment.id, threshold),
+            None => thresholds.remove(&deployment.id),
+        };
+    }
+}
@@ -47,7 +47,13 @@ pub mod layout_for_tests {
     pub use crate::primary::{
         make_dummy_site, Connection, Mirror, Namespace, EVENT_TAP, EVENT_TAP_ENABLED,
     };
+    pub use crate::relational::test_support::update_if_unchanged;
     pub use crate::relational::*;
+    pub use crate::relational_queries::{Count, RevertCountQuery};
+    pub mod deployment_store {
+        pub use crate::deployment_store::savepoint;
+        pub use crate::deployment_store::test_support::set_find_many_parallel_threshold;
+    }
     pub mod writable {
         pub use crate::writable::test_support::allow_steps;
     }
@@ -56,10 +62,12 @@ pub mod layout_for_tests {
 pub use self::block_store::BlockStore;
 pub use self::chain_head_listener::ChainHeadUpdateListener;
 pub use self::chain_store::ChainStore;
+pub use self::deployment::ManifestMetadata;
 pub use self::detail::DeploymentDetail;
 pub use self::jobs::register as register_jobs;
 pub use self::notification_listener::NotificationSender;
 pub use self::primary::{db_version, UnusedDeployment};
+pub use self::relational::SchemaChange;
 pub use self::store::Store;
 pub use self::store_events::SubscriptionManager;
 pub use self::subgraph_store::{unused, DeploymentPlacer, Shard, SubgraphStore, PRIMARY_SHARD};
@@ -32,7 +32,8 @@ use graph::{
     data::subgraph::status,
     prelude::{
         anyhow, bigdecimal::ToPrimitive, serde_json, DeploymentHash, EntityChange,
-        EntityChangeOperation, NodeId, StoreError, SubgraphName, SubgraphVersionSwitchingMode,
+        EntityChangeOperation, Logger, NodeId, StoreError, SubgraphName,
+        SubgraphVersionSwitchingMode,
     },
 };
 use graph::{data::subgraph::schema::generate_entity_id, prelude::StoreEvent};
@@ -50,6 +51,7 @@ use std::{
 use crate::{
     block_range::UNVERSIONED_RANGE,
     connection_pool::{ConnectionPool, ForeignServer},
+    deployment::{self, TransactionConflict},
     detail::DeploymentDetail,
     subgraph_store::{unused, Shard, PRIMARY_SHARD},
     NotificationSender,
@@ -476,6 +478,69 @@ mod queries {
         }
     }
 
+    /// Return the names of all subgraphs that have a current version
+    /// together with the deployment of that version, ordered by name
+    pub(super) fn current_deployments(
+        conn: &PgConnection,
+    ) -> Result<Vec<(SubgraphName, DeploymentHash)>, StoreError> {
+        v::table
+            .inner_join(s::table.on(s::current_version.eq(v::id.nullable())))
+            .order_by(s::name.asc())
+            .select((s::name, v::deployment))
+            .load::<(String, String)>(conn)?
+            .into_iter()
+            .map(|(name, id)| {
+                let name = SubgraphName::new(name.clone())
+                    .map_err(|()| constraint_violation!("illegal subgraph name: {}", name))?;
+                let id = DeploymentHash::new(id)
+                    .map_err(|id| constraint_violation!("illegal deployment id: {}", id))?;
+                Ok((name, id))
+            })
+            .collect()
+    }
+
+    /// Return the deployment of the pending version of the subgraph
+    /// `name`, or `None` if the subgraph has no pending version
+    pub(super) fn pending_deployment_for_subgraph(
+        conn: &PgConnection,
+        name: &SubgraphName,
+    ) -> Result<Option<DeploymentHash>, StoreError> {
+        v::table
+            .inner_join(s::table.on(s::pending_version.eq(v::id.nullable())))
+            .filter(s::name.eq(name.as_str()))
+            .select(v::deployment)
+            .first::<String>(conn)
+            .optional()?
+            .map(|id| {
+                DeploymentHash::new(id)
+                    .map_err(|id| constraint_violation!("illegal deployment id: {}", id))
+            })
+            .transpose()
+    }
+
+    /// Return all versions of the subgraph `name`, ordered by when they
+    /// were created, as `(version_id, deployment, is_current, is_pending)`
+    pub(super) fn subgraph_versions(
+        conn: &PgConnection,
+        name: &SubgraphName,
+    ) -> Result<Vec<(String, DeploymentHash, bool, bool)>, StoreError> {
+        v::table
+            .inner_join(s::table.on(v::subgraph.eq(s::id)))
+            .filter(s::name.eq(name.as_str()))
+            .order_by((v::created_at.asc(), v::vid.asc()))
+            .select((v::id, v::deployment, s::current_version, s::pending_version))
+            .load::<(String, String, Option<String>, Option<String>)>(conn)?
+            .into_iter()
+            .map(|(version, deployment, current, pending)| {
+                let deployment = DeploymentHash::new(deployment)
+                    .map_err(|id| constraint_violation!("illegal deployment id: {}", id))?;
+                let is_current = current.as_ref() == Some(&version);
+                let is_pending = pending.as_ref() == Some(&version);
+                Ok((version, deployment, is_current, is_pending))
+            })
+            .collect()
+    }
+
     pub(super) fn deployments_for_subgraph(
         conn: &PgConnection,
         name: &str,
@@ -696,6 +761,24 @@ impl<'a> Connection<'a> {
         self.conn.transaction(f)
     }
 
+    /// Run `f` in a transaction, and rerun it with the backoff of
+    /// `deployment::retry_on_transaction_conflict` if the database aborted
+    /// the transaction because of a deadlock or a serialization failure.
+    /// Gives up with the last error once `f` has run `attempts` times.
+    /// Since `f` can run several times, it must be idempotent
+    pub(crate) fn transaction_with_retry<T, E, F>(
+        &self,
+        logger: &Logger,
+        attempts: usize,
+        f: F,
+    ) -> Result<T, E>
+    where
+        F: Fn() -> Result<T, E>,
+        E: From<diesel::result::Error> + TransactionConflict + std::fmt::Display,
+    {
+        deployment::retry_on_transaction_conflict(logger, attempts, || self.conn.transaction(&f))
+    }
+
     /// Signal any copy process that might be copying into one of these
     /// deployments that it should stop. Copying is cancelled whenever we
     /// remove the assignment for a deployment
@@ -958,9 +1041,37 @@ impl<'a> Connection<'a> {
         Ok(changes)
     }
 
+    /// Create subgraph versions for all `specs` in a single transaction.
+    /// If creating any of the versions fails, none of them are created.
+    /// Since the transaction can touch many subgraphs, it is retried if it
+    /// conflicts with a concurrent transaction
+    pub fn create_subgraph_versions<F>(
+        &self,
+        logger: &Logger,
+        specs: Vec<(SubgraphName, &Site, NodeId)>,
+        mode: SubgraphVersionSwitchingMode,
+        exists_and_synced: F,
+    ) -> Result<Vec<EntityChange>, StoreError>
+    where
+        F: Fn(&DeploymentHash) -> Result<bool, StoreError>,
+    {
+        self.transaction_with_retry(logger, deployment::TRANSACTION_CONFLICT_ATTEMPTS, || {
+            let mut changes = Vec::new();
+            for (name, site, node_id) in &specs {
+                changes.extend(self.create_subgraph_version(
+                    name.clone(),
+                    site,
+                    node_id.clone(),
+                    mode,
+                    &exists_and_synced,
+                )?);
+            }
+            Ok(changes)
+        })
+    }
+
     pub fn remove_subgraph(&self, name: SubgraphName) -> Result<Vec<EntityChange>, StoreError> {
         use subgraph as s;
-        use subgraph_version as v;
 
         let conn = self.conn.as_ref();
 
@@ -972,14 +1083,41 @@ impl<'a> Connection<'a> {
             .first(conn)
             .optional()?;
         if let Some(subgraph) = subgraph {
-            delete(v::table.filter(v::subgraph.eq(&subgraph))).execute(conn)?;
-            delete(s::table.filter(s::id.eq(subgraph))).execute(conn)?;
-            self.remove_unused_assignments()
+            self.remove_subgraph_by_id(&subgraph)
         } else {
             Ok(vec![])
         }
     }
 
+    /// Like `remove_subgraph`, but identify the subgraph by the `id` of its
+    /// row in `subgraphs.subgraph` rather than by its name. If no subgraph
+    /// with that id exists, there is nothing to do
+    pub fn remove_subgraph_by_id(&self, id: &str) -> Result<Vec<EntityChange>, StoreError> {
+        use subgraph as s;
+        use subgraph_version as v;
+
+        let conn = self.conn.as_ref();
+
+        delete(v::table.filter(v::subgraph.eq(id))).execute(conn)?;
+        match delete(s::table.filter(s::id.eq(id))).execute(conn)? {
+            0 => Ok(vec![]),
+            _ => self.remove_unused_assignments(),
+        }
+    }
+
+    /// Like `remove_subgraph`, but return `None` if there is no subgraph
+    /// with the given name instead of an empty list of changes
+    pub fn remove_subgraph_if_exists(
+        &self,
+        name: SubgraphName,
+    ) -> Result<Option<Vec<EntityChange>>, StoreError> {
+        if queries::subgraph_exists(self.conn.as_ref(), &name)? {
+            self.remove_subgraph(name).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn reassign_subgraph(
         &self,
         site: &Site,
@@ -1674,6 +1812,24 @@ impl Mirror {
         self.read(|conn| queries::current_deployment_for_subgraph(conn, name))
     }
 
+    pub fn pending_deployment_for_subgraph(
+        &self,
+        name: &SubgraphName,
+    ) -> Result<Option<DeploymentHash>, StoreError> {
+        self.read(|conn| queries::pending_deployment_for_subgraph(conn, name))
+    }
+
+    pub fn subgraph_versions(
+        &self,
+        name: &SubgraphName,
+    ) -> Result<Vec<(String, DeploymentHash, bool, bool)>, StoreError> {
+        self.read(|conn| queries::subgraph_versions(conn, name))
+    }
+
+    pub fn current_deployments(&self) -> Result<Vec<(SubgraphName, DeploymentHash)>, StoreError> {
+        self.read(|conn| queries::current_deployments(conn))
+    }
+
     pub fn deployments_for_subgraph(&self, name: &str) -> Result<Vec<Site>, StoreError> {
         self.read(|conn| queries::deployments_for_subgraph(conn, name))
     }
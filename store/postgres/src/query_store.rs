@@ -38,11 +38,27 @@ impl QueryStoreTrait for QueryStore {
         query: EntityQuery,
     ) -> Result<Vec<BTreeMap<String, r::Value>>, QueryExecutionError> {
         assert_eq!(&self.site.deployment, &query.subgraph_id);
-        let conn = self
-            .store
-            .get_replica_conn(self.replica_id)
-            .map_err(|e| QueryExecutionError::StoreError(e.into()))?;
-        self.store.execute_query(&conn, self.site.clone(), query)
+        let (conn, canceler) = match &query.cancel {
+            Some(handle) => {
+                let (conn, pid) = self
+                    .store
+                    .get_replica_conn_with_backend_pid(self.replica_id)
+                    .map_err(|e| QueryExecutionError::StoreError(e.into()))?;
+                let canceler = self.store.cancel_on(self.replica_id, pid, handle);
+                (conn, Some(canceler))
+            }
+            None => {
+                let conn = self
+                    .store
+                    .get_replica_conn(self.replica_id)
+                    .map_err(|e| QueryExecutionError::StoreError(e.into()))?;
+                (conn, None)
+            }
+        };
+        let res = self.store.execute_query(&conn, self.site.clone(), query);
+        // The canceler must be gone before `conn` goes back into the pool
+        drop(canceler);
+        res
     }
 
     /// Return true if the deployment with the given id is fully synced,
@@ -14,6 +14,7 @@ mod tests;
 
 use diesel::{connection::SimpleConnection, Connection};
 use diesel::{debug_query, OptionalExtension, PgConnection, RunQueryDsl};
+use diesel::{pg::Pg, query_builder::QueryFragment};
 use graph::cheap_clone::CheapClone;
 use graph::constraint_violation;
 use graph::data::graphql::TypeExt as _;
@@ -33,8 +34,10 @@ use crate::relational_queries::{FindChangesQuery, FindPossibleDeletionsQuery};
 use crate::{
     primary::{Namespace, Site},
     relational_queries::{
-        ClampRangeQuery, ConflictingEntityQuery, EntityData, EntityDeletion, FilterCollection,
-        FilterQuery, FindManyQuery, FindQuery, InsertQuery, RevertClampQuery, RevertRemoveQuery,
+        ClampRangeQuery, ConflictingEntityQuery, Count, EntityData, EntityDeletion, ExistsData,
+        ExistsQuery, FilterCollection, FilterCountQuery, FilterQuery, FindForUpdateQuery,
+        FindManyQuery, FindQuery, InsertQuery, RevertClampQuery, RevertCountQuery,
+        RevertRemoveQuery,
     },
 };
 use graph::components::store::EntityType;
@@ -43,10 +46,12 @@ use graph::data::schema::{FulltextConfig, FulltextDefinition, Schema, SCHEMA_TYP
 use graph::data::store::BYTES_SCALAR;
 use graph::data::subgraph::schema::{POI_OBJECT, POI_TABLE};
 use graph::prelude::{
-    anyhow, info, BlockNumber, DeploymentHash, Entity, EntityChange, EntityCollection,
-    EntityFilter, EntityKey, EntityOperation, EntityOrder, EntityRange, Logger,
-    QueryExecutionError, StoreError, StoreEvent, ValueType, BLOCK_NUMBER_MAX,
+    anyhow, info, BlockNumber, Counter, DeploymentHash, Entity, EntityChange, EntityCollection,
+    EntityFilter, EntityKey, EntityOperation, EntityOrder, EntityRange, Gauge, Logger,
+    MetricsRegistry, QueryExecutionError, StoreError, StoreEvent, Value, ValueType,
+    BLOCK_NUMBER_MAX,
 };
+use lru_time_cache::LruCache;
 
 use crate::block_range::{BLOCK_COLUMN, BLOCK_RANGE_COLUMN};
 pub use crate::catalog::Catalog;
@@ -197,6 +202,95 @@ type IdTypeMap = HashMap<EntityType, IdType>;
 
 type EnumMap = BTreeMap<String, Arc<BTreeSet<String>>>;
 
+/// The result of looking up an entity with `Layout::find_with_tombstone`
+#[derive(Clone, Debug, PartialEq)]
+pub enum FindResult {
+    /// The entity exists at the block
+    Present(Entity),
+    /// The entity existed before the block, but was deleted at or before it
+    Deleted,
+    /// No version of the entity was created at or before the block
+    NeverExisted,
+}
+
+/// A difference between the schema of an existing deployment and a new
+/// schema, as determined by `Layout::schema_diff`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaChange {
+    /// The entity type only exists in the new schema
+    TypeAdded(EntityType),
+    /// The entity type only exists in the existing schema
+    TypeRemoved(EntityType),
+    /// The attribute only exists in the new schema
+    AttributeAdded {
+        entity: EntityType,
+        attribute: String,
+        nullable: bool,
+    },
+    /// The attribute only exists in the existing schema
+    AttributeRemoved {
+        entity: EntityType,
+        attribute: String,
+    },
+    /// The attribute exists in both schemas, but existing values are not
+    /// valid for its type in the new schema
+    AttributeChanged {
+        entity: EntityType,
+        attribute: String,
+        reason: String,
+    },
+}
+
+impl SchemaChange {
+    /// The attribute this change is about, if it is about an attribute
+    fn attribute(&self) -> Option<&str> {
+        match self {
+            SchemaChange::TypeAdded(_) | SchemaChange::TypeRemoved(_) => None,
+            SchemaChange::AttributeAdded { attribute, .. }
+            | SchemaChange::AttributeRemoved { attribute, .. }
+            | SchemaChange::AttributeChanged { attribute, .. } => Some(attribute),
+        }
+    }
+
+    /// Whether data stored with the existing schema is inconsistent with
+    /// the new schema after this change. Adding types and nullable
+    /// attributes is compatible, anything else is breaking
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            SchemaChange::TypeAdded(_) => false,
+            SchemaChange::AttributeAdded { nullable, .. } => !nullable,
+            SchemaChange::TypeRemoved(_)
+            | SchemaChange::AttributeRemoved { .. }
+            | SchemaChange::AttributeChanged { .. } => true,
+        }
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchemaChange::TypeAdded(entity) => write!(f, "added type {}", entity),
+            SchemaChange::TypeRemoved(entity) => write!(f, "removed type {}", entity),
+            SchemaChange::AttributeAdded {
+                entity,
+                attribute,
+                nullable,
+            } => {
+                let kind = if *nullable {
+                    "nullable"
+                } else {
+                    "non-nullable"
+                };
+                write!(f, "added {} attribute {}.{}", kind, entity, attribute)
+            }
+            SchemaChange::AttributeRemoved { entity, attribute } => {
+                write!(f, "removed attribute {}.{}", entity, attribute)
+            }
+            SchemaChange::AttributeChanged { reason, .. } => write!(f, "{}", reason),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Layout {
     /// Details of where the subgraph is stored
@@ -380,6 +474,21 @@ impl Layout {
         self.tables.contains_key(&*POI_OBJECT)
     }
 
+    /// Return the proof of indexing entry for the causality region
+    /// `region` as of `block`, or `None` if there is none or this
+    /// deployment does not keep proofs of indexing
+    pub fn poi_for_region(
+        &self,
+        conn: &PgConnection,
+        region: &str,
+        block: BlockNumber,
+    ) -> Result<Option<Entity>, StoreError> {
+        if !self.supports_proof_of_indexing() {
+            return Ok(None);
+        }
+        self.find(conn, &*POI_OBJECT, region, block)
+    }
+
     pub fn create_relational_schema(
         conn: &PgConnection,
         site: Arc<Site>,
@@ -411,6 +520,28 @@ impl Layout {
             .collect()
     }
 
+    /// List the changes needed to go from the schema of `self`, usually
+    /// the layout of an existing deployment, to the schema of `new`.
+    /// Changes are sorted by entity type and attribute so that the result
+    /// is stable. Use `SchemaChange::is_breaking` to find changes that
+    /// would make existing data inconsistent with the new schema
+    pub fn schema_diff(&self, new: &Layout) -> Vec<SchemaChange> {
+        let mut changes = Vec::new();
+
+        let objects: BTreeSet<&EntityType> = self.tables.keys().chain(new.tables.keys()).collect();
+        for object in objects {
+            match (self.tables.get(object), new.tables.get(object)) {
+                (Some(old_table), Some(new_table)) => {
+                    changes.extend(new_table.schema_diff(old_table))
+                }
+                (Some(_), None) => changes.push(SchemaChange::TypeRemoved(object.clone())),
+                (None, Some(_)) => changes.push(SchemaChange::TypeAdded(object.clone())),
+                (None, None) => unreachable!("the type {} comes from one of the layouts", object),
+            }
+        }
+        changes
+    }
+
     /// Import the database schema for this layout from its own database
     /// shard (in `self.site.shard`) into the database represented by `conn`
     /// if the schema for this layout does not exist yet
@@ -476,6 +607,62 @@ impl Layout {
             .transpose()
     }
 
+    /// Check whether the entity of type `entity` with the given `id` exists
+    /// as of `block`. This is cheaper than `find` since it does not load or
+    /// deserialize any of the entity's attributes
+    pub fn exists(
+        &self,
+        conn: &PgConnection,
+        entity: &EntityType,
+        id: &str,
+        block: BlockNumber,
+    ) -> Result<bool, StoreError> {
+        let table = self.table_for_entity(entity)?;
+        Ok(ExistsQuery::new(table.as_ref(), id, block)
+            .get_result::<ExistsData>(conn)?
+            .exists)
+    }
+
+    /// Find the entity of type `entity` with the given `id` as of `block`
+    /// and, if there is no such entity, distinguish between an entity that
+    /// was deleted at or before `block` and one that never existed
+    pub fn find_with_tombstone(
+        &self,
+        conn: &PgConnection,
+        entity: &EntityType,
+        id: &str,
+        block: BlockNumber,
+    ) -> Result<FindResult, StoreError> {
+        if let Some(entity) = self.find(conn, entity, id, block)? {
+            return Ok(FindResult::Present(entity));
+        }
+        let table = self.table_for_entity(entity)?;
+        let existed = EverExistedQuery::new(table.as_ref(), id, block)
+            .get_result::<ExistsData>(conn)?
+            .exists;
+        if existed {
+            Ok(FindResult::Deleted)
+        } else {
+            Ok(FindResult::NeverExisted)
+        }
+    }
+
+    /// Run `analyze` on each of the tables in this layout to refresh the
+    /// statistics Postgres' query planner uses, for example, after a bulk
+    /// load. Each table is analyzed in its own statement
+    pub(crate) fn analyze_tables(&self, conn: &PgConnection) -> Result<(), StoreError> {
+        let mut tables = self.tables.values().collect::<Vec<_>>();
+        tables.sort_by_key(|table| table.position);
+        for table in tables {
+            let sql = format!("analyze {}", table.qualified_name);
+            conn.batch_execute(&sql)?;
+        }
+        Ok(())
+    }
+
+    /// Find the entities with the given ids as of `block`. The entities for
+    /// each entity type are sorted by their id so that the result does not
+    /// depend on the order in which the database returns rows
     pub fn find_many(
         &self,
         conn: &PgConnection,
@@ -503,13 +690,68 @@ impl Layout {
                 .or_default()
                 .push(data.deserialize_with_layout(self, None)?);
         }
+        for entities in entities_for_type.values_mut() {
+            entities.sort_by_cached_key(|entity| entity.id().ok());
+        }
         Ok(entities_for_type)
     }
 
+    /// Find the current versions of the entities with the given ids,
+    /// whatever their type. Since an id can be used by entities of
+    /// several types, the result can contain more than one entity per id.
+    /// Ids that can not be the id of an entity in a table, like ids that
+    /// are not hex strings for tables whose ids are `Bytes`, are not looked
+    /// up in that table
+    pub fn find_any(
+        &self,
+        conn: &PgConnection,
+        ids: &[String],
+    ) -> Result<Vec<(EntityType, Entity)>, StoreError> {
+        let mut ids_for_type: BTreeMap<&EntityType, Vec<&str>> = BTreeMap::new();
+        for table in self.tables.values() {
+            if table.object.is_poi() {
+                continue;
+            }
+            let table_ids: Vec<_> = match table.primary_key().column_type.id_type() {
+                IdType::String => ids.iter().map(|id| id.as_str()).collect(),
+                IdType::Bytes => ids
+                    .iter()
+                    .filter(|id| graph::data::store::scalar::Bytes::from_str(id).is_ok())
+                    .map(|id| id.as_str())
+                    .collect(),
+            };
+            if !table_ids.is_empty() {
+                ids_for_type.insert(&table.object, table_ids);
+            }
+        }
+
+        Ok(self
+            .find_many(conn, &ids_for_type, BLOCK_NUMBER_MAX)?
+            .into_iter()
+            .flat_map(|(entity_type, entities)| {
+                entities
+                    .into_iter()
+                    .map(move |entity| (entity_type.clone(), entity))
+            })
+            .collect())
+    }
+
     pub fn find_changes(
         &self,
         conn: &PgConnection,
         block: BlockNumber,
+    ) -> Result<Vec<EntityOperation>, StoreError> {
+        self.entity_changes_since(conn, block - 1, block)
+    }
+
+    /// Find the entities that changed in the blocks `(from, to]`. Entities
+    /// that exist at `to` are reported with their data as of `to`, and
+    /// entities that were deleted in that interval as removals
+    pub fn entity_changes_since(
+        &self,
+        conn: &PgConnection,
+        from: BlockNumber,
+        to: BlockNumber,
     ) -> Result<Vec<EntityOperation>, StoreError> {
         let mut tables = Vec::new();
         for table in self.tables.values() {
@@ -519,10 +761,10 @@ impl Layout {
         }
 
         let inserts_or_updates =
-            FindChangesQuery::new(&self.catalog.site.namespace, &tables[..], block)
+            FindChangesQuery::new(&self.catalog.site.namespace, &tables[..], from, to)
                 .load::<EntityData>(conn)?;
         let deletions =
-            FindPossibleDeletionsQuery::new(&self.catalog.site.namespace, &tables[..], block)
+            FindPossibleDeletionsQuery::new(&self.catalog.site.namespace, &tables[..], from, to)
                 .load::<EntityDeletion>(conn)?;
 
         let mut processed_entities = HashSet::new();
@@ -553,8 +795,10 @@ impl Layout {
             let entity_id = del.id().to_string();
 
             // See the doc comment of `FindPossibleDeletionsQuery` for details
-            // about why this check is necessary.
-            if !processed_entities.contains(&(entity_type.clone(), entity_id.clone())) {
+            // about why this check is necessary. It also weeds out entities
+            // that were updated several times before they were deleted and
+            // therefore show up more than once
+            if processed_entities.insert((entity_type.clone(), entity_id.clone())) {
                 changes.push(EntityOperation::Remove {
                     key: EntityKey {
                         subgraph_id: self.site.deployment.cheap_clone(),
@@ -592,6 +836,52 @@ impl Layout {
         Ok(count)
     }
 
+    /// Load `rows` as new entities of type `entity_type` at `block`, for
+    /// example, when backfilling a deployment. Every row is checked
+    /// against the columns of the table before anything is written for
+    /// it, and rows are written in batches that are as large as Postgres
+    /// allows, so that `rows` is never held in memory completely.
+    ///
+    /// This uses multi-row inserts rather than `COPY ... FROM STDIN`
+    /// since `diesel` does not support `COPY`, and a separate connection
+    /// would not be part of the transaction that `conn` is in. Returns the
+    /// number of rows that were loaded
+    pub fn copy_entities(
+        &self,
+        conn: &PgConnection,
+        entity_type: &EntityType,
+        rows: impl Iterator<Item = Entity>,
+        block: BlockNumber,
+    ) -> Result<u64, StoreError> {
+        let table = self.table_for_entity(entity_type)?;
+        // We add 1 to account for the `block_range` bind parameter
+        let chunk_size = POSTGRES_MAX_PARAMETERS / (table.columns.len() + 1);
+
+        let mut count = 0;
+        let mut rows = rows.peekable();
+        while rows.peek().is_some() {
+            let chunk: Vec<Entity> = rows.by_ref().take(chunk_size).collect();
+            let keys = chunk
+                .iter()
+                .map(|entity| {
+                    table.check_entity(entity)?;
+                    Ok(EntityKey::data(
+                        self.site.deployment.clone(),
+                        entity_type.to_string(),
+                        entity.id()?,
+                    ))
+                })
+                .collect::<Result<Vec<_>, StoreError>>()?;
+            let mut entities: Vec<_> = keys
+                .iter()
+                .zip(chunk.iter())
+                .map(|(key, entity)| (key, Cow::from(entity)))
+                .collect();
+            count += InsertQuery::new(table, &mut entities, block)?.execute(conn)? as u64;
+        }
+        Ok(count)
+    }
+
     pub fn conflicting_entity(
         &self,
         conn: &PgConnection,
@@ -665,22 +955,7 @@ impl Layout {
                 }
                 query.load::<EntityData>(conn)
             })
-            .map_err(|e| match e {
-                diesel::result::Error::DatabaseError(
-                    diesel::result::DatabaseErrorKind::__Unknown,
-                    ref info,
-                ) if info.message().starts_with("syntax error in tsquery") => {
-                    QueryExecutionError::FulltextQueryInvalidSyntax(info.message().to_string())
-                }
-                diesel::result::Error::QueryBuilderError(e) => {
-                    QueryExecutionError::ResolveEntitiesError(e.to_string())
-                }
-                _ => QueryExecutionError::ResolveEntitiesError(format!(
-                    "{}, query = {}",
-                    e,
-                    debug_query(&query_clone).to_string()
-                )),
-            })?;
+            .map_err(|e| query_execution_error(e, &query_clone))?;
         log_query_timing(logger, &query_clone, start.elapsed(), values.len());
 
         let parent_type = filter_collection.parent_type()?.map(ColumnType::from);
@@ -694,6 +969,80 @@ impl Layout {
             .collect()
     }
 
+    /// Find the entity in `collection` that matches `filter` at `block`
+    /// when at most one entity is expected to match. Return `None` if no
+    /// entity matches, and a `QueryExecutionError::AmbiguousResult` if
+    /// more than one does
+    pub fn find_one(
+        &self,
+        logger: &Logger,
+        conn: &PgConnection,
+        collection: EntityCollection,
+        filter: EntityFilter,
+        block: BlockNumber,
+    ) -> Result<Option<Entity>, QueryExecutionError> {
+        let types: Vec<_> = match &collection {
+            EntityCollection::All(types) => types.iter().map(|(typ, _)| typ.to_string()).collect(),
+            EntityCollection::Window(windows) => windows
+                .iter()
+                .map(|window| window.child_type.to_string())
+                .collect(),
+        };
+
+        // We only need to look at two entities to know whether the result
+        // is ambiguous
+        let mut entities = self.query::<Entity>(
+            logger,
+            conn,
+            collection,
+            Some(filter),
+            EntityOrder::Default,
+            EntityRange::first(2),
+            block,
+            None,
+        )?;
+        match entities.len() {
+            0 | 1 => Ok(entities.pop()),
+            _ => Err(QueryExecutionError::AmbiguousResult(types.join(", "))),
+        }
+    }
+
+    /// Count the entities in `collection` that match `filter` at `block`.
+    /// This is the number of entities that `query` would return for the
+    /// same arguments if it was not limited by a range
+    pub fn count(
+        &self,
+        conn: &PgConnection,
+        collection: EntityCollection,
+        filter: Option<EntityFilter>,
+        block: BlockNumber,
+    ) -> Result<i64, QueryExecutionError> {
+        let filter_collection = FilterCollection::new(self, collection, filter.as_ref())?;
+        let range = EntityRange {
+            first: None,
+            skip: 0,
+        };
+        let query = FilterQuery::new(
+            &filter_collection,
+            filter.as_ref(),
+            EntityOrder::Unordered,
+            range,
+            block,
+            None,
+        )?;
+        let query = FilterCountQuery::new(query);
+        let query_clone = query.clone();
+
+        conn.transaction(|| {
+            if let Some(ref timeout_sql) = *STATEMENT_TIMEOUT {
+                conn.batch_execute(timeout_sql)?;
+            }
+            query.get_result::<Count>(conn)
+        })
+        .map(|count| count.count)
+        .map_err(|e| query_execution_error(e, &query_clone))
+    }
+
     pub fn update<'a>(
         &'a self,
         conn: &PgConnection,
@@ -739,6 +1088,68 @@ impl Layout {
         Ok(count)
     }
 
+    /// Update some of the attributes of the entity with `key` by merging
+    /// `changes` into the version of the entity that is current as of
+    /// `block` and writing the result as a new version. Attributes in
+    /// `changes` take precedence, and attributes set to `Value::Null` in
+    /// `changes` are removed from the entity. It is an error if the entity
+    /// does not exist as of `block`
+    pub fn patch(
+        &self,
+        conn: &PgConnection,
+        key: &EntityKey,
+        changes: Entity,
+        block: BlockNumber,
+        stopwatch: &StopwatchMetrics,
+    ) -> Result<(), StoreError> {
+        let mut entity = self
+            .find(conn, &key.entity_type, &key.entity_id, block)?
+            .ok_or_else(|| {
+                StoreError::QueryExecutionError(format!(
+                    "can not patch {}[{}] since it does not exist",
+                    key.entity_type, key.entity_id
+                ))
+            })?;
+        entity.merge_remove_null_fields(changes);
+
+        let mut entities = vec![(key, Cow::from(&entity))];
+        self.update(conn, &key.entity_type, &mut entities, block, stopwatch)?;
+        Ok(())
+    }
+
+    /// Write `entity` as the new version of the entity with `key`, but
+    /// only if the version that is current as of `block` is equal to
+    /// `expected`, which should be the entity as it was previously read
+    /// from the store. Return `true` if the update was applied and `false`
+    /// if the stored entity had changed or does not exist. The current
+    /// version is locked while it is compared and replaced so that
+    /// concurrent updates can not slip in between the two
+    pub(crate) fn update_if_unchanged(
+        &self,
+        conn: &PgConnection,
+        key: &EntityKey,
+        expected: &Entity,
+        entity: Entity,
+        block: BlockNumber,
+        stopwatch: &StopwatchMetrics,
+    ) -> Result<bool, StoreError> {
+        let table = self.table_for_entity(&key.entity_type)?;
+        conn.transaction(|| {
+            let current = FindForUpdateQuery::new(table.as_ref(), &key.entity_id, block)
+                .get_result::<EntityData>(conn)
+                .optional()?
+                .map(|entity_data| entity_data.deserialize_with_layout(self, None))
+                .transpose()?;
+            if current.as_ref() != Some(expected) {
+                return Ok(false);
+            }
+
+            let mut entities = vec![(key, Cow::Owned(entity))];
+            self.update(conn, &key.entity_type, &mut entities, block, stopwatch)?;
+            Ok(true)
+        })
+    }
+
     pub fn delete(
         &self,
         conn: &PgConnection,
@@ -822,6 +1233,52 @@ impl Layout {
         Ok((StoreEvent::new(changes), count))
     }
 
+    /// Remove all entity versions from all tables in this layout with
+    /// `truncate`, which is much faster than deleting them block by block.
+    /// The tables themselves and their indexes are kept
+    pub fn truncate(&self, conn: &PgConnection) -> Result<StoreEvent, StoreError> {
+        if self.tables.is_empty() {
+            return Ok(StoreEvent::new(vec![]));
+        }
+
+        let mut tables: Vec<_> = self.tables.values().collect();
+        tables.sort_by(|a, b| a.name.as_str().cmp(b.name.as_str()));
+        let names: Vec<_> = tables
+            .iter()
+            .map(|table| table.qualified_name.as_str())
+            .collect();
+        conn.batch_execute(&format!("truncate table {}", names.join(", ")))?;
+
+        let changes = tables
+            .into_iter()
+            .map(|table| EntityChange::Data {
+                subgraph_id: self.site.deployment.clone(),
+                entity_type: table.object.clone(),
+            })
+            .collect();
+        Ok(StoreEvent::new(changes))
+    }
+
+    /// Count, for each entity type, how many versions `revert_block` would
+    /// remove when reverting to `block`, without changing anything. Entity
+    /// types that would not be affected are left out
+    pub fn revert_block_preview(
+        &self,
+        conn: &PgConnection,
+        block: BlockNumber,
+    ) -> Result<BTreeMap<EntityType, i32>, StoreError> {
+        let mut counts = BTreeMap::new();
+        for table in self.tables.values() {
+            let count = RevertCountQuery::new(table, block)
+                .get_result::<Count>(conn)?
+                .count;
+            if count > 0 {
+                counts.insert(table.object.clone(), count as i32);
+            }
+        }
+        Ok(counts)
+    }
+
     /// Revert the metadata (dynamic data sources and related entities) for
     /// the given `subgraph`.
     ///
@@ -1120,6 +1577,44 @@ impl Column {
         self.name.as_str() == PRIMARY_KEY_COLUMN
     }
 
+    /// Check that `value` can be stored in this column
+    fn check_value(&self, object: &EntityType, value: &Value) -> Result<(), StoreError> {
+        fn is_scalar_of_type(column_type: &ColumnType, value: &Value) -> bool {
+            match (column_type, value) {
+                (ColumnType::Boolean, Value::Bool(_))
+                | (ColumnType::BigDecimal, Value::BigDecimal(_))
+                | (ColumnType::BigInt, Value::BigInt(_))
+                | (ColumnType::Bytes, Value::Bytes(_))
+                | (ColumnType::Int, Value::Int(_))
+                | (ColumnType::String, Value::String(_)) => true,
+                (ColumnType::Bytes, Value::String(s)) => {
+                    graph::data::store::scalar::Bytes::from_str(s).is_ok()
+                }
+                (ColumnType::Enum(enum_type), Value::String(s)) => enum_type.values.contains(s),
+                _ => false,
+            }
+        }
+
+        let valid = match value {
+            Value::Null => self.is_nullable(),
+            Value::List(values) => {
+                self.is_list()
+                    && values
+                        .iter()
+                        .all(|value| is_scalar_of_type(&self.column_type, value))
+            }
+            value => !self.is_list() && is_scalar_of_type(&self.column_type, value),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(StoreError::QueryExecutionError(format!(
+                "can not store the value {} in the attribute {}.{} of type {}",
+                value, object, self.field, self.field_type
+            )))
+        }
+    }
+
     pub fn is_assignable_from(&self, source: &Self, object: &EntityType) -> Option<String> {
         if !self.is_nullable() && source.is_nullable() {
             Some(format!(
@@ -1245,6 +1740,58 @@ impl Table {
             .ok_or_else(|| StoreError::UnknownField(field.to_string()))
     }
 
+    /// The changes to the attributes of this table compared to the same
+    /// table in an existing layout. See `Layout::schema_diff`
+    fn schema_diff(&self, old: &Self) -> Vec<SchemaChange> {
+        let removed = old
+            .columns
+            .iter()
+            .filter(|ocol| self.column(&ocol.name).is_none())
+            .map(|ocol| SchemaChange::AttributeRemoved {
+                entity: self.object.clone(),
+                attribute: ocol.field.clone(),
+            });
+        let added_or_changed = self
+            .columns
+            .iter()
+            .filter_map(|col| match old.column(&col.name) {
+                Some(ocol) => col.is_assignable_from(ocol, &self.object).map(|reason| {
+                    SchemaChange::AttributeChanged {
+                        entity: self.object.clone(),
+                        attribute: col.field.clone(),
+                        reason,
+                    }
+                }),
+                None => Some(SchemaChange::AttributeAdded {
+                    entity: self.object.clone(),
+                    attribute: col.field.clone(),
+                    nullable: col.is_nullable(),
+                }),
+            });
+
+        let mut changes: Vec<_> = removed.chain(added_or_changed).collect();
+        changes.sort_by(|a, b| a.attribute().cmp(b.attribute()));
+        changes
+    }
+
+    /// Check that every attribute of `entity` corresponds to a column of
+    /// this table and has a value that can be stored in it. The
+    /// `__typename` attribute is ignored
+    fn check_entity(&self, entity: &Entity) -> Result<(), StoreError> {
+        for (attr, value) in entity.iter() {
+            if attr == "__typename" {
+                continue;
+            }
+            let column = self
+                .columns
+                .iter()
+                .find(|column| &column.field == attr && !column.is_fulltext())
+                .ok_or_else(|| StoreError::UnknownField(format!("{}.{}", self.object, attr)))?;
+            column.check_value(&self.object, value)?;
+        }
+        Ok(())
+    }
+
     fn can_copy_from(&self, source: &Self) -> Vec<String> {
         self.columns
             .iter()
@@ -1273,6 +1820,30 @@ impl Table {
     }
 }
 
+/// Turn an error from running an entity query into the corresponding
+/// `QueryExecutionError`
+fn query_execution_error<Q: QueryFragment<Pg>>(
+    e: diesel::result::Error,
+    query: &Q,
+) -> QueryExecutionError {
+    match e {
+        diesel::result::Error::DatabaseError(
+            diesel::result::DatabaseErrorKind::__Unknown,
+            ref info,
+        ) if info.message().starts_with("syntax error in tsquery") => {
+            QueryExecutionError::FulltextQueryInvalidSyntax(info.message().to_string())
+        }
+        diesel::result::Error::QueryBuilderError(e) => {
+            QueryExecutionError::ResolveEntitiesError(e.to_string())
+        }
+        _ => QueryExecutionError::ResolveEntitiesError(format!(
+            "{}, query = {}",
+            e,
+            debug_query::<Pg, _>(query).to_string()
+        )),
+    }
+}
+
 /// Return the enclosed named type for a field type, i.e., the type after
 /// stripping List and NonNull.
 fn named_type(field_type: &q::Type) -> &str {
@@ -1298,21 +1869,50 @@ struct CacheEntry {
 /// Cache layouts for some time and refresh them when they expire.
 /// Refreshing happens one at a time, and the cache makes sure we minimize
 /// blocking while a refresh happens, favoring using an expired layout over
-/// a refreshed one.
+/// a refreshed one. The cache holds at most a fixed number of layouts and
+/// evicts the least recently used one when it is full; since layouts can
+/// always be loaded again, that is safe.
 pub struct LayoutCache {
-    entries: Mutex<HashMap<DeploymentHash, CacheEntry>>,
+    entries: Mutex<LruCache<DeploymentHash, CacheEntry>>,
     ttl: Duration,
     /// Use this so that we only refresh one layout at any given time to
     /// avoid refreshing the same layout multiple times
     refresh: Mutex<()>,
+    /// The number of layouts in the cache
+    size: Gauge,
+    /// The number of layouts that were evicted because the cache was full
+    evictions: Counter,
 }
 
 impl LayoutCache {
-    pub fn new(ttl: Duration) -> Self {
+    pub fn new(
+        ttl: Duration,
+        capacity: usize,
+        registry: &dyn MetricsRegistry,
+        shard: &str,
+    ) -> Self {
+        let size = registry
+            .global_gauge_vec(
+                "store_layout_cache_size",
+                "The number of deployment layouts in the layout cache",
+                &["shard"],
+            )
+            .expect("failed to create `store_layout_cache_size` gauge")
+            .with_label_values(&[shard]);
+        let evictions = registry
+            .global_counter_vec(
+                "store_layout_cache_evictions_total",
+                "The number of deployment layouts evicted from the full layout cache",
+                &["shard"],
+            )
+            .expect("failed to create `store_layout_cache_evictions_total` counter")
+            .with_label_values(&[shard]);
         Self {
-            entries: Mutex::new(HashMap::new()),
+            entries: Mutex::new(LruCache::with_capacity(capacity)),
             ttl,
             refresh: Mutex::new(()),
+            size,
+            evictions,
         }
     }
 
@@ -1330,7 +1930,10 @@ impl LayoutCache {
                 expires: Instant::now() + self.ttl,
                 value: layout,
             };
-            self.entries.lock().unwrap().insert(deployment, entry);
+            let mut entries = self.entries.lock().unwrap();
+            let (_, evicted) = entries.notify_insert(deployment, entry);
+            self.evictions.inc_by(evicted.len() as f64);
+            self.size.set(entries.len() as f64);
         }
     }
 
@@ -1355,7 +1958,7 @@ impl LayoutCache {
     ) -> Result<Arc<Layout>, StoreError> {
         let now = Instant::now();
         let entry = {
-            let lock = self.entries.lock().unwrap();
+            let mut lock = self.entries.lock().unwrap();
             lock.get(&site.deployment).cloned()
         };
         match entry {
@@ -1403,6 +2006,27 @@ impl LayoutCache {
     // Only needed for tests
     #[cfg(debug_assertions)]
     pub(crate) fn clear(&self) {
-        self.entries.lock().unwrap().clear()
+        self.entries.lock().unwrap().clear();
+        self.size.set(0.0);
+    }
+}
+
+pub(crate) mod test_support {
+    use diesel::PgConnection;
+    use graph::prelude::{BlockNumber, Entity, EntityKey, StopwatchMetrics, StoreError};
+
+    use super::Layout;
+
+    /// Expose `Layout::update_if_unchanged` to tests
+    pub fn update_if_unchanged(
+        layout: &Layout,
+        conn: &PgConnection,
+        key: &EntityKey,
+        expected: &Entity,
+        entity: Entity,
+        block: BlockNumber,
+        stopwatch: &StopwatchMetrics,
+    ) -> Result<bool, StoreError> {
+        layout.update_if_unchanged(conn, key, expected, entity, block, stopwatch)
     }
 }
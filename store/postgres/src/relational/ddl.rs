@@ -1,6 +1,7 @@
+use std::collections::BTreeSet;
 use std::fmt::{self, Write};
 
-use graph::prelude::BLOCK_NUMBER_MAX;
+use graph::prelude::{EntityFilter, BLOCK_NUMBER_MAX};
 
 use crate::relational::{
     ColumnType, BLOCK_COLUMN, BLOCK_RANGE_COLUMN, BYTE_ARRAY_PREFIX_SIZE, STRING_PREFIX_SIZE,
@@ -33,6 +34,85 @@ impl Layout {
         Ok(out)
     }
 
+    /// Suggest `create index` statements that would cover the attributes
+    /// constrained by `filters`. Filters do not know which entity type
+    /// they apply to, and we therefore suggest an index for every table
+    /// that has a column for a filtered attribute. The indexes use the
+    /// same method and expression as the attribute indexes we create for
+    /// new deployments, in particular, large String and Bytes columns are
+    /// only indexed on a prefix. The statements are meant to be reviewed
+    /// by an operator and are never run automatically
+    pub(crate) fn suggested_indexes(&self, filters: &[EntityFilter]) -> Vec<String> {
+        fn add_attributes<'a>(filter: &'a EntityFilter, attrs: &mut BTreeSet<&'a str>) {
+            use EntityFilter::*;
+
+            match filter {
+                And(filters) | Or(filters) => {
+                    for filter in filters {
+                        add_attributes(filter, attrs);
+                    }
+                }
+                Equal(attr, _)
+                | Not(attr, _)
+                | GreaterThan(attr, _)
+                | LessThan(attr, _)
+                | GreaterOrEqual(attr, _)
+                | LessOrEqual(attr, _)
+                | In(attr, _)
+                | NotIn(attr, _)
+                | Contains(attr, _)
+                | ContainsNoCase(attr, _)
+                | NotContains(attr, _)
+                | NotContainsNoCase(attr, _)
+                | StartsWith(attr, _)
+                | StartsWithNoCase(attr, _)
+                | NotStartsWith(attr, _)
+                | NotStartsWithNoCase(attr, _)
+                | EndsWith(attr, _)
+                | EndsWithNoCase(attr, _)
+                | NotEndsWith(attr, _)
+                | NotEndsWithNoCase(attr, _)
+                | IsNull(attr)
+                | IsNotNull(attr) => {
+                    attrs.insert(attr.as_str());
+                }
+                ChangeBlockGte(_) => { /* covered by the block range indexes */ }
+            }
+        }
+
+        let mut attrs = BTreeSet::new();
+        for filter in filters {
+            add_attributes(filter, &mut attrs);
+        }
+
+        let mut tables = self.tables.values().collect::<Vec<_>>();
+        tables.sort_by_key(|table| table.position);
+
+        let mut ddl = Vec::new();
+        for table in tables {
+            for attr in &attrs {
+                let column = match table.column_for_field(attr) {
+                    Ok(column) => column,
+                    Err(_) => continue,
+                };
+                // The primary key is always indexed, and arrays of enums
+                // can not be indexed
+                if column.is_primary_key() || (column.is_list() && column.is_enum()) {
+                    continue;
+                }
+                let (method, index_expr) = column.attribute_index(table.immutable);
+                ddl.push(format!(
+                    "create index concurrently if not exists manual_{table_name}_{column_name}\n    \
+                     on {schema_name}.\"{table_name}\" using {method}({index_expr})",
+                    table_name = table.name,
+                    column_name = column.name,
+                    schema_name = self.catalog.site.namespace,
+                ));
+            }
+        }
+        ddl
+    }
+
     pub(crate) fn write_enum_ddl(&self, out: &mut dyn Write) -> Result<(), fmt::Error> {
         for (name, values) in &self.enums {
             let mut sep = "";
@@ -188,48 +268,7 @@ impl Table {
                     continue;
                 }
 
-                let (method, index_expr) = if column.is_reference() && !column.is_list() {
-                    // For foreign keys, index the key together with the block range
-                    // since we almost always also have a block_range clause in
-                    // queries that look for specific foreign keys
-                    if table.immutable {
-                        let index_expr = format!("{}, {}", column.name.quoted(), BLOCK_COLUMN);
-                        ("btree", index_expr)
-                    } else {
-                        let index_expr =
-                            format!("{}, {}", column.name.quoted(), BLOCK_RANGE_COLUMN);
-                        ("gist", index_expr)
-                    }
-                } else {
-                    // Attributes that are plain strings or bytes are
-                    // indexed with a BTree; but they can be too large for
-                    // Postgres' limit on values that can go into a BTree.
-                    // For those attributes, only index the first
-                    // STRING_PREFIX_SIZE or BYTE_ARRAY_PREFIX_SIZE characters
-                    let index_expr = if column.use_prefix_comparison {
-                        match column.column_type {
-                            ColumnType::String => {
-                                format!("left({}, {})", column.name.quoted(), STRING_PREFIX_SIZE)
-                            }
-                            ColumnType::Bytes => format!(
-                                "substring({}, 1, {})",
-                                column.name.quoted(),
-                                BYTE_ARRAY_PREFIX_SIZE
-                            ),
-                            _ => unreachable!("only String and Bytes can have arbitrary size"),
-                        }
-                    } else {
-                        column.name.quoted()
-                    };
-
-                    let method = if column.is_list() || column.is_fulltext() {
-                        "gin"
-                    } else {
-                        "btree"
-                    };
-
-                    (method, index_expr)
-                };
+                let (method, index_expr) = column.attribute_index(table.immutable);
                 write!(
                 out,
                 "create index attr_{table_index}_{column_index}_{table_name}_{column_name}\n    on {schema_name}.\"{table_name}\" using {method}({index_expr});\n",
@@ -252,6 +291,52 @@ impl Table {
 }
 
 impl Column {
+    /// Return the index method and the index expression for the attribute
+    /// index on this column in a table that is `immutable` or not
+    pub(crate) fn attribute_index(&self, immutable: bool) -> (&'static str, String) {
+        if self.is_reference() && !self.is_list() {
+            // For foreign keys, index the key together with the block range
+            // since we almost always also have a block_range clause in
+            // queries that look for specific foreign keys
+            if immutable {
+                let index_expr = format!("{}, {}", self.name.quoted(), BLOCK_COLUMN);
+                ("btree", index_expr)
+            } else {
+                let index_expr = format!("{}, {}", self.name.quoted(), BLOCK_RANGE_COLUMN);
+                ("gist", index_expr)
+            }
+        } else {
+            // Attributes that are plain strings or bytes are
+            // indexed with a BTree; but they can be too large for
+            // Postgres' limit on values that can go into a BTree.
+            // For those attributes, only index the first
+            // STRING_PREFIX_SIZE or BYTE_ARRAY_PREFIX_SIZE characters
+            let index_expr = if self.use_prefix_comparison {
+                match self.column_type {
+                    ColumnType::String => {
+                        format!("left({}, {})", self.name.quoted(), STRING_PREFIX_SIZE)
+                    }
+                    ColumnType::Bytes => format!(
+                        "substring({}, 1, {})",
+                        self.name.quoted(),
+                        BYTE_ARRAY_PREFIX_SIZE
+                    ),
+                    _ => unreachable!("only String and Bytes can have arbitrary size"),
+                }
+            } else {
+                self.name.quoted()
+            };
+
+            let method = if self.is_list() || self.is_fulltext() {
+                "gin"
+            } else {
+                "btree"
+            };
+
+            (method, index_expr)
+        }
+    }
+
     /// Generate the DDL for one column, i.e. the part of a `create table`
     /// statement for this column.
     ///
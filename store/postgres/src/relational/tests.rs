@@ -151,6 +151,84 @@ fn can_copy_from() {
     );
 }
 
+#[test]
+fn schema_diff() {
+    let old = test_layout(THING_GQL);
+    // An identical schema has no changes
+    assert!(old.schema_diff(&old).is_empty());
+
+    let new = test_layout(
+        "
+        type Thing @entity {
+            id: ID!
+            bigThing: Thing!
+            name: String
+        }
+
+        type Scalar {
+            id: ID,
+            bool: Boolean,
+            int: BigInt,
+            bigDecimal: BigDecimal,
+            string: String,
+            bytes: Bytes,
+            bigInt: BigInt,
+            size: Int!,
+        }
+
+        type Other @entity {
+            id: ID!
+        }",
+    );
+    let changes = old.schema_diff(&new);
+    let descriptions: Vec<_> = changes.iter().map(|change| change.to_string()).collect();
+    assert_eq!(
+        vec![
+            "added type Other",
+            "removed attribute Scalar.color",
+            "The attribute Scalar.int has type BigInt, but its type in the source is Int",
+            "added non-nullable attribute Scalar.size",
+            "added nullable attribute Thing.name",
+        ],
+        descriptions
+    );
+    let breaking: Vec<_> = changes.iter().map(|change| change.is_breaking()).collect();
+    assert_eq!(vec![false, true, true, true, false], breaking);
+
+    // Removing a type is breaking
+    let changes = new.schema_diff(&test_layout("type Thing @entity { id: ID! }"));
+    assert!(changes.contains(&SchemaChange::TypeRemoved(EntityType::new(
+        "Scalar".to_owned()
+    ))));
+    assert!(changes.iter().all(|change| change.is_breaking()));
+}
+
+#[test]
+fn suggested_indexes() {
+    use graph::prelude::Value;
+
+    let layout = test_layout(THING_GQL);
+    let filters = vec![
+        EntityFilter::Equal("string".to_owned(), Value::from("hello")),
+        EntityFilter::And(vec![
+            EntityFilter::Equal("bigThing".to_owned(), Value::from("thing1")),
+            EntityFilter::Equal("id".to_owned(), Value::from("thing2")),
+        ]),
+        EntityFilter::Equal("nonexistent".to_owned(), Value::from("nowhere")),
+    ];
+    let ddl = layout.suggested_indexes(&filters);
+    assert_eq!(
+        vec![
+            "create index concurrently if not exists manual_thing_big_thing
+    on sgd0815.\"thing\" using gist(\"big_thing\", block_range)",
+            "create index concurrently if not exists manual_scalar_string
+    on sgd0815.\"scalar\" using btree(left(\"string\", 256))",
+        ],
+        ddl
+    );
+    assert!(layout.suggested_indexes(&[]).is_empty());
+}
+
 const THING_GQL: &str = "
         type Thing @entity {
             id: ID!
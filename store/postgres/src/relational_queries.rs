@@ -14,8 +14,8 @@ use diesel::Connection;
 
 use graph::prelude::{
     anyhow, r, serde_json, Attribute, BlockNumber, ChildMultiplicity, Entity, EntityCollection,
-    EntityFilter, EntityKey, EntityLink, EntityOrder, EntityRange, EntityWindow, ParentLink,
-    QueryExecutionError, StoreError, Value, ENV_VARS,
+    EntityFilter, EntityKey, EntityLink, EntityOrder, EntityRange, EntityWindow, OrderDirection,
+    ParentLink, QueryExecutionError, StoreError, Value, ENV_VARS,
 };
 use graph::{
     components::store::{AttributeNames, EntityType},
@@ -896,7 +896,9 @@ impl<'a> QueryFilter<'a> {
             | EndsWith(attr, _)
             | EndsWithNoCase(attr, _)
             | NotEndsWith(attr, _)
-            | NotEndsWithNoCase(attr, _) => {
+            | NotEndsWithNoCase(attr, _)
+            | IsNull(attr)
+            | IsNotNull(attr) => {
                 table.column_for_field(attr)?;
             }
         }
@@ -1078,8 +1080,14 @@ impl<'a> QueryFilter<'a> {
     ) -> QueryResult<()> {
         let column = self.column(attribute);
 
+        // An empty list matches nothing with `in` and everything with
+        // `not in`; `attribute in ()` would be a syntax error
         if values.is_empty() {
-            out.push_sql("false");
+            if negated {
+                out.push_sql("true");
+            } else {
+                out.push_sql("false");
+            }
             return Ok(());
         }
 
@@ -1252,6 +1260,11 @@ impl<'a> QueryFragment<Pg> for QueryFilter<'a> {
             NotEndsWithNoCase(attr, value) => {
                 self.starts_or_ends_with(attr, value, " not ilike ", false, out)?
             }
+            // List attributes are treated like any other attribute: an
+            // empty list is a value, and only an unset list is null
+            IsNull(attr) => self.equals(attr, &Value::Null, c::Equal, out)?,
+            IsNotNull(attr) => self.equals(attr, &Value::Null, c::NotEqual, out)?,
+
             ChangeBlockGte(block_number) => self.filter_block_gte(block_number, out)?,
         }
         Ok(())
@@ -1298,14 +1311,145 @@ impl<'a> LoadQuery<PgConnection, EntityData> for FindQuery<'a> {
 
 impl<'a, Conn> RunQueryDsl<Conn> for FindQuery<'a> {}
 
+/// A `FindQuery` that also locks the version of the entity it finds until
+/// the end of the current transaction so that no other transaction can
+/// change or delete that version in the meantime
+#[derive(Debug, Clone, Constructor)]
+pub struct FindForUpdateQuery<'a> {
+    table: &'a Table,
+    id: &'a str,
+    block: BlockNumber,
+}
+
+impl<'a> QueryFragment<Pg> for FindForUpdateQuery<'a> {
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.unsafe_to_cache_prepared();
+
+        // Generate
+        //    select '..' as entity, to_jsonb(e.*) as data
+        //      from schema.table e where id = $1
+        //       for update
+        FindQuery::new(self.table, self.id, self.block).walk_ast(out.reborrow())?;
+        out.push_sql("\n for update");
+        Ok(())
+    }
+}
+
+impl<'a> QueryId for FindForUpdateQuery<'a> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<'a> LoadQuery<PgConnection, EntityData> for FindForUpdateQuery<'a> {
+    fn internal_load(self, conn: &PgConnection) -> QueryResult<Vec<EntityData>> {
+        conn.query_by_name(&self)
+    }
+}
+
+impl<'a, Conn> RunQueryDsl<Conn> for FindForUpdateQuery<'a> {}
+
+/// A query that checks whether an entity exists at a block without
+/// loading any of its attributes
+#[derive(Debug, Clone, Constructor)]
+pub struct ExistsQuery<'a> {
+    table: &'a Table,
+    id: &'a str,
+    block: BlockNumber,
+}
+
+impl<'a> QueryFragment<Pg> for ExistsQuery<'a> {
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.unsafe_to_cache_prepared();
+
+        // Generate
+        //    select exists(select 1 from schema.table e
+        //                   where id = $1 and block_range @> $2) as exists
+        out.push_sql("select exists(select 1\n");
+        out.push_sql("  from ");
+        out.push_sql(self.table.qualified_name.as_str());
+        out.push_sql(" e\n where ");
+        self.table.primary_key().eq(self.id, &mut out)?;
+        out.push_sql(" and ");
+        BlockRangeColumn::new(self.table, "e.", self.block).contains(&mut out)?;
+        out.push_sql(") as exists");
+        Ok(())
+    }
+}
+
+impl<'a> QueryId for ExistsQuery<'a> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+#[derive(QueryableByName)]
+pub struct ExistsData {
+    #[sql_type = "Bool"]
+    pub exists: bool,
+}
+
+impl<'a> LoadQuery<PgConnection, ExistsData> for ExistsQuery<'a> {
+    fn internal_load(self, conn: &PgConnection) -> QueryResult<Vec<ExistsData>> {
+        conn.query_by_name(&self)
+    }
+}
+
+impl<'a, Conn> RunQueryDsl<Conn> for ExistsQuery<'a> {}
+
+/// A query that checks whether any version of an entity was created at or
+/// before a block, whether or not that version is still current at the
+/// block
+#[derive(Debug, Clone, Constructor)]
+pub struct EverExistedQuery<'a> {
+    table: &'a Table,
+    id: &'a str,
+    block: BlockNumber,
+}
+
+impl<'a> QueryFragment<Pg> for EverExistedQuery<'a> {
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.unsafe_to_cache_prepared();
+
+        // Generate
+        //    select exists(select 1 from schema.table e
+        //                   where id = $1 and lower(block_range) <= $2) as exists
+        out.push_sql("select exists(select 1\n");
+        out.push_sql("  from ");
+        out.push_sql(self.table.qualified_name.as_str());
+        out.push_sql(" e\n where ");
+        self.table.primary_key().eq(self.id, &mut out)?;
+        out.push_sql(" and ");
+        BlockRangeColumn::new(self.table, "e.", self.block).starts_at_or_before(&mut out)?;
+        out.push_sql(") as exists");
+        Ok(())
+    }
+}
+
+impl<'a> QueryId for EverExistedQuery<'a> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<'a> LoadQuery<PgConnection, ExistsData> for EverExistedQuery<'a> {
+    fn internal_load(self, conn: &PgConnection) -> QueryResult<Vec<ExistsData>> {
+        conn.query_by_name(&self)
+    }
+}
+
+impl<'a, Conn> RunQueryDsl<Conn> for EverExistedQuery<'a> {}
+
 /// Builds a query over a given set of [`Table`]s in an attempt to find updated
-/// and/or newly inserted entities at a given block number; i.e. such that the
-/// block range's lower bound is equal to said block number.
+/// and/or newly inserted entities in the blocks `(from, to]`; i.e. such that
+/// the block range's lower bound falls into that interval. Only the version
+/// of each entity that is current at `to` is returned.
 #[derive(Debug, Clone, Constructor)]
 pub struct FindChangesQuery<'a> {
     pub(crate) _namespace: &'a Namespace,
     pub(crate) tables: &'a [&'a Table],
-    pub(crate) block: BlockNumber,
+    pub(crate) from: BlockNumber,
+    pub(crate) to: BlockNumber,
 }
 
 impl<'a> QueryFragment<Pg> for FindChangesQuery<'a> {
@@ -1322,7 +1466,7 @@ impl<'a> QueryFragment<Pg> for FindChangesQuery<'a> {
             out.push_sql("  from ");
             out.push_sql(table.qualified_name.as_str());
             out.push_sql(" e\n where ");
-            BlockRangeLowerBoundClause::new("e.", self.block).walk_ast(out.reborrow())?;
+            BlockRangeLowerBoundClause::new("e.", self.from, self.to).walk_ast(out.reborrow())?;
         }
 
         Ok(())
@@ -1344,8 +1488,8 @@ impl<'a> LoadQuery<PgConnection, EntityData> for FindChangesQuery<'a> {
 impl<'a, Conn> RunQueryDsl<Conn> for FindChangesQuery<'a> {}
 
 /// Builds a query over a given set of [`Table`]s in an attempt to find deleted
-/// entities; i.e. such that the block range's lower bound is equal to said
-/// block number.
+/// entities in the blocks `(from, to]`; i.e. such that the block range's
+/// upper bound falls into that interval.
 ///
 /// Please note that the result set from this query is *not* definitive. This
 /// query is intented to be used together with [`FindChangesQuery`]; by
@@ -1355,7 +1499,8 @@ impl<'a, Conn> RunQueryDsl<Conn> for FindChangesQuery<'a> {}
 pub struct FindPossibleDeletionsQuery<'a> {
     pub(crate) _namespace: &'a Namespace,
     pub(crate) tables: &'a [&'a Table],
-    pub(crate) block: BlockNumber,
+    pub(crate) from: BlockNumber,
+    pub(crate) to: BlockNumber,
 }
 
 impl<'a> QueryFragment<Pg> for FindPossibleDeletionsQuery<'a> {
@@ -1372,7 +1517,7 @@ impl<'a> QueryFragment<Pg> for FindPossibleDeletionsQuery<'a> {
             out.push_sql("  from ");
             out.push_sql(table.qualified_name.as_str());
             out.push_sql(" e\n where ");
-            BlockRangeUpperBoundClause::new("e.", self.block).walk_ast(out.reborrow())?;
+            BlockRangeUpperBoundClause::new("e.", self.from, self.to).walk_ast(out.reborrow())?;
         }
 
         Ok(())
@@ -2224,7 +2369,7 @@ impl<'a> FilterCollection<'a> {
 
 /// Convenience to pass the name of the column to order by around. If `name`
 /// is `None`, the sort key should be ignored
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub enum SortKey<'a> {
     None,
     /// Order by `id asc`
@@ -2237,6 +2382,12 @@ pub enum SortKey<'a> {
         value: Option<&'a str>,
         direction: &'static str,
     },
+    /// Order by several columns, followed by `id` if it is not one of
+    /// them. None of the columns is a fulltext column
+    Multiple {
+        columns: Vec<(&'a Column, &'static str)>,
+        br_column: Option<BlockRangeColumn<'a>>,
+    },
 }
 
 impl<'a> SortKey<'a> {
@@ -2294,6 +2445,24 @@ impl<'a> SortKey<'a> {
         match order {
             EntityOrder::Ascending(attr, _) => with_key(table, attr, filter, ASC, br_column),
             EntityOrder::Descending(attr, _) => with_key(table, attr, filter, DESC, br_column),
+            EntityOrder::Multiple(keys) => {
+                let mut columns = Vec::with_capacity(keys.len());
+                for (attr, direction) in keys {
+                    let column = table.column_for_field(&attr)?;
+                    if column.is_fulltext() {
+                        return Err(QueryExecutionError::NotSupported(format!(
+                            "can not order by fulltext field `{}` together with other fields",
+                            attr
+                        )));
+                    }
+                    let direction = match direction {
+                        OrderDirection::Ascending => ASC,
+                        OrderDirection::Descending => DESC,
+                    };
+                    columns.push((column, direction));
+                }
+                Ok(SortKey::Multiple { columns, br_column })
+            }
             EntityOrder::Default => Ok(SortKey::IdAsc(br_column)),
             EntityOrder::Unordered => Ok(SortKey::None),
         }
@@ -2322,6 +2491,19 @@ impl<'a> SortKey<'a> {
                 out.push_identifier(column.name.as_str())?;
                 Ok(())
             }
+            SortKey::Multiple { columns, br_column } => {
+                for (column, _) in columns {
+                    if !column.is_primary_key() {
+                        out.push_sql(", c.");
+                        out.push_identifier(column.name.as_str())?;
+                    }
+                }
+                if let Some(br_column) = br_column {
+                    out.push_sql(", ");
+                    br_column.name(out);
+                }
+                Ok(())
+            }
         }
     }
 
@@ -2358,6 +2540,10 @@ impl<'a> SortKey<'a> {
                 out.push_sql("order by ");
                 SortKey::sort_expr(column, value, direction, out)
             }
+            SortKey::Multiple { columns, br_column } => {
+                out.push_sql("order by ");
+                SortKey::multi_sort_expr(columns, br_column, out)
+            }
         }
     }
 
@@ -2384,9 +2570,64 @@ impl<'a> SortKey<'a> {
                 out.push_sql("order by g$parent_id, ");
                 SortKey::sort_expr(column, value, direction, out)
             }
+            SortKey::Multiple { columns, .. } => {
+                out.push_sql("order by g$parent_id, ");
+                SortKey::multi_sort_expr(columns, &None, out)
+            }
         }
     }
 
+    /// Generate
+    ///   name1 direction1, name2 direction2, .., [id direction], [block_range direction]
+    /// The `id` and the block range tiebreakers use the direction of the
+    /// last sort key, unless `id` is one of the sort keys
+    fn multi_sort_expr(
+        columns: &[(&Column, &str)],
+        br_column: &Option<BlockRangeColumn>,
+        out: &mut AstPass<Pg>,
+    ) -> QueryResult<()> {
+        for (i, (column, direction)) in columns.iter().enumerate() {
+            if i > 0 {
+                out.push_sql(", ");
+            }
+            out.push_identifier(column.name.as_str())?;
+            out.push_sql(" ");
+            out.push_sql(direction);
+            if ENV_VARS.store.reversible_order_by_off {
+                // Old behavior
+                out.push_sql(" nulls last");
+            }
+        }
+
+        let id_direction = match columns.iter().find(|(column, _)| column.is_primary_key()) {
+            Some((_, direction)) => *direction,
+            None => {
+                let direction = if ENV_VARS.store.reversible_order_by_off {
+                    "asc"
+                } else {
+                    columns
+                        .last()
+                        .map(|(_, direction)| *direction)
+                        .unwrap_or("asc")
+                };
+                if !columns.is_empty() {
+                    out.push_sql(", ");
+                }
+                out.push_identifier(PRIMARY_KEY_COLUMN)?;
+                out.push_sql(" ");
+                out.push_sql(direction);
+                direction
+            }
+        };
+        if let Some(br_column) = br_column {
+            out.push_sql(", ");
+            br_column.bare_name(out);
+            out.push_sql(" ");
+            out.push_sql(id_direction);
+        }
+        Ok(())
+    }
+
     /// Generate
     ///   [name direction,] id
     fn sort_expr(
@@ -2800,6 +3041,46 @@ impl<'a> LoadQuery<PgConnection, EntityData> for FilterQuery<'a> {
 
 impl<'a, Conn> RunQueryDsl<Conn> for FilterQuery<'a> {}
 
+/// Count the rows that a `FilterQuery` returns
+#[derive(Debug, Clone, Constructor)]
+pub struct FilterCountQuery<'a> {
+    query: FilterQuery<'a>,
+}
+
+impl<'a> QueryFragment<Pg> for FilterCountQuery<'a> {
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.unsafe_to_cache_prepared();
+
+        // An empty collection produces no SQL for the `FilterQuery`, and
+        // nothing to count
+        if self.query.collection.is_empty() {
+            out.push_sql("select 0::bigint as count");
+            return Ok(());
+        }
+
+        // Construct a query
+        //   select count(*) as count from (<filter query>) q
+        out.push_sql("select count(*) as count from (\n");
+        self.query.walk_ast(out.reborrow())?;
+        out.push_sql(") q");
+        Ok(())
+    }
+}
+
+impl<'a> QueryId for FilterCountQuery<'a> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<'a> LoadQuery<PgConnection, Count> for FilterCountQuery<'a> {
+    fn internal_load(self, conn: &PgConnection) -> QueryResult<Vec<Count>> {
+        conn.query_by_name(&self)
+    }
+}
+
+impl<'a, Conn> RunQueryDsl<Conn> for FilterCountQuery<'a> {}
+
 /// Reduce the upper bound of the current entry's block range to `block` as
 /// long as that does not result in an empty block range
 #[derive(Debug)]
@@ -3023,6 +3304,60 @@ impl<'a> LoadQuery<PgConnection, ReturnedEntityData> for RevertClampQuery<'a> {
 
 impl<'a, Conn> RunQueryDsl<Conn> for RevertClampQuery<'a> {}
 
+/// The result of a query that counts rows, like `RevertCountQuery` or
+/// `FilterCountQuery`
+#[derive(QueryableByName)]
+pub struct Count {
+    #[sql_type = "BigInt"]
+    pub count: i64,
+}
+
+/// A query that counts the versions whose block range lies entirely beyond
+/// `block`, i.e., the versions that `RevertRemoveQuery` would remove
+#[derive(Debug, Clone)]
+pub struct RevertCountQuery<'a> {
+    table: &'a Table,
+    br_column: BlockRangeColumn<'a>,
+}
+
+impl<'a> RevertCountQuery<'a> {
+    pub fn new(table: &'a Table, block: BlockNumber) -> Self {
+        let br_column = BlockRangeColumn::new(table, "", block);
+        Self { table, br_column }
+    }
+}
+
+impl<'a> QueryFragment<Pg> for RevertCountQuery<'a> {
+    fn walk_ast(&self, mut out: AstPass<Pg>) -> QueryResult<()> {
+        out.unsafe_to_cache_prepared();
+
+        // Construct a query
+        //   select count(*) as count from table
+        //    where lower(block_range) >= $block
+        out.push_sql("select count(*) as count from ");
+        out.push_sql(self.table.qualified_name.as_str());
+        out.push_sql(
+            "
+ where ",
+        );
+        self.br_column.changed_since(&mut out)
+    }
+}
+
+impl<'a> QueryId for RevertCountQuery<'a> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<'a> LoadQuery<PgConnection, Count> for RevertCountQuery<'a> {
+    fn internal_load(self, conn: &PgConnection) -> QueryResult<Vec<Count>> {
+        conn.query_by_name(&self)
+    }
+}
+
+impl<'a, Conn> RunQueryDsl<Conn> for RevertCountQuery<'a> {}
+
 #[test]
 fn block_number_max_is_i32_max() {
     // The code in RevertClampQuery::walk_ast embeds i32::MAX
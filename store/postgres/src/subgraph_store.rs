@@ -23,9 +23,10 @@ use graph::{
     prelude::StoreEvent,
     prelude::{
         anyhow, futures03::future::join_all, lazy_static, o, web3::types::Address, ApiSchema,
-        BlockHash, BlockNumber, BlockPtr, ChainStore, DeploymentHash, EntityOperation, Logger,
+        BlockHash, BlockNumber, BlockPtr, ChainStore, DeploymentHash, DeploymentState, Entity,
+        EntityChange, EntityChangeOperation, EntityFilter, EntityOperation, Logger,
         MetricsRegistry, NodeId, PartialBlockPtr, Schema, StoreError, SubgraphName,
-        SubgraphStore as SubgraphStoreTrait, SubgraphVersionSwitchingMode,
+        SubgraphStore as SubgraphStoreTrait, SubgraphVersionSwitchingMode, ENV_VARS,
     },
     url::Url,
     util::timed_cache::TimedCache,
@@ -36,11 +37,12 @@ use crate::{
     connection_pool::ConnectionPool,
     primary,
     primary::{DeploymentId, Mirror as PrimaryMirror, Site},
-    relational::Layout,
+    relational::{Layout, SchemaChange},
     writable::WritableStore,
     NotificationSender,
 };
 use crate::{
+    deployment::{self, ManifestMetadata},
     deployment_store::{DeploymentStore, ReplicaId},
     detail::DeploymentDetail,
     primary::UnusedDeployment,
@@ -258,6 +260,7 @@ impl std::ops::Deref for SubgraphStore {
 }
 
 pub struct SubgraphStoreInner {
+    logger: Logger,
     mirror: PrimaryMirror,
     stores: HashMap<Shard, Arc<DeploymentStore>>,
     /// Cache for the mapping from deployment id to shard/namespace/id. Only
@@ -314,12 +317,14 @@ impl SubgraphStoreInner {
                         main_pool,
                         read_only_pools,
                         weights,
+                        registry.cheap_clone(),
                     )),
                 )
             },
         ));
         let sites = TimedCache::new(SITES_CACHE_TTL);
         SubgraphStoreInner {
+            logger: logger.clone(),
             mirror,
             stores,
             sites,
@@ -346,6 +351,13 @@ impl SubgraphStoreInner {
             .map(|site| site.shard.clone())
     }
 
+    /// Return the shard that holds the data for the active deployment of
+    /// `id`. Each shard has its own connection pools whose metrics are
+    /// labeled with the shard name
+    pub fn shard_for_deployment(&self, id: &DeploymentHash) -> Result<Shard, StoreError> {
+        self.site(id).map(|site| site.shard.clone())
+    }
+
     fn cache_active(&self, site: &Arc<Site>) {
         if site.active {
             self.sites.set(site.deployment.clone(), site.clone());
@@ -494,6 +506,22 @@ impl SubgraphStoreInner {
         #[cfg(not(debug_assertions))]
         assert!(!replace);
 
+        if let Some(base) = &deployment.graft_base {
+            // Each deployment in the chain of the base is one graft for
+            // the new deployment
+            let depth = self.graft_chain(base)?.len();
+            if depth > ENV_VARS.store.max_graft_depth {
+                return Err(constraint_violation!(
+                    "can not graft {} onto {} since that would lead to a chain of {} grafts \
+                     but at most {} are allowed (see GRAPH_MAX_GRAFT_DEPTH)",
+                    schema.id,
+                    base,
+                    depth,
+                    ENV_VARS.store.max_graft_depth
+                ));
+            }
+        }
+
         let (site, node_id) = {
             // We need to deal with two situations:
             //   (1) We are really creating a new subgraph; it therefore needs
@@ -545,13 +573,21 @@ impl SubgraphStoreInner {
         // FIXME: This simultaneously holds a `primary_conn` and a shard connection, which can
         // potentially deadlock.
         let pconn = self.primary_conn()?;
-        pconn.transaction(|| -> Result<_, StoreError> {
-            // Create subgraph, subgraph version, and assignment
-            let changes =
-                pconn.create_subgraph_version(name, &site, node_id, mode, exists_and_synced)?;
-            let event = StoreEvent::new(changes);
-            pconn.send_store_event(&self.sender, &event)?;
-            Ok(())
+        let attempts = deployment::TRANSACTION_CONFLICT_ATTEMPTS;
+        deployment::retry_on_transaction_conflict(&self.logger, attempts, || {
+            pconn.transaction(|| -> Result<_, StoreError> {
+                // Create subgraph, subgraph version, and assignment
+                let changes = pconn.create_subgraph_version(
+                    name.clone(),
+                    &site,
+                    node_id.clone(),
+                    mode,
+                    exists_and_synced,
+                )?;
+                let event = StoreEvent::new(changes);
+                pconn.send_store_event(&self.sender, &event)?;
+                Ok(())
+            })
         })?;
         Ok(site.as_ref().into())
     }
@@ -779,6 +815,22 @@ impl SubgraphStoreInner {
         let site = self.find_site(id)?;
         let store = self.for_site(site.as_ref())?;
 
+        // Refuse to remove a graft base while deployments grafted onto it
+        // still need to copy its data
+        let dependents = self.graft_dependents_in_shards(&site.deployment, true)?;
+        if !dependents.is_empty() {
+            return Err(constraint_violation!(
+                "can not remove deployment {} since the deployments [{}] are grafted \
+                 onto it and have not copied its data yet",
+                site.deployment,
+                dependents
+                    .iter()
+                    .map(|id| id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
         // Check that deployment is not assigned
         let mut removable = self.mirror.assigned_node(site.as_ref())?.is_none();
 
@@ -848,6 +900,264 @@ impl SubgraphStoreInner {
         Ok(infos)
     }
 
+    /// Check for every deployment whether the entity count stored in its
+    /// metadata matches the number of entities it actually contains, and
+    /// return `(deployment, stored, actual)` for those where it does not.
+    /// This only reads data and never corrects the stored counts
+    pub fn audit_entity_counts(&self) -> Result<Vec<(DeploymentHash, i64, i64)>, StoreError> {
+        let sites = self.primary_conn()?.sites()?;
+        let by_shard = self.deployments_by_shard(sites)?;
+
+        let mut mismatches = Vec::new();
+        for (shard, sites) in by_shard.into_iter() {
+            let store = self
+                .stores
+                .get(&shard)
+                .ok_or_else(|| StoreError::UnknownShard(shard.to_string()))?;
+            mismatches.extend(store.audit_entity_counts(&sites)?);
+        }
+        Ok(mismatches)
+    }
+
+    /// Return the deployment that the subgraph `name` is currently syncing
+    /// as its pending version and that will become the current version
+    /// once it has caught up, or `None` if there is no pending version
+    pub fn pending_deployment(
+        &self,
+        name: &SubgraphName,
+    ) -> Result<Option<DeploymentHash>, StoreError> {
+        self.mirror.pending_deployment_for_subgraph(name)
+    }
+
+    /// Return all versions of the subgraph `name`, including ones that
+    /// are neither current nor pending anymore, in the order in which they
+    /// were created, as `(version_id, deployment, is_current, is_pending)`
+    pub fn subgraph_versions(
+        &self,
+        name: &SubgraphName,
+    ) -> Result<Vec<(String, DeploymentHash, bool, bool)>, StoreError> {
+        self.mirror.subgraph_versions(name)
+    }
+
+    /// Suggest `create index` statements for the deployment `id` that
+    /// would speed up queries using `filters`. See
+    /// `Layout::suggested_indexes` for details
+    pub fn suggested_indexes(
+        &self,
+        id: &DeploymentHash,
+        filters: &[EntityFilter],
+    ) -> Result<Vec<String>, StoreError> {
+        Ok(self.layout(id)?.suggested_indexes(filters))
+    }
+
+    /// Compare the schema of the deployment `id` with `new_schema`, for
+    /// example, before deploying a new version of a subgraph over it. See
+    /// `Layout::schema_diff` for details
+    pub fn schema_diff(
+        &self,
+        id: &DeploymentHash,
+        new_schema: &Schema,
+    ) -> Result<Vec<SchemaChange>, StoreError> {
+        let layout = self.layout(id)?;
+        let new_layout = Layout::new(layout.site.clone(), new_schema, layout.catalog.clone())?;
+        Ok(layout.schema_diff(&new_layout))
+    }
+
+    /// Load the states of the deployments in `ids`, querying each shard only
+    /// once. Deployments that can not be found are left out of the result
+    pub fn deployment_states(
+        &self,
+        ids: &[DeploymentHash],
+    ) -> Result<Vec<DeploymentState>, StoreError> {
+        // find_sites takes an empty list to mean 'give me everything', so
+        // we short-circuit that here
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let ids: Vec<_> = ids.iter().map(|id| id.to_string()).collect();
+        let sites = self.mirror.find_sites(&ids, true)?;
+        let by_shard = self.deployments_by_shard(sites)?;
+
+        let mut states = Vec::new();
+        for (shard, sites) in by_shard.into_iter() {
+            let store = self
+                .stores
+                .get(&shard)
+                .ok_or_else(|| StoreError::UnknownShard(shard.to_string()))?;
+            let ids: Vec<_> = sites.iter().map(|site| site.deployment.clone()).collect();
+            states.extend(store.deployment_states_from_ids(&ids)?);
+        }
+        Ok(states)
+    }
+
+    /// List all subgraphs that have a current version with the deployment
+    /// of that version and its state. Deployments whose state can not be
+    /// loaded, for example because they are still being set up, are listed
+    /// with a state of `None` rather than failing the whole list
+    pub fn list_detailed(
+        &self,
+    ) -> Result<Vec<(SubgraphName, DeploymentHash, Option<DeploymentState>)>, StoreError> {
+        let subgraphs = self.mirror.current_deployments()?;
+
+        let ids: Vec<_> = subgraphs.iter().map(|(_, id)| id.clone()).collect();
+        let states: HashMap<_, _> = self
+            .deployment_states(&ids)?
+            .into_iter()
+            .map(|state| (state.id.clone(), state))
+            .collect();
+
+        Ok(subgraphs
+            .into_iter()
+            .map(|(name, id)| {
+                // Several subgraphs can use the same deployment
+                let state = states.get(&id).cloned();
+                (name, id, state)
+            })
+            .collect())
+    }
+
+    fn deployments_by_synced(&self, synced: bool) -> Result<Vec<DeploymentHash>, StoreError> {
+        let mut ids = Vec::new();
+        for store in self.stores.values() {
+            ids.extend(store.deployments_by_synced(synced)?);
+        }
+        Ok(ids)
+    }
+
+    /// Return all deployments across all shards that have finished syncing
+    pub fn synced_deployments(&self) -> Result<Vec<DeploymentHash>, StoreError> {
+        self.deployments_by_synced(true)
+    }
+
+    /// Return all deployments across all shards that are still syncing
+    pub fn syncing_deployments(&self) -> Result<Vec<DeploymentHash>, StoreError> {
+        self.deployments_by_synced(false)
+    }
+
+    /// Return all deployments across all shards that are currently in a
+    /// reorg that is deeper than `threshold` blocks, together with the
+    /// depth of that reorg
+    pub fn deployments_exceeding_reorg_depth(
+        &self,
+        threshold: u32,
+    ) -> Result<Vec<(DeploymentHash, u32)>, StoreError> {
+        let mut deployments = Vec::new();
+        for store in self.stores.values() {
+            deployments.extend(store.deployments_exceeding_reorg_depth(threshold)?);
+        }
+        deployments.sort_by(|(_, a), (_, b)| b.cmp(a));
+        Ok(deployments)
+    }
+
+    fn graft_dependents_in_shards(
+        &self,
+        base: &DeploymentHash,
+        pending_only: bool,
+    ) -> Result<Vec<DeploymentHash>, StoreError> {
+        let mut dependents = Vec::new();
+        for store in self.stores.values() {
+            dependents.extend(store.graft_dependents(base, pending_only)?);
+        }
+        Ok(dependents)
+    }
+
+    /// Return all deployments, across all shards, that are grafted onto
+    /// `base`
+    pub fn graft_dependents(
+        &self,
+        base: &DeploymentHash,
+    ) -> Result<Vec<DeploymentHash>, StoreError> {
+        self.graft_dependents_in_shards(base, false)
+    }
+
+    /// Return the chain of graft bases for the deployment `id`, starting
+    /// with `id` itself, followed by its graft base, the graft base of that
+    /// and so on. The walk stops once the chain is longer than the maximum
+    /// graft depth
+    pub fn graft_chain(&self, id: &DeploymentHash) -> Result<Vec<DeploymentHash>, StoreError> {
+        let mut chain = vec![id.clone()];
+        let mut current = id.clone();
+        while chain.len() <= ENV_VARS.store.max_graft_depth {
+            let (store, _) = self.store(&current)?;
+            match store.graft_point(&current)? {
+                Some((base, _)) => {
+                    chain.push(base.clone());
+                    current = base;
+                }
+                None => break,
+            }
+        }
+        Ok(chain)
+    }
+
+    /// Remove the subgraph `name` and all its versions like
+    /// `remove_subgraph`. Return `false` if there was no such subgraph and
+    /// `true` if it was removed, so that callers can tell the two apart
+    /// without treating a missing subgraph as an error
+    pub fn remove_subgraph_if_exists(&self, name: SubgraphName) -> Result<bool, StoreError> {
+        let pconn = self.primary_conn()?;
+        pconn.transaction(|| -> Result<_, StoreError> {
+            match pconn.remove_subgraph_if_exists(name)? {
+                Some(changes) => {
+                    pconn.send_store_event(&self.sender, &StoreEvent::new(changes))?;
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        })
+    }
+
+    /// Remove the subgraph whose row in `subgraphs.subgraph` has the given
+    /// `id` together with all its versions. Deployments that are not used
+    /// by any subgraph version anymore are unassigned
+    pub fn remove_subgraph_by_id(&self, id: &str) -> Result<(), StoreError> {
+        let pconn = self.primary_conn()?;
+        pconn.transaction(|| -> Result<_, StoreError> {
+            let changes = pconn.remove_subgraph_by_id(id)?;
+            pconn.send_store_event(&self.sender, &StoreEvent::new(changes))
+        })
+    }
+
+    /// Return the `StoreEvent`s that describe the current state of the
+    /// deployment `id`, as if it had just been assigned and all its data
+    /// had just been written. That lets consumers of store events that
+    /// just started catch up with the current state: the first event sets
+    /// the deployment's assignment if it is assigned to a node, and the
+    /// second one announces changes for every entity type of the deployment.
+    /// There is no event for the deployment's manifest: the manifest is not
+    /// stored as entities, and `EntityChange` has no way to describe it, so
+    /// no consumer could act on such an event
+    pub fn store_events_for_deployment(
+        &self,
+        id: &DeploymentHash,
+    ) -> Result<Vec<StoreEvent>, StoreError> {
+        let site = self.site(id)?;
+        let mut events = Vec::new();
+
+        if self.mirror.assigned_node(site.as_ref())?.is_some() {
+            events.push(StoreEvent::new(vec![EntityChange::for_assignment(
+                site.as_ref().into(),
+                EntityChangeOperation::Set,
+            )]));
+        }
+
+        let layout = self.layout(id)?;
+        let mut entity_types: Vec<_> = layout.tables.keys().cloned().collect();
+        entity_types.sort();
+        let changes: Vec<_> = entity_types
+            .into_iter()
+            .map(|entity_type| EntityChange::Data {
+                subgraph_id: id.clone(),
+                entity_type,
+            })
+            .collect();
+        if !changes.is_empty() {
+            events.push(StoreEvent::new(changes));
+        }
+        Ok(events)
+    }
+
     pub(crate) fn version_info(&self, version: &str) -> Result<VersionInfo, StoreError> {
         if let Some((deployment_id, created_at)) = self.mirror.version_info(version)? {
             let id = DeploymentHash::new(deployment_id.clone())
@@ -915,6 +1225,43 @@ impl SubgraphStoreInner {
         self.send_store_event(&event)
     }
 
+    /// Remove all data for the deployment `id` so that it gets indexed
+    /// again from scratch the next time it is started. The deployment
+    /// keeps its schema, its metadata and its errors. It must be unassigned
+    /// so that no node is indexing it while it is being truncated
+    pub fn truncate(&self, id: &DeploymentHash) -> Result<(), StoreError> {
+        let (store, site) = self.store(id)?;
+        if let Some(node) = self.primary_conn()?.assigned_node(site.as_ref())? {
+            return Err(StoreError::Unknown(anyhow!(
+                "can not truncate deployment {} since it is assigned to node `{}`",
+                id,
+                node
+            )));
+        }
+        let event = store.truncate(site)?;
+        self.send_store_event(&event)
+    }
+
+    /// Return the spec version, description, and repository from the
+    /// manifest of the deployment `id`
+    pub fn manifest_metadata(&self, id: &DeploymentHash) -> Result<ManifestMetadata, StoreError> {
+        let (store, site) = self.store(id)?;
+        store.manifest_metadata(site.as_ref())
+    }
+
+    /// Return the proof of indexing entry of the deployment `id` for the
+    /// causality region `region` as of `block`. Comparing these entries
+    /// region by region helps find where two indexers diverge
+    pub fn poi_for_region(
+        &self,
+        id: &DeploymentHash,
+        region: &str,
+        block: BlockNumber,
+    ) -> Result<Option<Entity>, StoreError> {
+        let (store, site) = self.store(id)?;
+        store.poi_for_region(site, region, block)
+    }
+
     pub(crate) async fn get_proof_of_indexing(
         &self,
         id: &DeploymentHash,
@@ -1006,6 +1353,11 @@ impl SubgraphStoreInner {
         store.analyze(site, entity_name).await
     }
 
+    pub async fn analyze_all(&self, deployment: &DeploymentLocator) -> Result<(), StoreError> {
+        let (store, site) = self.store(&deployment.hash)?;
+        store.analyze_all(site).await
+    }
+
     pub async fn create_manual_index(
         &self,
         deployment: &DeploymentLocator,
@@ -1,6 +1,6 @@
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{collections::BTreeMap, sync::Arc};
 
 use graph::data::subgraph::schema;
@@ -12,7 +12,7 @@ use graph::slog::info;
 use graph::util::bounded_queue::BoundedQueue;
 use graph::{
     cheap_clone::CheapClone,
-    components::store::{self, EntityType, WritableStore as WritableStoreTrait},
+    components::store::{self, EntityType, WritableStore as WritableStoreTrait, WriteBatch},
     data::subgraph::schema::SubgraphError,
     prelude::{
         BlockPtr, DeploymentHash, EntityKey, EntityModification, Error, Logger, StopwatchMetrics,
@@ -23,7 +23,7 @@ use graph::{
 };
 use store::StoredDynamicDataSource;
 
-use crate::deployment_store::DeploymentStore;
+use crate::deployment_store::{BlockWrite, DeploymentStore};
 use crate::{primary, primary::Site, relational::Layout, SubgraphStore};
 
 graph::prelude::lazy_static! {
@@ -281,6 +281,22 @@ impl SyncStore {
         })
     }
 
+    fn transact_block_batch(
+        &self,
+        writes: &[BlockWrite],
+        stopwatch: &StopwatchMetrics,
+    ) -> Result<(), StoreError> {
+        self.retry("transact_block_batch", move || {
+            let event = self
+                .writable
+                .transact_block_batch(self.site.clone(), writes, stopwatch)?;
+
+            let _section = stopwatch.start_section("send_store_event");
+            self.try_send_store_event(event)?;
+            Ok(())
+        })
+    }
+
     fn get_many(
         &self,
         ids_for_type: BTreeMap<&EntityType, Vec<&str>>,
@@ -423,6 +439,8 @@ enum Request {
         mods: Vec<EntityModification>,
         data_sources: Vec<StoredDynamicDataSource>,
         deterministic_errors: Vec<SubgraphError>,
+        /// When the request was put into the queue
+        queued_at: Instant,
     },
     RevertTo {
         store: Arc<SyncStore>,
@@ -443,6 +461,7 @@ impl Request {
                 mods,
                 data_sources,
                 deterministic_errors,
+                queued_at: _,
             } => store.transact_block_operations(
                 block_ptr_to,
                 firehose_cursor.as_deref(),
@@ -458,6 +477,58 @@ impl Request {
             } => store.revert_block_operations(block_ptr.clone(), firehose_cursor.as_deref()),
         }
     }
+
+    /// Execute `batch` in one transaction. This only combines `Write`
+    /// requests that were queued at most `duration` after the first one;
+    /// if `batch` consists of a single request or starts with any other
+    /// kind of request, only the first request is executed. Returns how
+    /// many requests from the front of `batch` were executed
+    fn execute_batch(batch: &[Arc<Request>], duration: Duration) -> Result<usize, StoreError> {
+        let mut started_at = None;
+        let writes: Vec<_> = batch
+            .iter()
+            .map_while(|req| match req.as_ref() {
+                Request::Write {
+                    store,
+                    stopwatch,
+                    block_ptr,
+                    firehose_cursor,
+                    mods,
+                    data_sources,
+                    deterministic_errors,
+                    queued_at,
+                } => {
+                    let started_at = *started_at.get_or_insert(*queued_at);
+                    if queued_at.saturating_duration_since(started_at) > duration {
+                        return None;
+                    }
+                    Some((
+                        store,
+                        stopwatch,
+                        BlockWrite {
+                            block_ptr,
+                            firehose_cursor: firehose_cursor.as_deref(),
+                            mods,
+                            data_sources,
+                            deterministic_errors,
+                        },
+                    ))
+                }
+                Request::RevertTo { .. } => None,
+            })
+            .collect();
+
+        match writes.first() {
+            Some((store, stopwatch, _)) if writes.len() > 1 => {
+                let (store, stopwatch) = (*store, *stopwatch);
+                let count = writes.len();
+                let writes: Vec<_> = writes.into_iter().map(|(_, _, write)| write).collect();
+                store.transact_block_batch(&writes, stopwatch)?;
+                Ok(count)
+            }
+            _ => batch[0].execute().map(|()| 1),
+        }
+    }
 }
 
 /// A queue that asynchronously writes requests queued with `push` to the
@@ -482,36 +553,54 @@ struct Queue {
     poisoned: AtomicBool,
 
     stopwatch: StopwatchMetrics,
+
+    /// How many queued blocks the background writer combines into one
+    /// transaction
+    write_batch: Mutex<WriteBatch>,
 }
 
 /// Support for controlling the background writer (pause/resume) only for
 /// use in tests. In release builds, the checks that pause the writer are
-/// compiled out. Before `allow_steps` is called, the background writer is
-/// allowed to process as many requests as it can
+/// compiled out. Before `allow_steps` is called for a deployment, the
+/// background writer of that deployment is allowed to process as many
+/// requests as it can
 #[cfg(debug_assertions)]
 pub(crate) mod test_support {
-    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
 
-    use graph::{prelude::lazy_static, util::bounded_queue::BoundedQueue};
+    use graph::{
+        components::store::{DeploymentId, DeploymentLocator},
+        prelude::lazy_static,
+        util::bounded_queue::BoundedQueue,
+    };
 
     lazy_static! {
-        static ref DO_STEP: AtomicBool = AtomicBool::new(false);
-        static ref ALLOWED_STEPS: BoundedQueue<()> = BoundedQueue::with_capacity(1_000);
+        static ref ALLOWED_STEPS: Mutex<HashMap<DeploymentId, Arc<BoundedQueue<()>>>> =
+            Mutex::new(HashMap::new());
     }
 
-    pub(super) async fn take_step() {
-        if DO_STEP.load(Ordering::SeqCst) {
-            ALLOWED_STEPS.pop().await
+    pub(super) async fn take_step(deployment: DeploymentId) {
+        let steps = ALLOWED_STEPS.lock().unwrap().get(&deployment).cloned();
+        if let Some(steps) = steps {
+            steps.pop().await
         }
     }
 
-    /// Allow the writer to process `steps` requests. After calling this,
-    /// the writer will only process the number of requests it is allowed to
-    pub async fn allow_steps(steps: usize) {
+    /// Allow the writer for `deployment` to take `steps` steps, each of
+    /// which writes one request or one batch of write requests. After
+    /// calling this, the writer will only take the number of steps it is
+    /// allowed to
+    pub async fn allow_steps(deployment: &DeploymentLocator, steps: usize) {
+        let allowed = ALLOWED_STEPS
+            .lock()
+            .unwrap()
+            .entry(deployment.id)
+            .or_insert_with(|| Arc::new(BoundedQueue::with_capacity(1_000)))
+            .clone();
         for _ in 0..steps {
-            ALLOWED_STEPS.push(()).await
+            allowed.push(()).await
         }
-        DO_STEP.store(true, Ordering::SeqCst);
     }
 }
 
@@ -525,9 +614,6 @@ impl Queue {
     ) -> Arc<Self> {
         async fn start_writer(queue: Arc<Queue>, logger: Logger) {
             loop {
-                #[cfg(debug_assertions)]
-                test_support::take_step().await;
-
                 // We peek at the front of the queue, rather than pop it
                 // right away, so that query methods like `get` have access
                 // to the data while it is being written. If we popped here,
@@ -535,13 +621,32 @@ impl Queue {
                 // the write transaction commits, causing them to return
                 // incorrect results.
                 let req = queue.queue.peek().await;
-                let res = graph::spawn_blocking_allow_panic(move || req.execute()).await;
+
+                // Tests that pause the writer need it to wait with a request
+                // in hand so that it only forms a batch once it may go on
+                #[cfg(debug_assertions)]
+                test_support::take_step(queue.store.site.id.into()).await;
+
+                // When more requests are already waiting, write several of
+                // them in one transaction
+                let write_batch = *queue.write_batch.lock().unwrap();
+                let batch = if write_batch.size > 1 {
+                    queue.queue.peek_many(write_batch.size)
+                } else {
+                    vec![req]
+                };
+                let res = graph::spawn_blocking_allow_panic(move || {
+                    Request::execute_batch(&batch, write_batch.duration)
+                })
+                .await;
 
                 match res {
-                    Ok(Ok(())) => {
-                        // The request has been handled. It's now safe to remove it
-                        // from the queue
-                        queue.queue.pop().await;
+                    Ok(Ok(count)) => {
+                        // The requests have been handled. It's now safe to
+                        // remove them from the queue
+                        for _ in 0..count {
+                            queue.queue.pop().await;
+                        }
                     }
                     Ok(Err(e)) => {
                         error!(logger, "Subgraph writer failed"; "error" => e.to_string());
@@ -576,6 +681,7 @@ impl Queue {
             write_err,
             poisoned: AtomicBool::new(false),
             stopwatch,
+            write_batch: Mutex::new(WriteBatch::default()),
         };
         let queue = Arc::new(queue);
 
@@ -820,6 +926,7 @@ impl Writer {
                     mods,
                     data_sources,
                     deterministic_errors,
+                    queued_at: Instant::now(),
                 };
                 queue.push(req).await
             }
@@ -852,6 +959,13 @@ impl Writer {
         }
     }
 
+    fn set_write_batch(&self, write_batch: WriteBatch) {
+        match self {
+            Writer::Sync { .. } => (),
+            Writer::Async(queue) => *queue.write_batch.lock().unwrap() = write_batch,
+        }
+    }
+
     fn get(&self, key: &EntityKey) -> Result<Option<Entity>, StoreError> {
         match self {
             Writer::Sync(store) => store.get(key, BLOCK_NUMBER_MAX),
@@ -1053,4 +1167,8 @@ impl WritableStoreTrait for WritableStore {
     async fn flush(&self) -> Result<(), StoreError> {
         self.writer.flush().await
     }
+
+    fn set_write_batch(&self, write_batch: WriteBatch) {
+        self.writer.set_write_batch(write_batch)
+    }
 }
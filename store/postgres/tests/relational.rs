@@ -1,13 +1,14 @@
 //! Test mapping of GraphQL schema to a relational schema
-use diesel::connection::SimpleConnection as _;
+use diesel::connection::{Connection as _, SimpleConnection as _};
 use diesel::pg::PgConnection;
 use graph::data::store::scalar;
 use graph::entity;
 use graph::prelude::BlockNumber;
 use graph::prelude::{
-    o, slog, tokio, web3::types::H256, DeploymentHash, Entity, EntityCollection, EntityFilter,
-    EntityKey, EntityOrder, EntityQuery, EntityRange, Logger, Schema, StopwatchMetrics, Value,
-    ValueType, BLOCK_NUMBER_MAX,
+    anyhow, o, slog, tokio, web3::types::H256, DeploymentHash, Entity, EntityChange,
+    EntityCollection, EntityFilter, EntityKey, EntityOperation, EntityOrder, EntityQuery,
+    EntityRange, Logger, OrderDirection, QueryExecutionError, Schema, StopwatchMetrics, StoreError,
+    Value, ValueType, BLOCK_NUMBER_MAX,
 };
 use graph_mock::MockMetricsRegistry;
 use graph_store_postgres::layout_for_tests::set_account_like;
@@ -16,6 +17,7 @@ use graph_store_postgres::layout_for_tests::SqlName;
 use hex_literal::hex;
 use lazy_static::lazy_static;
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::panic;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -27,8 +29,9 @@ use graph::{
     data::store::scalar::{BigDecimal, BigInt, Bytes},
 };
 use graph_store_postgres::{
+    layout_for_tests::deployment_store::savepoint,
     layout_for_tests::make_dummy_site,
-    layout_for_tests::{Layout, Namespace, STRING_PREFIX_SIZE},
+    layout_for_tests::{update_if_unchanged, FindResult, Layout, Namespace, STRING_PREFIX_SIZE},
 };
 
 use test_store::*;
@@ -527,6 +530,96 @@ fn find() {
     });
 }
 
+#[test]
+fn exists() {
+    run_test(|conn, layout| {
+        insert_entity(&conn, &layout, "Scalar", vec![SCALAR_ENTITY.clone()]);
+
+        assert!(layout
+            .exists(conn, &*SCALAR, "one", BLOCK_NUMBER_MAX)
+            .expect("Failed to check Scalar[one]"));
+        assert!(!layout
+            .exists(conn, &*SCALAR, "noone", BLOCK_NUMBER_MAX)
+            .expect("Failed to check Scalar[noone]"));
+        // The entity was inserted at block 0 and therefore did not exist
+        // before that
+        assert!(!layout
+            .exists(conn, &*SCALAR, "one", -1)
+            .expect("Failed to check Scalar[one] at block -1"));
+
+        let err = layout.exists(conn, &*NO_ENTITY, "one", BLOCK_NUMBER_MAX);
+        match err {
+            Err(e) => assert_eq!("unknown table 'NoEntity'", e.to_string()),
+            Ok(_) => panic!("expected an error for a nonexistent entity type"),
+        }
+    });
+}
+
+#[test]
+fn find_with_tombstone() {
+    run_test(|conn, layout| {
+        insert_entity(&conn, &layout, "Scalar", vec![SCALAR_ENTITY.clone()]);
+        let mut two = SCALAR_ENTITY.clone();
+        two.set("id", "two");
+        insert_entity(&conn, &layout, "Scalar", vec![two]);
+        layout
+            .delete(&conn, &*SCALAR, &["two"], 1, &MOCK_STOPWATCH)
+            .expect("Failed to delete");
+
+        match layout
+            .find_with_tombstone(conn, &*SCALAR, "one", BLOCK_NUMBER_MAX)
+            .expect("Failed to find Scalar[one]")
+        {
+            FindResult::Present(entity) => assert_entity_eq!(scrub(&*SCALAR_ENTITY), entity),
+            res => panic!("expected Scalar[one] to be present but got {:?}", res),
+        }
+
+        // `two` existed at block 0 and was deleted at block 1
+        let res = layout
+            .find_with_tombstone(conn, &*SCALAR, "two", BLOCK_NUMBER_MAX)
+            .expect("Failed to find Scalar[two]");
+        assert_eq!(FindResult::Deleted, res);
+        let res = layout
+            .find_with_tombstone(conn, &*SCALAR, "two", 0)
+            .expect("Failed to find Scalar[two] at block 0");
+        assert!(matches!(res, FindResult::Present(_)));
+
+        let res = layout
+            .find_with_tombstone(conn, &*SCALAR, "noone", BLOCK_NUMBER_MAX)
+            .expect("Failed to find Scalar[noone]");
+        assert_eq!(FindResult::NeverExisted, res);
+        // Nothing existed before block 0
+        let res = layout
+            .find_with_tombstone(conn, &*SCALAR, "one", -1)
+            .expect("Failed to find Scalar[one] at block -1");
+        assert_eq!(FindResult::NeverExisted, res);
+    });
+}
+
+#[test]
+fn find_many_sorted_by_id() {
+    run_test(|conn, layout| {
+        let cat = EntityType::from("Cat");
+        let cats = ["tom", "felix", "garfield"]
+            .iter()
+            .map(|id| entity! { id: *id, name: id.to_uppercase() })
+            .collect();
+        insert_entity(&conn, &layout, "Cat", cats);
+
+        let mut ids_for_type: BTreeMap<&EntityType, Vec<&str>> = BTreeMap::new();
+        ids_for_type.insert(&cat, vec!["garfield", "tom", "felix"]);
+        let entities = layout
+            .find_many(conn, &ids_for_type, BLOCK_NUMBER_MAX)
+            .expect("Failed to read many cats");
+
+        let ids: Vec<_> = entities[&cat]
+            .iter()
+            .map(|entity| entity.id().unwrap())
+            .collect();
+        assert_eq!(vec!["felix", "garfield", "tom"], ids);
+    });
+}
+
 #[test]
 fn insert_null_fulltext_fields() {
     run_test(|conn, layout| {
@@ -546,6 +639,53 @@ fn insert_null_fulltext_fields() {
     });
 }
 
+#[test]
+fn find_null_strings() {
+    run_test(|conn, layout| {
+        let two = entity! {
+            id: "two",
+            name: "Two",
+            __typename: "NullableStrings"
+        };
+        insert_entity(
+            &conn,
+            &layout,
+            "NullableStrings",
+            vec![EMPTY_NULLABLESTRINGS_ENTITY.clone(), two],
+        );
+
+        let find = |filter: EntityFilter| -> Vec<String> {
+            let query = query(vec!["NullableStrings"]).filter(filter).asc("id");
+            layout
+                .query::<Entity>(
+                    &*LOGGER,
+                    conn,
+                    query.collection,
+                    query.filter,
+                    query.order,
+                    query.range,
+                    BLOCK_NUMBER_MAX,
+                    None,
+                )
+                .expect("layout.query failed to execute query")
+                .into_iter()
+                .map(|entity| entity.id().unwrap())
+                .collect()
+        };
+
+        assert_eq!(vec!["one"], find(EntityFilter::IsNull("name".to_owned())));
+        assert_eq!(
+            vec!["two"],
+            find(EntityFilter::IsNotNull("name".to_owned()))
+        );
+        assert_eq!(
+            vec!["one", "two"],
+            find(EntityFilter::IsNull("description".to_owned()))
+        );
+        assert!(find(EntityFilter::IsNotNull("description".to_owned())).is_empty());
+    });
+}
+
 #[test]
 fn update() {
     run_test(|conn, layout| {
@@ -576,6 +716,189 @@ fn update() {
     });
 }
 
+#[test]
+fn patch() {
+    run_test(|conn, layout| {
+        insert_entity(&conn, &layout, "Scalar", vec![SCALAR_ENTITY.clone()]);
+        let key = EntityKey::data(
+            THINGS_SUBGRAPH_ID.clone(),
+            "Scalar".to_owned(),
+            "one".to_owned(),
+        );
+
+        // Only the attributes in `changes` are touched, and `Value::Null`
+        // removes an attribute
+        let mut changes = Entity::new();
+        changes.set("string", "patched");
+        changes.set("bool", Value::Null);
+        layout
+            .patch(&conn, &key, changes, 1, &MOCK_STOPWATCH)
+            .expect("Failed to patch");
+
+        let mut expected = SCALAR_ENTITY.clone();
+        expected.set("string", "patched");
+        expected.remove("bool");
+        let actual = layout
+            .find(conn, &*SCALAR, "one", BLOCK_NUMBER_MAX)
+            .expect("Failed to read Scalar[one]")
+            .unwrap();
+        assert_entity_eq!(scrub(&expected), actual);
+
+        // The version before the patch is still there
+        let before = layout
+            .find(conn, &*SCALAR, "one", 0)
+            .expect("Failed to read Scalar[one] at block 0")
+            .unwrap();
+        assert_entity_eq!(scrub(&*SCALAR_ENTITY), before);
+
+        // Patching an entity that does not exist fails
+        let key = EntityKey::data(
+            THINGS_SUBGRAPH_ID.clone(),
+            "Scalar".to_owned(),
+            "noone".to_owned(),
+        );
+        layout
+            .patch(&conn, &key, Entity::new(), 1, &MOCK_STOPWATCH)
+            .expect_err("patching a missing entity fails");
+    });
+}
+
+#[test]
+fn update_only_if_unchanged() {
+    run_test(|conn, layout| {
+        insert_entity(&conn, &layout, "Scalar", vec![SCALAR_ENTITY.clone()]);
+        let key = EntityKey::data(
+            THINGS_SUBGRAPH_ID.clone(),
+            "Scalar".to_owned(),
+            "one".to_owned(),
+        );
+
+        let stored = layout
+            .find(conn, &*SCALAR, "one", BLOCK_NUMBER_MAX)
+            .expect("Failed to read Scalar[one]")
+            .unwrap();
+
+        // The update is applied when the stored entity is what we expect
+        let mut first = stored.clone();
+        first.set("string", "first");
+        let updated = update_if_unchanged(
+            &layout,
+            &conn,
+            &key,
+            &stored,
+            first.clone(),
+            1,
+            &MOCK_STOPWATCH,
+        )
+        .expect("Failed to update");
+        assert!(updated);
+
+        // Trying to update based on the old version does nothing
+        let mut second = stored.clone();
+        second.set("string", "second");
+        let updated =
+            update_if_unchanged(&layout, &conn, &key, &stored, second, 2, &MOCK_STOPWATCH)
+                .expect("Failed to update");
+        assert!(!updated);
+
+        let actual = layout
+            .find(conn, &*SCALAR, "one", BLOCK_NUMBER_MAX)
+            .expect("Failed to read Scalar[one]")
+            .unwrap();
+        assert_entity_eq!(scrub(&first), actual);
+    });
+}
+
+#[test]
+fn savepoint_rolls_back_inside_transaction() {
+    run_test(|conn, layout| {
+        savepoint(conn, || Ok(())).expect_err("a savepoint needs a transaction");
+
+        conn.transaction::<_, StoreError, _>(|| {
+            insert_entity(&conn, &layout, "Scalar", vec![SCALAR_ENTITY.clone()]);
+            let res: Result<(), _> = savepoint(conn, || {
+                let mut entity = SCALAR_ENTITY.clone();
+                entity.set("id", "two");
+                insert_entity(&conn, &layout, "Scalar", vec![entity]);
+                Err(StoreError::Unknown(anyhow!("roll back")))
+            });
+            assert!(res.is_err());
+            Ok(())
+        })
+        .expect("the outer transaction commits");
+
+        // Only the changes made inside the savepoint were rolled back
+        let find = |id| {
+            layout
+                .find(conn, &*SCALAR, id, BLOCK_NUMBER_MAX)
+                .expect("Failed to read Scalar")
+        };
+        assert!(find("one").is_some());
+        assert!(find("two").is_none());
+    });
+}
+
+#[test]
+fn copy_entities() {
+    run_test(|conn, layout| {
+        let rows = (0..5).map(|i| {
+            let mut entity = SCALAR_ENTITY.clone();
+            entity.set("id", format!("copy{}", i));
+            entity
+        });
+        let count = layout
+            .copy_entities(&conn, &*SCALAR, rows, 0)
+            .expect("Failed to copy entities");
+        assert_eq!(5, count);
+        assert_eq!(5, count_scalar_entities(conn, layout));
+
+        // Rows with values of the wrong type are rejected
+        let mut bad = SCALAR_ENTITY.clone();
+        bad.set("id", "bad");
+        bad.set("int", "not an int");
+        layout
+            .copy_entities(&conn, &*SCALAR, vec![bad].into_iter(), 0)
+            .expect_err("copying a string into an Int attribute fails");
+
+        // So are rows with attributes that are not in the schema
+        let mut bad = SCALAR_ENTITY.clone();
+        bad.set("id", "bad");
+        bad.set("nonexistent", 1);
+        layout
+            .copy_entities(&conn, &*SCALAR, vec![bad].into_iter(), 0)
+            .expect_err("copying an unknown attribute fails");
+        assert_eq!(5, count_scalar_entities(conn, layout));
+    });
+}
+
+#[test]
+fn find_one() {
+    run_test(|conn, layout| {
+        let mut two = SCALAR_ENTITY.clone();
+        two.set("id", "two");
+        two.set("string", "other");
+        insert_entity(&conn, &layout, "Scalar", vec![SCALAR_ENTITY.clone(), two]);
+
+        let find = |filter: EntityFilter| {
+            let collection = EntityCollection::All(vec![(SCALAR.to_owned(), AttributeNames::All)]);
+            layout.find_one(&*LOGGER, conn, collection, filter, BLOCK_NUMBER_MAX)
+        };
+
+        let entity = find(EntityFilter::Equal("string".into(), "other".into()))
+            .expect("Failed to find Scalar")
+            .expect("Scalar[two] matches");
+        assert_eq!(Some(&Value::from("two")), entity.get("id"));
+
+        let entity = find(EntityFilter::Equal("string".into(), "nothing".into()))
+            .expect("Failed to find Scalar");
+        assert!(entity.is_none());
+
+        let err =
+            find(EntityFilter::Equal("bool".into(), true.into())).expect_err("both entities match");
+        assert!(matches!(err, QueryExecutionError::AmbiguousResult(_)));
+    });
+}
+
 #[test]
 fn update_many() {
     run_test(|conn, layout| {
@@ -777,6 +1100,56 @@ fn delete() {
     });
 }
 
+#[test]
+fn truncate() {
+    run_test(|conn, layout| {
+        let mut two = SCALAR_ENTITY.clone();
+        two.set("id", "two");
+        insert_entity(&conn, &layout, "Scalar", vec![SCALAR_ENTITY.clone(), two]);
+        assert_eq!(2, count_scalar_entities(conn, layout));
+
+        let event = layout.truncate(&conn).expect("Failed to truncate");
+        assert_eq!(0, count_scalar_entities(conn, layout));
+        assert!(event.changes.contains(&EntityChange::Data {
+            subgraph_id: THINGS_SUBGRAPH_ID.clone(),
+            entity_type: EntityType::from("Scalar"),
+        }));
+
+        // The tables are still usable after truncating them
+        insert_entity(&conn, &layout, "Scalar", vec![SCALAR_ENTITY.clone()]);
+        assert_eq!(1, count_scalar_entities(conn, layout));
+    });
+}
+
+#[test]
+fn poi_for_region() {
+    run_test(|conn, layout| {
+        const REGION: &str = "ethereum/mainnet";
+        assert!(layout.supports_proof_of_indexing());
+
+        let digest = |byte: u8| Value::Bytes(scalar::Bytes::from(&[byte; 32][..]));
+        let poi = entity! { id: REGION, digest: digest(1) };
+        insert_entity_at(conn, layout, "Poi$", vec![poi], 1);
+        let poi = entity! { id: REGION, digest: digest(2) };
+        update_entity_at(conn, layout, "Poi$", vec![poi], 3);
+
+        let poi_at = |block| {
+            layout
+                .poi_for_region(conn, REGION, block)
+                .expect("Failed to read POI")
+                .map(|poi| poi.get("digest").cloned())
+        };
+        assert_eq!(None, poi_at(0));
+        assert_eq!(Some(Some(digest(1))), poi_at(2));
+        assert_eq!(Some(Some(digest(2))), poi_at(3));
+
+        let other = layout
+            .poi_for_region(conn, "ethereum/ropsten", 3)
+            .expect("Failed to read POI");
+        assert!(other.is_none());
+    });
+}
+
 #[test]
 fn insert_many_and_delete_many() {
     run_test(|conn, layout| {
@@ -815,7 +1188,8 @@ async fn layout_cache() {
             let site = Arc::new(primary_mirror().find_active_site(&id).unwrap().unwrap());
             let table_name = SqlName::verbatim("scalar".to_string());
 
-            let cache = LayoutCache::new(Duration::from_millis(10));
+            let registry = MockMetricsRegistry::new();
+            let cache = LayoutCache::new(Duration::from_millis(10), 10, &registry, "primary");
 
             // Without an entry, account_like is false
             let layout = cache
@@ -897,6 +1271,62 @@ fn conflicting_entity() {
     })
 }
 
+#[test]
+fn find_any() {
+    run_test(|conn, layout| {
+        insert_pet(conn, layout, "Cat", "shared", "Tom", 0);
+        insert_pet(conn, layout, "Dog", "shared", "Rex", 0);
+        insert_pet(conn, layout, "Ferret", "gone", "Gone", 0);
+        let mut byte_cat = Entity::new();
+        byte_cat.set(
+            "id",
+            Value::Bytes(scalar::Bytes::from_str("0xf1ed").unwrap()),
+        );
+        byte_cat.set("name", "Bytey");
+        insert_entity(conn, layout, "ByteCat", vec![byte_cat]);
+        layout
+            .delete(
+                conn,
+                &EntityType::from("Ferret"),
+                &["gone"],
+                1,
+                &MOCK_STOPWATCH,
+            )
+            .expect("Failed to delete");
+
+        // Ids that are not hex strings are not looked up in `ByteCat` etc.
+        // and deleted entities are not found
+        let ids: Vec<_> = ["shared", "0xf1ed", "gone", "nobody"]
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        let mut found: Vec<_> = layout
+            .find_any(conn, &ids)
+            .expect("Failed to find entities")
+            .into_iter()
+            .map(|(entity_type, entity)| {
+                format!(
+                    "{}[{}]: {}",
+                    entity_type,
+                    entity.id().unwrap(),
+                    entity.get("name").unwrap().as_str().unwrap()
+                )
+            })
+            .collect();
+        found.sort();
+        assert_eq!(
+            vec![
+                "ByteCat[0xf1ed]: Bytey",
+                "Cat[shared]: Tom",
+                "Dog[shared]: Rex"
+            ],
+            found
+        );
+
+        assert!(layout.find_any(conn, &[]).unwrap().is_empty());
+    })
+}
+
 #[test]
 fn revert_block() {
     fn check_fred(conn: &PgConnection, layout: &Layout) {
@@ -1070,6 +1500,141 @@ impl<'a> QueryChecker<'a> {
     }
 }
 
+#[test]
+fn revert_block_preview() {
+    use diesel::RunQueryDsl as _;
+    use graph_store_postgres::layout_for_tests::{Count, RevertCountQuery};
+
+    run_test(|conn, layout| {
+        let cat_type = EntityType::from("Cat");
+        let mink_type = EntityType::from("Mink");
+        let cat = |id: &str, name: &str| entity! { id: id, name: name };
+        let mink = |id: &str, order: i32| entity! { id: id, order: order };
+        // The number of versions of `entity_type` in the database
+        let versions = |entity_type: &EntityType| {
+            let table = layout.table_for_entity(entity_type).unwrap();
+            RevertCountQuery::new(table, 0)
+                .get_result::<Count>(conn)
+                .unwrap()
+                .count as i32
+        };
+        // Check that reverting to `block` removes exactly as many versions
+        // as the preview says it will
+        let check_revert = |block, expected: Vec<(&EntityType, i32)>| {
+            let preview = layout.revert_block_preview(conn, block).unwrap();
+            let expected: BTreeMap<_, _> = expected
+                .into_iter()
+                .map(|(entity_type, count)| (entity_type.clone(), count))
+                .collect();
+            assert_eq!(expected, preview);
+
+            let before: Vec<_> = [&cat_type, &mink_type]
+                .iter()
+                .map(|et| versions(*et))
+                .collect();
+            layout.revert_block(conn, block).unwrap();
+            let after: Vec<_> = [&cat_type, &mink_type]
+                .iter()
+                .map(|et| versions(*et))
+                .collect();
+            let removed = |idx: usize| before[idx] - after[idx];
+            assert_eq!(preview.get(&cat_type).copied().unwrap_or(0), removed(0));
+            assert_eq!(preview.get(&mink_type).copied().unwrap_or(0), removed(1));
+
+            assert!(layout.revert_block_preview(conn, block).unwrap().is_empty());
+        };
+
+        insert_entity_at(conn, layout, "Cat", vec![cat("a", "a0")], 0);
+        update_entity_at(conn, layout, "Cat", vec![cat("a", "a1")], 1);
+        insert_entity_at(conn, layout, "Cat", vec![cat("b", "b1")], 1);
+        insert_entity_at(conn, layout, "Mink", vec![mink("m1", 1)], 1);
+        update_entity_at(conn, layout, "Cat", vec![cat("a", "a2")], 2);
+        layout
+            .delete(conn, &cat_type, &["b"], 2, &MOCK_STOPWATCH)
+            .expect("Failed to delete");
+        insert_entity_at(conn, layout, "Mink", vec![mink("m2", 2), mink("m3", 2)], 2);
+        insert_entity_at(conn, layout, "Cat", vec![cat("c", "c3")], 3);
+
+        // Reverting to a block after the last change removes nothing
+        check_revert(4, vec![]);
+        // The deletion of `b` at block 2 only clamps a version and
+        // does not count
+        check_revert(2, vec![(&cat_type, 2), (&mink_type, 2)]);
+        check_revert(1, vec![(&cat_type, 2), (&mink_type, 1)]);
+    });
+}
+
+#[test]
+fn entity_changes_since() {
+    run_test(|conn, layout| {
+        let cat = |id: &str, name: &str| entity! { id: id, name: name };
+        let delete_cat = |id: &str, block| {
+            layout
+                .delete(
+                    conn,
+                    &EntityType::from("Cat"),
+                    &[id],
+                    block,
+                    &MOCK_STOPWATCH,
+                )
+                .expect("Failed to delete");
+        };
+        // Summarize changes as `set <id> <name>` and `remove <id>`
+        let summarize = |ops: Vec<EntityOperation>| {
+            let mut changes: Vec<_> = ops
+                .into_iter()
+                .map(|op| match op {
+                    EntityOperation::Set { key, data } => format!(
+                        "set {} {}",
+                        key.entity_id,
+                        data.get("name").unwrap().as_str().unwrap()
+                    ),
+                    EntityOperation::Remove { key } => format!("remove {}", key.entity_id),
+                })
+                .collect();
+            changes.sort();
+            changes
+        };
+        let changes = |from, to| {
+            summarize(
+                layout
+                    .entity_changes_since(conn, from, to)
+                    .expect("Failed to find changes"),
+            )
+        };
+
+        insert_entity_at(
+            conn,
+            layout,
+            "Cat",
+            vec![cat("a", "a0"), cat("b", "b0"), cat("e", "e0")],
+            0,
+        );
+        update_entity_at(conn, layout, "Cat", vec![cat("a", "a1")], 1);
+        insert_entity_at(conn, layout, "Cat", vec![cat("c", "c1")], 1);
+        update_entity_at(conn, layout, "Cat", vec![cat("a", "a2")], 2);
+        delete_cat("b", 2);
+        delete_cat("c", 3);
+        insert_entity_at(conn, layout, "Cat", vec![cat("d", "d3")], 3);
+        update_entity_at(conn, layout, "Cat", vec![cat("e", "e4")], 4);
+
+        // Entities that changed several times are reported once with
+        // their data at the end of the range
+        assert_eq!(
+            vec!["remove b", "remove c", "set a a2", "set d d3"],
+            changes(0, 3)
+        );
+        assert_eq!(vec!["remove b", "set a a2"], changes(1, 2));
+        assert_eq!(vec!["remove c", "set d d3", "set e e4"], changes(2, 4));
+        // A range of one block is the same as `find_changes`
+        assert_eq!(
+            changes(3, 4),
+            summarize(layout.find_changes(conn, 4).unwrap())
+        );
+        assert!(changes(4, 5).is_empty());
+    });
+}
+
 fn query(entity_types: Vec<&str>) -> EntityQuery {
     EntityQuery::new(
         THINGS_SUBGRAPH_ID.clone(),
@@ -1087,6 +1652,54 @@ fn user_query() -> EntityQuery {
     query(vec!["User"])
 }
 
+#[test]
+fn count() {
+    run_test(|conn, layout| {
+        insert_users(conn, layout);
+        insert_pets(conn, layout);
+
+        // Check that `count` agrees with the number of entities `query`
+        // returns, and return the count
+        let count = |query: EntityQuery| -> i64 {
+            let entities = layout
+                .query::<Entity>(
+                    &*LOGGER,
+                    conn,
+                    query.collection.clone(),
+                    query.filter.clone(),
+                    EntityOrder::Unordered,
+                    EntityRange {
+                        first: None,
+                        skip: 0,
+                    },
+                    BLOCK_NUMBER_MAX,
+                    None,
+                )
+                .expect("layout.query failed to execute query");
+            let count = layout
+                .count(conn, query.collection, query.filter, BLOCK_NUMBER_MAX)
+                .expect("layout.count failed to execute query");
+            assert_eq!(entities.len() as i64, count);
+            count
+        };
+
+        assert_eq!(3, count(user_query()));
+        assert_eq!(
+            1,
+            count(user_query().filter(EntityFilter::Equal("coffee".to_owned(), Value::Bool(true))))
+        );
+        assert_eq!(
+            2,
+            count(user_query().filter(EntityFilter::IsNotNull("favorite_color".to_owned())))
+        );
+        assert_eq!(
+            0,
+            count(user_query().filter(EntityFilter::Equal("name".to_owned(), "Nobody".into())))
+        );
+        assert_eq!(2, count(query(vec!["Cat", "Dog", "Ferret"])));
+    });
+}
+
 trait EasyOrder {
     fn asc(self, attr: &str) -> Self;
     fn desc(self, attr: &str) -> Self;
@@ -1327,6 +1940,82 @@ fn check_find() {
                     ))
                     .desc("name"),
             );
+        // id lists
+        let checker = checker
+            .check(
+                vec![],
+                user_query().filter(EntityFilter::In("id".to_owned(), vec![])),
+            )
+            .check(
+                vec!["2"],
+                user_query().filter(EntityFilter::In("id".to_owned(), vec!["2".into()])),
+            )
+            .check(
+                vec!["1", "3"],
+                user_query()
+                    .filter(EntityFilter::In(
+                        "id".to_owned(),
+                        vec!["3".into(), "1".into(), "nobody".into()],
+                    ))
+                    .asc("id"),
+            )
+            .check(
+                vec!["1", "2", "3"],
+                user_query()
+                    .filter(EntityFilter::NotIn("id".to_owned(), vec![]))
+                    .asc("id"),
+            )
+            .check(
+                vec!["1", "3"],
+                user_query()
+                    .filter(EntityFilter::NotIn("id".to_owned(), vec!["2".into()]))
+                    .asc("id"),
+            )
+            .check(
+                vec![],
+                user_query().filter(EntityFilter::NotIn(
+                    "id".to_owned(),
+                    vec!["1".into(), "2".into(), "3".into()],
+                )),
+            )
+            .check(
+                vec!["3"],
+                user_query().filter(EntityFilter::And(vec![
+                    EntityFilter::In("id".to_owned(), vec!["1".into(), "3".into()]),
+                    EntityFilter::NotIn("name".to_owned(), vec!["Jono".into()]),
+                ])),
+            );
+        // compound sort keys; users 1 and 3 both have `coffee: false`
+        let checker = checker
+            .check(
+                vec!["1", "3", "2"],
+                user_query().order(EntityOrder::Multiple(vec![(
+                    "coffee".to_owned(),
+                    OrderDirection::Ascending,
+                )])),
+            )
+            .check(
+                vec!["2", "3", "1"],
+                user_query().order(EntityOrder::Multiple(vec![
+                    ("coffee".to_owned(), OrderDirection::Descending),
+                    ("age".to_owned(), OrderDirection::Ascending),
+                ])),
+            )
+            .check(
+                vec!["3", "1", "2"],
+                user_query().order(EntityOrder::Multiple(vec![
+                    ("coffee".to_owned(), OrderDirection::Ascending),
+                    ("id".to_owned(), OrderDirection::Descending),
+                ])),
+            )
+            // the `id` tiebreaker follows the direction of the last key
+            .check(
+                vec!["2", "3", "1"],
+                user_query().order(EntityOrder::Multiple(vec![(
+                    "coffee".to_owned(),
+                    OrderDirection::Descending,
+                )])),
+            );
         // float attributes
         let checker = checker
             .check(
@@ -1575,6 +2264,30 @@ fn check_find() {
                     ))
                     .desc("name"),
             )
+            .check(
+                vec!["3"],
+                user_query()
+                    .filter(EntityFilter::IsNull("favorite_color".to_owned()))
+                    .desc("name"),
+            )
+            .check(
+                vec!["1", "2"],
+                user_query()
+                    .filter(EntityFilter::IsNotNull("favorite_color".to_owned()))
+                    .desc("name"),
+            )
+            .check(
+                vec!["1"],
+                user_query()
+                    .filter(EntityFilter::IsNull("drinks".to_owned()))
+                    .desc("name"),
+            )
+            .check(
+                vec!["3", "2"],
+                user_query()
+                    .filter(EntityFilter::IsNotNull("drinks".to_owned()))
+                    .desc("name"),
+            )
             .check(vec!["3", "2", "1"], user_query().asc("weight"))
             .check(vec!["1", "2", "3"], user_query().desc("weight"))
             .check(vec!["1", "2", "3"], user_query().unordered())
@@ -278,6 +278,52 @@ fn find_many() {
     });
 }
 
+#[test]
+fn find_by_hash() {
+    run_test(|conn, layout| {
+        const TX1: &str = "0xe8b3b02b936c4a4a331ac691ac9a86e197fb7731f14e3108602c87d4dac55160";
+        const TX2: &str = "0xb98fb783b49de5652097a989414c767824dff7e7fd765a63b493772511db81c1";
+        const MISSING: &str = "0x977c084229c72a0fa377cae304eda9099b6a2cb5d83b25cdf0f0969b69874255";
+        insert_thing(&conn, &layout, TX1, "one");
+        insert_thing(&conn, &layout, TX2, "two");
+
+        let entity = layout
+            .find(conn, &*THING, TX1, BLOCK_NUMBER_MAX)
+            .expect("Failed to read Thing by hash")
+            .unwrap();
+        assert_eq!(TX1, entity.id().unwrap());
+        assert_eq!(Some(&Value::from("one")), entity.get("name"));
+
+        let entity = layout
+            .find(conn, &*THING, MISSING, BLOCK_NUMBER_MAX)
+            .expect("Failed to read missing Thing by hash");
+        assert!(entity.is_none());
+
+        let mut id_map: BTreeMap<&EntityType, Vec<&str>> = BTreeMap::default();
+        id_map.insert(&*THING, vec![TX1, TX2, MISSING]);
+        let entities = layout
+            .find_many(conn, &id_map, BLOCK_NUMBER_MAX)
+            .expect("Failed to read many things by hash");
+        let mut ids = entities
+            .get(&*THING)
+            .expect("We got some things")
+            .iter()
+            .map(|thing| thing.id().unwrap())
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(vec![TX2.to_owned(), TX1.to_owned()], ids);
+
+        let conflict = layout
+            .conflicting_entity(conn, TX1, vec![THING.clone()])
+            .expect("Failed to check for conflicting entity");
+        assert_eq!(Some(THING.to_string()), conflict);
+        let conflict = layout
+            .conflicting_entity(conn, MISSING, vec![THING.clone()])
+            .expect("Failed to check for conflicting entity");
+        assert_eq!(None, conflict);
+    });
+}
+
 #[test]
 fn update() {
     run_test(|conn, layout| {
@@ -4,6 +4,7 @@ use graph_chain_ethereum::{Mapping, MappingABI};
 use graph_mock::MockMetricsRegistry;
 use hex_literal::hex;
 use lazy_static::lazy_static;
+use std::collections::BTreeMap;
 use std::time::Duration;
 use std::{collections::HashSet, sync::Mutex};
 use std::{marker::PhantomData, str::FromStr};
@@ -11,6 +12,7 @@ use test_store::*;
 
 use graph::components::store::{DeploymentLocator, WritableStore};
 use graph::data::subgraph::*;
+use graph::entity;
 use graph::prelude::*;
 use graph::{
     blockchain::DataSource,
@@ -309,6 +311,34 @@ fn get_entity_count(store: Arc<DieselStore>, subgraph_id: &DeploymentHash) -> u6
     info.entity_count
 }
 
+#[test]
+fn audit_entity_counts() {
+    run_test(|store, _, deployment| async move {
+        let subgraph_store = store.subgraph_store();
+        let drifted = || {
+            subgraph_store
+                .audit_entity_counts()
+                .unwrap()
+                .into_iter()
+                .filter(|(id, _, _)| id == &deployment.hash)
+                .collect::<Vec<_>>()
+        };
+
+        // The count maintained while writing the test data is accurate
+        assert_eq!(Vec::<(DeploymentHash, i64, i64)>::new(), drifted());
+
+        let count = get_entity_count(store.clone(), &deployment.hash) as i64;
+        set_stored_entity_count(&deployment.hash, count + 7);
+        assert_eq!(vec![(deployment.hash.clone(), count + 7, count)], drifted());
+
+        // Auditing does not fix the stored count
+        assert_eq!(
+            (count + 7) as u64,
+            get_entity_count(store.clone(), &deployment.hash)
+        );
+    })
+}
+
 #[test]
 fn delete_entity() {
     run_test(|store, writable, deployment| async move {
@@ -1952,6 +1982,23 @@ fn find_at_block() {
     shaqueeena_at_block(7000, "teeko@email.com");
 }
 
+#[test]
+fn find_canceled() {
+    run_test(|store, _, _| async move {
+        let guard = CancelGuard::new();
+        let query = user_query().cancel_on(guard.handle());
+        let entities = store
+            .subgraph_store()
+            .find(query.clone())
+            .expect("store.find failed to execute query");
+        assert_eq!(3, entities.len());
+
+        guard.cancel();
+        let res = store.subgraph_store().find(query);
+        assert!(matches!(res, Err(QueryExecutionError::Cancelled)));
+    })
+}
+
 #[test]
 fn cleanup_cached_blocks() {
     if store_is_sharded() {
@@ -2070,3 +2117,131 @@ fn reorg_tracking() {
         check_state!(store, 5, 3, 2);
     })
 }
+
+#[test]
+fn revert_through_block_history() {
+    async fn reorg_state(store: &DieselStore) -> (u32, u32, BlockNumber) {
+        let state = deployment_state(store, &TEST_SUBGRAPH_ID).await;
+        (
+            state.reorg_count,
+            state.max_reorg_depth,
+            state.latest_ethereum_block_number,
+        )
+    }
+
+    run_test(|store, _, deployment| async move {
+        let subgraph_store = store.subgraph_store();
+
+        for block in [&*TEST_BLOCK_3_PTR, &*TEST_BLOCK_4_PTR] {
+            transact_and_wait(&subgraph_store, &deployment, block.clone(), vec![])
+                .await
+                .unwrap();
+        }
+        assert_eq!((0, 0, 4), reorg_state(store.as_ref()).await);
+
+        // Block 2 is in the block history, and reverting to it goes back
+        // two blocks in one step
+        revert_block(&store, &deployment, &*TEST_BLOCK_2_PTR).await;
+        assert_eq!((1, 2, 2), reorg_state(store.as_ref()).await);
+
+        // Continue on a different fork
+        for number in 3..=6u64 {
+            let block = BlockPtr::from((H256::from_low_u64_be(0xf0 + number), number));
+            transact_and_wait(&subgraph_store, &deployment, block, vec![])
+                .await
+                .unwrap();
+        }
+        assert_eq!((1, 2, 6), reorg_state(store.as_ref()).await);
+
+        // The history forgot the original block 3 when we reverted it, and
+        // has a different hash for block 3 now. Reverting to the original
+        // block 3 therefore does not go through the history and counts as
+        // going back just one block
+        revert_block(&store, &deployment, &*TEST_BLOCK_3_PTR).await;
+        assert_eq!((2, 2, 3), reorg_state(store.as_ref()).await);
+    })
+}
+
+#[test]
+fn get_many_in_parallel() {
+    use graph_store_postgres::layout_for_tests::deployment_store::set_find_many_parallel_threshold;
+
+    run_test(|store, writable, deployment| async move {
+        let person = EntityOperation::Set {
+            key: EntityKey::data(
+                TEST_SUBGRAPH_ID.clone(),
+                "Person".to_owned(),
+                "p1".to_owned(),
+            ),
+            data: entity! { id: "p1", name: "Plato" },
+        };
+        transact_and_wait(
+            &store.subgraph_store(),
+            &deployment,
+            TEST_BLOCK_3_PTR.clone(),
+            vec![person],
+        )
+        .await
+        .unwrap();
+
+        let user_type = EntityType::from(USER);
+        let person_type = EntityType::from("Person");
+        let ids_for_type = || {
+            BTreeMap::from([
+                (&user_type, vec!["1", "3", "nobody"]),
+                (&person_type, vec!["p1"]),
+            ])
+        };
+
+        set_find_many_parallel_threshold(&deployment, Some(0));
+        let sequential = writable.get_many(ids_for_type()).unwrap();
+        set_find_many_parallel_threshold(&deployment, Some(1));
+        let parallel = writable.get_many(ids_for_type());
+        set_find_many_parallel_threshold(&deployment, None);
+        let parallel = parallel.unwrap();
+
+        assert_eq!(sequential, parallel);
+        let ids = |entity_type: &EntityType| {
+            let mut ids: Vec<_> = parallel[entity_type]
+                .iter()
+                .map(|entity| entity.id().unwrap())
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(vec!["1", "3"], ids(&user_type));
+        assert_eq!(vec!["p1"], ids(&person_type));
+    })
+}
+
+#[test]
+fn cancel_running_query() {
+    const LOCK_DURATION: Duration = Duration::from_secs(5);
+
+    run_test(|store, _, deployment| async move {
+        let query_store = store
+            .query_store(deployment.hash.clone().into(), false)
+            .await
+            .unwrap();
+
+        let guard = CancelGuard::new();
+        let query = user_query().cancel_on(guard.handle());
+
+        // With the table locked, the query blocks until we cancel it
+        let lock = lock_table(&deployment.hash, "user", LOCK_DURATION);
+        let canceler = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            guard.cancel();
+        });
+
+        let start = std::time::Instant::now();
+        let res = query_store.find_query_values(query);
+        let elapsed = start.elapsed();
+
+        canceler.join().unwrap();
+        lock.join().unwrap();
+
+        assert!(matches!(res, Err(QueryExecutionError::Cancelled)));
+        assert!(elapsed < LOCK_DURATION / 2);
+    })
+}
@@ -14,11 +14,11 @@ use graph::{
     prelude::SubgraphVersionSwitchingMode,
     prelude::UnfailOutcome,
     prelude::{futures03, StoreEvent},
-    prelude::{CheapClone, DeploymentHash, NodeId, SubgraphStore as _},
+    prelude::{CheapClone, DeploymentHash, NodeId, StoreError, SubgraphStore as _, ENV_VARS},
     semver::Version,
 };
 use graph_store_postgres::layout_for_tests::Connection as Primary;
-use graph_store_postgres::SubgraphStore;
+use graph_store_postgres::{SubgraphStore, PRIMARY_SHARD};
 
 use std::{collections::HashSet, marker::PhantomData, sync::Arc};
 use test_store::*;
@@ -51,6 +51,40 @@ fn get_version_info(store: &Store, subgraph_name: &str) -> VersionInfo {
     store.version_info(&current).unwrap()
 }
 
+/// Create a deployment `id` that is grafted onto `base` at the genesis
+/// block, but do not start it so that it has not copied any data from
+/// `base` yet
+fn create_grafted_deployment(
+    store: &SubgraphStore,
+    id: &str,
+    base: &DeploymentHash,
+) -> Result<DeploymentLocator, StoreError> {
+    let id = DeploymentHash::new(id).unwrap();
+    let schema = Schema::parse(SUBGRAPH_GQL, id.clone()).unwrap();
+    let manifest = SubgraphManifest::<graph_chain_ethereum::Chain> {
+        id: id.clone(),
+        spec_version: Version::new(1, 0, 0),
+        features: Default::default(),
+        description: None,
+        repository: None,
+        schema: schema.clone(),
+        data_sources: vec![],
+        graft: None,
+        templates: vec![],
+        chain: PhantomData,
+    };
+    let deployment =
+        DeploymentCreate::new(&manifest, None).graft(Some((base.clone(), GENESIS_PTR.clone())));
+    store.create_deployment_replace(
+        SubgraphName::new(id.as_str()).unwrap(),
+        &schema,
+        deployment,
+        NODE_ID.clone(),
+        NETWORK_NAME.to_string(),
+        SubgraphVersionSwitchingMode::Instant,
+    )
+}
+
 #[test]
 fn reassign_subgraph() {
     async fn setup() -> DeploymentLocator {
@@ -280,6 +314,24 @@ fn create_subgraph() {
         let (current, pending) = subgraph_deployments(&primary);
         assert_eq!(Some(ID2), current.as_deref());
         assert_eq!(Some(ID3), pending.as_deref());
+        let name = SubgraphName::new(SUBGRAPH_NAME.to_string()).unwrap();
+        let pending = store.pending_deployment(&name).unwrap();
+        assert_eq!(Some(ID3), pending.as_ref().map(|id| id.as_str()));
+
+        // The version history lists versions in the order they were
+        // created, with the synced version current and the new one pending
+        let history = store.subgraph_versions(&name).unwrap();
+        let history: Vec<_> = history
+            .iter()
+            .map(|(_, id, current, pending)| (id.as_str(), *current, *pending))
+            .collect();
+        assert_eq!(
+            &[(ID2, true, false), (ID3, false, true)],
+            &history[history.len() - 2..]
+        );
+        assert!(history[..history.len() - 2]
+            .iter()
+            .all(|(_, current, pending)| !current && !pending));
 
         // Deploying that same thing again changes nothing
         let (deployment3_again, events) = deploy(store.as_ref(), ID3, MODE);
@@ -315,6 +367,7 @@ fn create_subgraph() {
         let (current, pending) = subgraph_deployments(&primary);
         assert_eq!(Some(ID3), current.as_deref());
         assert_eq!(None, pending.as_deref());
+        assert_eq!(None, store.pending_deployment(&name).unwrap());
     })
 }
 
@@ -467,6 +520,374 @@ fn version_info() {
         assert_eq!(&*NETWORK_NAME, vi.network.as_str());
         // We set the head for the network to null in the test framework
         assert_eq!(None, vi.total_ethereum_blocks_count);
+
+        let metadata = store
+            .subgraph_store()
+            .manifest_metadata(&deployment.hash)
+            .unwrap();
+        assert_eq!("1.0.0", metadata.spec_version);
+        assert_eq!(vi.description, metadata.description);
+        assert_eq!(vi.repository, metadata.repository);
+    })
+}
+
+#[test]
+fn list_detailed() {
+    const NAME: &str = "listDetailedSubgraph";
+    const NO_VERSION: &str = "listDetailedNoVersion";
+
+    async fn setup() -> DeploymentLocator {
+        let id = DeploymentHash::new(NAME).unwrap();
+        remove_subgraphs();
+        block_store::set_chain(vec![], NETWORK_NAME);
+        create_test_subgraph(&id, SUBGRAPH_GQL).await
+    }
+
+    run_test_sequentially(|store| async move {
+        let deployment = setup().await;
+        let subgraph_store = store.subgraph_store();
+        subgraph_store
+            .create_subgraph(SubgraphName::new(NO_VERSION).unwrap())
+            .unwrap();
+
+        transact_and_wait(&subgraph_store, &deployment, BLOCK_ONE.clone(), vec![])
+            .await
+            .unwrap();
+
+        // Subgraphs without a current version are not listed
+        let list = subgraph_store.list_detailed().unwrap();
+        assert_eq!(1, list.len());
+        let (name, id, state) = list.into_iter().next().unwrap();
+        assert_eq!(NAME, name.as_str());
+        assert_eq!(deployment.hash, id);
+        let state = state.expect("the deployment state can be loaded");
+        assert_eq!(deployment.hash, state.id);
+        assert_eq!(1, state.latest_ethereum_block_number);
+    })
+}
+
+#[test]
+fn deployment_states() {
+    const NAME: &str = "statesSubgraph";
+    const OTHER: &str = "otherStatesSubgraph";
+
+    run_test_sequentially(|store| async move {
+        remove_subgraphs();
+        block_store::set_chain(vec![], NETWORK_NAME);
+        let subgraph_store = store.subgraph_store();
+        let id = DeploymentHash::new(NAME).unwrap();
+        let other = DeploymentHash::new(OTHER).unwrap();
+        let deployment = create_test_subgraph(&id, SUBGRAPH_GQL).await;
+        let other_deployment = create_test_subgraph(&other, SUBGRAPH_GQL).await;
+
+        transact_and_wait(&subgraph_store, &deployment, BLOCK_ONE.clone(), vec![])
+            .await
+            .unwrap();
+        transact_and_wait(
+            &subgraph_store,
+            &other_deployment,
+            GENESIS_PTR.clone(),
+            vec![],
+        )
+        .await
+        .unwrap();
+
+        // Deployments that do not exist are left out
+        let mut states = subgraph_store
+            .deployment_states(&[
+                id.clone(),
+                DeploymentHash::new("notASubgraph").unwrap(),
+                other.clone(),
+            ])
+            .unwrap();
+        states.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(2, states.len());
+
+        assert_eq!(other, states[0].id);
+        assert_eq!(0, states[0].latest_ethereum_block_number);
+        assert_eq!(id, states[1].id);
+        assert_eq!(1, states[1].latest_ethereum_block_number);
+        assert_eq!(0, states[1].reorg_count);
+
+        // The states match what we get when we load them one by one
+        for state in &states {
+            let single = deployment_state(&store, &state.id).await;
+            assert_eq!(
+                single.latest_ethereum_block_number,
+                state.latest_ethereum_block_number
+            );
+            assert_eq!(single.reorg_count, state.reorg_count);
+            assert_eq!(single.max_reorg_depth, state.max_reorg_depth);
+        }
+
+        assert!(subgraph_store.deployment_states(&[]).unwrap().is_empty());
+    })
+}
+
+#[test]
+fn synced_and_syncing_deployments() {
+    const SYNCED: &str = "syncedSubgraph";
+    const SYNCING: &str = "syncingSubgraph";
+
+    run_test_sequentially(|store| async move {
+        remove_subgraphs();
+        let subgraph_store = store.subgraph_store();
+        let synced = DeploymentHash::new(SYNCED).unwrap();
+        let syncing = DeploymentHash::new(SYNCING).unwrap();
+        let deployment = create_test_subgraph(&synced, SUBGRAPH_GQL).await;
+        create_test_subgraph(&syncing, SUBGRAPH_GQL).await;
+
+        assert!(subgraph_store.synced_deployments().unwrap().is_empty());
+        let mut ids = subgraph_store.syncing_deployments().unwrap();
+        ids.sort();
+        assert_eq!(vec![synced.clone(), syncing.clone()], ids);
+
+        subgraph_store
+            .cheap_clone()
+            .writable(LOGGER.clone(), deployment.id)
+            .await
+            .expect("can get writable")
+            .deployment_synced()
+            .unwrap();
+
+        assert_eq!(vec![synced], subgraph_store.synced_deployments().unwrap());
+        assert_eq!(vec![syncing], subgraph_store.syncing_deployments().unwrap());
+    })
+}
+
+#[test]
+fn truncate() {
+    const NAME: &str = "truncateSubgraph";
+    const GRAFT: &str = "truncateGraft";
+
+    run_test_sequentially(|store| async move {
+        remove_subgraphs();
+        let subgraph_store = store.subgraph_store();
+        let id = DeploymentHash::new(NAME).unwrap();
+        let deployment = create_test_subgraph(&id, SUBGRAPH_GQL).await;
+        transact_and_wait(&subgraph_store, &deployment, BLOCK_ONE.clone(), vec![])
+            .await
+            .unwrap();
+        let writable = subgraph_store
+            .cheap_clone()
+            .writable(LOGGER.clone(), deployment.id)
+            .await
+            .expect("can get writable");
+        writable.deployment_synced().unwrap();
+
+        // A deployment that might be running can not be truncated
+        let err = subgraph_store
+            .truncate(&id)
+            .expect_err("can not truncate an assigned deployment");
+        assert!(err.to_string().contains("assigned"));
+
+        writable.unassign_subgraph().unwrap();
+        subgraph_store.truncate(&id).unwrap();
+        assert!(subgraph_store.synced_deployments().unwrap().is_empty());
+        assert_eq!(
+            vec![id.clone()],
+            subgraph_store.syncing_deployments().unwrap()
+        );
+
+        // Grafted deployments would need their data copied again
+        let graft = create_grafted_deployment(&subgraph_store, GRAFT, &id).unwrap();
+        subgraph_store
+            .cheap_clone()
+            .writable(LOGGER.clone(), graft.id)
+            .await
+            .expect("can get writable")
+            .unassign_subgraph()
+            .unwrap();
+        let err = subgraph_store
+            .truncate(&graft.hash)
+            .expect_err("can not truncate a grafted deployment");
+        assert!(err.to_string().contains("grafted"));
+    })
+}
+
+#[test]
+fn graft_dependents() {
+    const BASE: &str = "graftDependentsBase";
+    const GRAFT: &str = "graftDependentsGraft";
+
+    run_test_sequentially(|store| async move {
+        remove_subgraphs();
+        let subgraph_store = store.subgraph_store();
+        let base_id = DeploymentHash::new(BASE).unwrap();
+        let base = create_test_subgraph(&base_id, SUBGRAPH_GQL).await;
+        transact_and_wait(&subgraph_store, &base, GENESIS_PTR.clone(), vec![])
+            .await
+            .unwrap();
+        assert!(subgraph_store
+            .graft_dependents(&base_id)
+            .unwrap()
+            .is_empty());
+
+        let graft = create_grafted_deployment(&subgraph_store, GRAFT, &base_id).unwrap();
+        assert_eq!(
+            vec![graft.hash.clone()],
+            subgraph_store.graft_dependents(&base_id).unwrap()
+        );
+
+        // The graft still needs the data from the base
+        let err = subgraph_store
+            .remove_deployment(base.id)
+            .expect_err("can not remove a base with pending grafts");
+        assert!(err.to_string().contains(GRAFT));
+
+        subgraph_store
+            .cheap_clone()
+            .writable(LOGGER.clone(), graft.id)
+            .await
+            .expect("can get writable")
+            .start_subgraph_deployment(&*LOGGER)
+            .await
+            .unwrap();
+
+        // Once the graft has copied the data, it is still a dependent but
+        // no longer stops the base from being removed
+        assert_eq!(
+            vec![graft.hash],
+            subgraph_store.graft_dependents(&base_id).unwrap()
+        );
+        subgraph_store.remove_deployment(base.id).unwrap();
+    })
+}
+
+#[test]
+fn max_graft_depth() {
+    run_test_sequentially(|store| async move {
+        remove_subgraphs();
+        let subgraph_store = store.subgraph_store();
+        let max_depth = ENV_VARS.store.max_graft_depth;
+        let name = |i: usize| format!("graftDepth{}", i);
+
+        // Build a chain where each deployment is grafted onto the previous
+        // one, with as many grafts as we allow
+        let root = DeploymentHash::new(name(0)).unwrap();
+        create_test_subgraph(&root, SUBGRAPH_GQL).await;
+        let mut base = root.clone();
+        for i in 1..=max_depth {
+            base = create_grafted_deployment(&subgraph_store, &name(i), &base)
+                .unwrap()
+                .hash;
+        }
+
+        let chain = subgraph_store.graft_chain(&base).unwrap();
+        assert_eq!(max_depth + 1, chain.len());
+        assert_eq!(&base, chain.first().unwrap());
+        assert_eq!(&root, chain.last().unwrap());
+
+        // One more graft makes the chain too long
+        let err = create_grafted_deployment(&subgraph_store, &name(max_depth + 1), &base)
+            .expect_err("the graft chain is too long");
+        assert!(err.to_string().contains("GRAPH_MAX_GRAFT_DEPTH"));
+
+        // Grafting onto a deployment further up the chain is fine
+        let shorter = DeploymentHash::new(name(max_depth - 1)).unwrap();
+        create_grafted_deployment(&subgraph_store, &name(max_depth + 1), &shorter).unwrap();
+    })
+}
+
+#[test]
+fn create_subgraph_versions() {
+    const FIRST: &str = "createVersionsFirst";
+    const SECOND: &str = "createVersionsSecond";
+    const THIRD: &str = "createVersionsThird";
+
+    run_test_sequentially(|store| async move {
+        remove_subgraphs();
+        let subgraph_store = store.subgraph_store();
+        let primary = primary_connection();
+        let name = |name: &str| SubgraphName::new(name).unwrap();
+        let site_for = |id: &str| {
+            let id = DeploymentHash::new(id).unwrap();
+            primary
+                .allocate_site(PRIMARY_SHARD.clone(), &id, NETWORK_NAME.to_string())
+                .unwrap()
+        };
+        let first = site_for(FIRST);
+        let second = site_for(SECOND);
+        let third = site_for(THIRD);
+
+        let changes = primary
+            .create_subgraph_versions(
+                &*LOGGER,
+                vec![
+                    (name(FIRST), &first, NODE_ID.clone()),
+                    (name(SECOND), &second, NODE_ID.clone()),
+                ],
+                SubgraphVersionSwitchingMode::Instant,
+                |_| Ok(false),
+            )
+            .unwrap();
+        assert_eq!(2, changes.len());
+        for id in &[FIRST, SECOND] {
+            let (current, pending) = primary.versions_for_subgraph(id).unwrap();
+            let current = current.expect("the subgraph has a current version");
+            assert_eq!(
+                Some(id.to_string()),
+                primary.deployment_for_version(&current).unwrap()
+            );
+            assert_eq!(None, pending);
+        }
+
+        // If creating one of the versions fails, none of them are created
+        let res = primary.create_subgraph_versions(
+            &*LOGGER,
+            vec![
+                (name(THIRD), &third, NODE_ID.clone()),
+                (name(FIRST), &third, NODE_ID.clone()),
+            ],
+            SubgraphVersionSwitchingMode::Instant,
+            |_| Err(StoreError::Unknown(anyhow::anyhow!("synced check failed"))),
+        );
+        assert!(res.is_err());
+        assert!(!subgraph_store.subgraph_exists(&name(THIRD)).unwrap());
+        let (current, _) = primary.versions_for_subgraph(FIRST).unwrap();
+        assert_eq!(
+            Some(FIRST.to_string()),
+            primary.deployment_for_version(&current.unwrap()).unwrap()
+        );
+    })
+}
+
+#[test]
+fn remove_subgraph_if_exists() {
+    const NAME: &str = "removeIfExistsSubgraph";
+
+    run_test_sequentially(|store| async move {
+        remove_subgraphs();
+        let subgraph_store = store.subgraph_store();
+        let name = SubgraphName::new(NAME).unwrap();
+
+        subgraph_store.create_subgraph(name.clone()).unwrap();
+        assert!(subgraph_store
+            .remove_subgraph_if_exists(name.clone())
+            .unwrap());
+        assert!(!subgraph_store.subgraph_exists(&name).unwrap());
+
+        // Removing a subgraph that is already gone is not an error
+        assert!(!subgraph_store.remove_subgraph_if_exists(name).unwrap());
+    })
+}
+
+#[test]
+fn remove_subgraph_by_id() {
+    const NAME: &str = "removeByIdSubgraph";
+
+    run_test_sequentially(|store| async move {
+        remove_subgraphs();
+        let subgraph_store = store.subgraph_store();
+        let name = SubgraphName::new(NAME).unwrap();
+
+        let id = subgraph_store.create_subgraph(name.clone()).unwrap();
+        assert!(subgraph_store.subgraph_exists(&name).unwrap());
+        subgraph_store.remove_subgraph_by_id(&id).unwrap();
+        assert!(!subgraph_store.subgraph_exists(&name).unwrap());
+
+        // Removing a subgraph that is already gone is not an error
+        subgraph_store.remove_subgraph_by_id(&id).unwrap();
     })
 }
 
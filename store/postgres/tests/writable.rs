@@ -1,10 +1,14 @@
 use graph::data::subgraph::schema::DeploymentCreate;
 use lazy_static::lazy_static;
 use std::marker::PhantomData;
+use std::time::Duration;
 use test_store::*;
 
-use graph::components::store::{DeploymentLocator, WritableStore};
+use graph::components::store::{
+    DeploymentLocator, EntityCache, StatusStore, StoredDynamicDataSource, WritableStore, WriteBatch,
+};
 use graph::components::store::{EntityKey, EntityType};
+use graph::data::subgraph::schema::SubgraphError;
 use graph::data::subgraph::*;
 use graph::prelude::*;
 use graph::semver::Version;
@@ -125,11 +129,11 @@ async fn insert_count(store: &Arc<DieselSubgraphStore>, deployment: &DeploymentL
 
 async fn pause_writer(deployment: &DeploymentLocator) {
     flush(&deployment).await.unwrap();
-    writable::allow_steps(0).await;
+    writable::allow_steps(deployment, 0).await;
 }
 
 async fn resume_writer(deployment: &DeploymentLocator, steps: usize) {
-    writable::allow_steps(steps).await;
+    writable::allow_steps(deployment, steps).await;
     flush(&deployment).await.unwrap();
 }
 
@@ -165,3 +169,137 @@ fn tracker() {
         assert_eq!(2, read_count());
     })
 }
+
+/// Write block `ptr` through `writable`, setting the counter to `count`
+/// and adding a data source and a non-fatal error named after `count`
+async fn write_block(
+    writable: &Arc<dyn WritableStore>,
+    deployment: &DeploymentLocator,
+    ptr: BlockPtr,
+    count: i32,
+) {
+    let mut cache = EntityCache::new(writable.clone());
+    cache.append(vec![EntityOperation::Set {
+        key: count_key(deployment, "1"),
+        data: entity! { id: "1", count: count },
+    }]);
+    let mods = cache.as_modifications().unwrap().modifications;
+    let data_source = StoredDynamicDataSource {
+        name: format!("ds{}", count),
+        source: Source {
+            address: None,
+            abi: "Counter".to_string(),
+            start_block: ptr.number,
+        },
+        context: None,
+        creation_block: Some(ptr.number),
+    };
+    let error = SubgraphError {
+        subgraph_id: deployment.hash.clone(),
+        message: format!("error{}", count),
+        block_ptr: Some(ptr.clone()),
+        handler: None,
+        deterministic: true,
+    };
+    let stopwatch = StopwatchMetrics::new(
+        LOGGER.clone(),
+        deployment.hash.clone(),
+        "transact",
+        METRICS_REGISTRY.clone(),
+    );
+    writable
+        .transact_block_operations(ptr, None, mods, &stopwatch, vec![data_source], vec![error])
+        .await
+        .unwrap();
+}
+
+/// Wait until the block pointer of `deployment` in the database is `ptr`
+async fn wait_for_block_ptr(
+    store: &Arc<DieselSubgraphStore>,
+    deployment: &DeploymentLocator,
+    ptr: &BlockPtr,
+) {
+    for _ in 0..500 {
+        let current = store.least_block_ptr(&deployment.hash).await.unwrap();
+        if current.as_ref() == Some(ptr) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("the subgraph never reached block {}", ptr);
+}
+
+#[test]
+fn write_batch() {
+    run_test(|store, writable, deployment| async move {
+        let subgraph_store = store.subgraph_store();
+
+        writable.set_write_batch(WriteBatch {
+            size: 10,
+            duration: Duration::from_secs(60),
+        });
+        insert_count(&subgraph_store, &deployment, 1).await;
+        pause_writer(&deployment).await;
+
+        // Queue three blocks, a revert, and another block
+        for number in 2..5 {
+            write_block(&writable, &deployment, block_pointer(number), number as i32).await;
+        }
+        writable
+            .revert_block_operations(block_pointer(3), None)
+            .await
+            .unwrap();
+        let block_4 = BlockPtr::from((H256::from([44; 32]), 4));
+        write_block(&writable, &deployment, block_4.clone(), 44).await;
+
+        // The writer writes the three blocks in one step, and the revert
+        // ends that batch
+        writable::allow_steps(&deployment, 1).await;
+        wait_for_block_ptr(&subgraph_store, &deployment, &block_pointer(4)).await;
+
+        let infos = store
+            .status(status::Filter::Deployments(vec![deployment
+                .hash
+                .to_string()]))
+            .unwrap();
+        let mut errors: Vec<_> = infos[0]
+            .non_fatal_errors
+            .iter()
+            .map(|error| {
+                (
+                    error.block_ptr.as_ref().unwrap().number,
+                    error.message.clone(),
+                )
+            })
+            .collect();
+        errors.sort();
+        assert_eq!(
+            vec![
+                (2, "error2".to_string()),
+                (3, "error3".to_string()),
+                (4, "error4".to_string())
+            ],
+            errors
+        );
+
+        // Write the revert and the last block
+        resume_writer(&deployment, 2).await;
+        assert_eq!(
+            Some(block_4),
+            subgraph_store
+                .least_block_ptr(&deployment.hash)
+                .await
+                .unwrap()
+        );
+        let counter = writable.get(&count_key(&deployment, "1")).unwrap().unwrap();
+        assert_eq!(Some(&Value::Int(44)), counter.get("count"));
+        let names: Vec<_> = writable
+            .load_dynamic_data_sources()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|ds| ds.name)
+            .collect();
+        assert_eq!(vec!["ds2", "ds3", "ds44"], names);
+    })
+}
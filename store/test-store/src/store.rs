@@ -326,6 +326,53 @@ pub fn insert_ens_name(hash: &str, name: &str) {
         .unwrap();
 }
 
+/// Overwrite the entity count stored in the metadata for `deployment`
+/// without touching any entities, as if the count had drifted
+pub fn set_stored_entity_count(deployment: &DeploymentHash, count: i64) {
+    use diesel::prelude::*;
+    use diesel::sql_types::{BigInt, Text};
+
+    let conn = PRIMARY_POOL.get().unwrap();
+
+    diesel::sql_query(
+        "update subgraphs.subgraph_deployment set entity_count = $1 where deployment = $2",
+    )
+    .bind::<BigInt, _>(count)
+    .bind::<Text, _>(deployment.as_str())
+    .execute(&conn)
+    .unwrap();
+}
+
+/// Lock the table `table` of `deployment` from another connection for
+/// `duration` so that queries against it block. Returns once the lock is
+/// held; joining the returned handle waits for the lock to be released
+pub fn lock_table(
+    deployment: &DeploymentHash,
+    table: &str,
+    duration: Duration,
+) -> std::thread::JoinHandle<()> {
+    use diesel::connection::SimpleConnection;
+
+    let site = primary_mirror()
+        .find_active_site(deployment)
+        .unwrap()
+        .expect("the deployment exists");
+    let sql = format!(
+        "begin; lock table {}.\"{}\" in access exclusive mode",
+        site.namespace, table
+    );
+    let (locked_sender, locked_receiver) = std::sync::mpsc::channel();
+    let handle = std::thread::spawn(move || {
+        let conn = PRIMARY_POOL.get().unwrap();
+        conn.batch_execute(&sql).unwrap();
+        locked_sender.send(()).unwrap();
+        std::thread::sleep(duration);
+        conn.batch_execute("rollback").unwrap();
+    });
+    locked_receiver.recv().unwrap();
+    handle
+}
+
 /// Insert the given entities and wait until all writes have been processed.
 /// The inserts all happen at `GENESIS_PTR`, i.e., block 0
 pub async fn insert_entities(
@@ -16,7 +16,7 @@ use graph::blockchain::{
     TriggersAdapter, TriggersAdapterSelector,
 };
 use graph::cheap_clone::CheapClone;
-use graph::components::store::{BlockStore, DeploymentId, DeploymentLocator};
+use graph::components::store::{BlockStore, DeploymentId, DeploymentLocator, WriteBatch};
 use graph::env::{EnvVars, ENV_VARS};
 use graph::firehose::{FirehoseEndpoint, FirehoseEndpoints};
 use graph::ipfs_client::IpfsClient;
@@ -232,6 +232,8 @@ async fn setup(
         mock_registry.clone(),
         link_resolver.cheap_clone(),
         static_filters,
+        ENV_VARS.max_concurrent_hosts,
+        WriteBatch::default(),
     );
 
     // Create IPFS-based subgraph provider